@@ -1,6 +1,6 @@
 use clap::{Parser, Subcommand};
 use beads_core::{Store, SqliteStore, Issue, StdFileSystem};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
 use std::io::Write;
@@ -12,27 +12,96 @@ use anyhow::Context;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Don't write changes to .beads/issues.jsonl when the command finishes
+    #[arg(long, global = true)]
+    no_auto_flush: bool,
+    /// Don't load .beads/issues.jsonl when it changed since the last import or export
+    #[arg(long, global = true)]
+    no_auto_import: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     List {
-        #[arg(long)]
-        status: Option<String>,
+        /// Only these statuses (repeat or comma-separate)
+        #[arg(long, value_delimiter = ',')]
+        status: Vec<String>,
+        /// Skip these statuses, e.g. --not-status closed
+        #[arg(long, value_delimiter = ',')]
+        not_status: Vec<String>,
+        /// Assignee, or "unassigned"
         #[arg(long)]
         assignee: Option<String>,
-        #[arg(long)]
+        #[arg(long, value_parser = parse_priority)]
         priority: Option<i32>,
+        #[arg(long, value_parser = parse_priority)]
+        priority_min: Option<i32>,
+        #[arg(long, value_parser = parse_priority)]
+        priority_max: Option<i32>,
         #[arg(long = "type")]
         type_: Option<String>,
+        /// Require all of these labels
+        #[arg(long, value_delimiter = ',')]
+        label: Vec<String>,
+        /// Require at least one of these labels
+        #[arg(long, value_delimiter = ',')]
+        label_any: Vec<String>,
+        /// Dates are YYYY-MM-DD or RFC 3339
+        #[arg(long, value_parser = parse_date)]
+        created_after: Option<DateTime<Utc>>,
+        #[arg(long, value_parser = parse_date)]
+        created_before: Option<DateTime<Utc>>,
+        #[arg(long, value_parser = parse_date)]
+        updated_after: Option<DateTime<Utc>>,
+        #[arg(long, value_parser = parse_date)]
+        updated_before: Option<DateTime<Utc>>,
+        #[arg(long, value_parser = parse_date)]
+        closed_after: Option<DateTime<Utc>>,
+        #[arg(long, value_parser = parse_date)]
+        closed_before: Option<DateTime<Utc>>,
         #[arg(long)]
-        label: Option<String>,
+        id_prefix: Option<String>,
+        /// Case-insensitive text in the title or description
         #[arg(long)]
-        sort: Option<String>,
+        contains: Option<String>,
+        /// created, updated, closed, priority, id or title
+        #[arg(long, default_value = "created")]
+        sort: beads_core::filter::SortField,
+        #[arg(short, long)]
+        reverse: bool,
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
     },
     Show {
         id: String,
     },
+    /// Search issue IDs, text fields and comments
+    Search {
+        query: String,
+        #[arg(short, long)]
+        status: Option<String>,
+        #[arg(short, long)]
+        assignee: Option<String>,
+        #[arg(short = 't', long = "type")]
+        type_: Option<String>,
+        /// Require all of these labels
+        #[arg(short, long, action = clap::ArgAction::Append)]
+        label: Vec<String>,
+        /// Require at least one of these labels
+        #[arg(long, action = clap::ArgAction::Append)]
+        label_any: Vec<String>,
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+        /// Order by priority, created, updated, id or title instead of relevance
+        #[arg(long)]
+        sort: Option<String>,
+        #[arg(short, long)]
+        reverse: bool,
+        #[arg(long)]
+        long: bool,
+    },
     Update {
         id: String,
         #[arg(long)]
@@ -64,14 +133,42 @@ enum Commands {
     Close {
         id: String,
     },
+    Reopen {
+        #[arg(required = true)]
+        ids: Vec<String>,
+        #[arg(short, long, default_value = "")]
+        reason: String,
+    },
+    Delete {
+        #[arg(required = true)]
+        ids: Vec<String>,
+        #[arg(long, default_value = "manual delete")]
+        reason: String,
+        #[arg(short, long)]
+        force: bool,
+    },
+    Undelete {
+        id: String,
+    },
     Create {
         title: String,
         #[arg(short, long, default_value = "")]
         description: String,
-        #[arg(short = 't', long = "type", default_value = "bug")]
-        type_: String,
-        #[arg(short, long, default_value_t = 2)]
-        priority: i32,
+        /// Defaults to "bug", or the template's type
+        #[arg(short = 't', long = "type")]
+        type_: Option<String>,
+        /// Defaults to 2, or the template's priority
+        #[arg(short, long)]
+        priority: Option<i32>,
+        /// Labels to add (repeat or comma-separate)
+        #[arg(short, long, value_delimiter = ',')]
+        label: Vec<String>,
+        /// Create as the next hierarchical child of this issue (e.g. bd-a3f8.1)
+        #[arg(long)]
+        parent: Option<String>,
+        /// Pre-fill from a template (see `bd template list`), creating its child tasks too
+        #[arg(long)]
+        from_template: Option<String>,
     },
     Export {
         #[arg(short, long, default_value = ".beads/issues.jsonl")]
@@ -88,9 +185,20 @@ enum Commands {
         right: String,
         #[arg(long)]
         debug: bool,
+        /// Print what was decided for each issue; "json" is the only format
+        #[arg(long)]
+        report: Option<String>,
     },
     Onboard,
-    Ready,
+    Ready {
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+        #[arg(short, long)]
+        assignee: Option<String>,
+        #[arg(short, long)]
+        unassigned: bool,
+    },
+    Blocked,
     Sync {
         #[arg(long)]
         squash: bool,
@@ -98,10 +206,92 @@ enum Commands {
         dry_run: bool,
     },
     Stats,
+    /// Move every issue to a new ID prefix, rewriting all references to them
+    RenamePrefix {
+        /// Up to 8 lowercase letters, digits and hyphens, e.g. "kw"
+        new_prefix: String,
+        /// Only show what would be renamed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Summarize long-closed issues that no open work depends on
+    Compact {
+        /// Only list the issues that would be compacted
+        #[arg(long)]
+        dry_run: bool,
+        /// Compact every eligible issue
+        #[arg(long)]
+        all: bool,
+        /// Compact just this issue
+        #[arg(long, conflicts_with = "all")]
+        id: Option<String>,
+        /// 1: short summary, closed 30+ days; 2: one line, closed 90+ days
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(i32).range(1..=2))]
+        tier: i32,
+        /// With --id, skip the age and open-dependent checks
+        #[arg(long, requires = "id")]
+        force: bool,
+    },
+    /// Show the full original of a compacted issue from git history
+    Restore {
+        id: String,
+    },
+    /// Apply pending database schema migrations
+    Migrate {
+        /// Only list the migrations that would run
+        #[arg(long)]
+        dry_run: bool,
+    },
     Config {
         #[command(subcommand)]
         command: ConfigCommands,
     },
+    Dep {
+        #[command(subcommand)]
+        command: DepCommands,
+    },
+    /// Link two issues to each other as related
+    Relate {
+        a: String,
+        b: String,
+    },
+    /// Close an issue as a duplicate of another
+    Duplicate {
+        id: String,
+        #[arg(long)]
+        of: String,
+    },
+    /// Close an issue as replaced by a newer one
+    Supersede {
+        id: String,
+        #[arg(long)]
+        with: String,
+    },
+    /// Send and read messages between agents
+    Mail {
+        /// Act as this identity instead of $BEADS_IDENTITY, config identity or user.name
+        #[arg(long, global = true)]
+        identity: Option<String>,
+        #[command(subcommand)]
+        command: MailCommands,
+    },
+    /// List, show and create issue templates for `create --from-template`
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommands,
+    },
+    /// Track epics through their parent-child children
+    Epic {
+        #[command(subcommand)]
+        command: EpicCommands,
+    },
+    /// List the comments on an issue, or add one
+    #[command(args_conflicts_with_subcommands = true)]
+    Comments {
+        id: Option<String>,
+        #[command(subcommand)]
+        command: Option<CommentCommands>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -111,6 +301,123 @@ enum ConfigCommands {
     List,
 }
 
+#[derive(Subcommand)]
+enum DepCommands {
+    /// Make ISSUE_ID depend on DEPENDS_ON_ID
+    Add {
+        issue_id: String,
+        depends_on_id: String,
+        #[arg(short = 't', long = "type", default_value = beads_core::graph::DEP_BLOCKS)]
+        dep_type: String,
+    },
+    /// Remove every dependency of ISSUE_ID on DEPENDS_ON_ID
+    Remove {
+        issue_id: String,
+        depends_on_id: String,
+    },
+    /// List what an issue depends on and what depends on it
+    List { id: String },
+    /// Show the dependency tree around an issue
+    Tree {
+        id: String,
+        /// down (dependencies), up (dependents) or both
+        #[arg(long, default_value = "both")]
+        direction: String,
+        #[arg(short = 'd', long, default_value_t = 50)]
+        max_depth: usize,
+    },
+    /// Detect dependency cycles
+    Cycles,
+}
+
+#[derive(Subcommand)]
+enum TemplateCommands {
+    /// List the built-in templates and those in .beads/templates
+    List,
+    /// Show what a template fills in
+    Show {
+        name: String,
+    },
+    /// Write a template skeleton to .beads/templates/<name>.yaml to edit
+    Create {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum MailCommands {
+    /// Send a message to another agent
+    Send {
+        to: String,
+        #[arg(short, long)]
+        subject: String,
+        #[arg(short = 'm', long)]
+        body: String,
+        /// Send at priority 0
+        #[arg(long)]
+        urgent: bool,
+    },
+    /// List unread messages, most urgent first
+    Inbox {
+        /// Only messages from this sender
+        #[arg(long)]
+        from: Option<String>,
+        #[arg(long, value_parser = parse_priority)]
+        priority: Option<i32>,
+    },
+    /// Show a message without marking it read
+    Read {
+        id: String,
+    },
+    /// Mark messages read
+    Ack {
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Reply to the sender of a message
+    Reply {
+        id: String,
+        #[arg(short = 'm', long)]
+        body: String,
+        #[arg(long)]
+        urgent: bool,
+    },
+    /// Delete acknowledged messages
+    Cleanup {
+        /// Only list the messages that would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum EpicCommands {
+    /// Show how many children of each open epic are closed
+    Status {
+        /// Only epics whose children are all closed
+        #[arg(long)]
+        eligible_only: bool,
+    },
+    /// Close every epic whose children are all closed
+    CloseEligible {
+        /// Only list the epics that would be closed
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CommentCommands {
+    /// Add a comment; the text comes from the argument, --file, or stdin
+    Add {
+        id: String,
+        text: Option<String>,
+        /// Read the comment from a file ("-" for stdin)
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+    },
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct FrontmatterDependency {
     id: String,
@@ -120,7 +427,7 @@ struct FrontmatterDependency {
 }
 
 fn default_dep_type() -> String {
-    "blocking".to_string()
+    beads_core::graph::DEP_BLOCKS.to_string()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -171,11 +478,72 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
+    if let Commands::Migrate { dry_run } = cli.command {
+        return run_migrate(&db_path, dry_run);
+    }
+
     let mut store = SqliteStore::open(&db_path).map_err(|e| anyhow::anyhow!("Failed to open DB at {:?}: {}", db_path, e))?;
+    let mut auto_flush = !cli.no_auto_flush;
+
+    if !cli.no_auto_import {
+        let jsonl_path = db_path.with_file_name("issues.jsonl");
+        match beads_core::autoimport::auto_import(&mut store, &jsonl_path, &StdFileSystem) {
+            Ok(Some(imported)) => {
+                let stats = imported.stats;
+                if stats.created + stats.updated > 0 {
+                    eprintln!(
+                        "Auto-imported {} new and {} updated issue(s) from {}",
+                        stats.created, stats.updated, jsonl_path.display()
+                    );
+                }
+                if !imported.protected.is_empty() {
+                    eprintln!(
+                        "Kept unflushed local changes to {} over {}",
+                        imported.protected.join(", "), jsonl_path.display()
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                // Flushing now could overwrite whatever is wrong with the file
+                eprintln!("Warning: auto-import skipped: {:#}", e);
+                auto_flush = false;
+            }
+        }
+    }
 
     match cli.command {
-        Commands::List { status, assignee, priority, type_, label, sort } => {
-            let issues = store.list_issues(status.as_deref(), assignee.as_deref(), priority, type_.as_deref(), label.as_deref(), sort.as_deref())?;
+        Commands::List {
+            status, not_status, assignee, priority, priority_min, priority_max, type_, label, label_any,
+            created_after, created_before, updated_after, updated_before, closed_after, closed_before,
+            id_prefix, contains, sort, reverse, limit, offset,
+        } => {
+            // Messages only show up when asked for with --type message
+            let exclude_types = if type_.is_none() { vec![beads_core::TYPE_MESSAGE.to_string()] } else { Vec::new() };
+            let filter = beads_core::IssueFilter {
+                statuses: status,
+                exclude_statuses: not_status,
+                assignee,
+                issue_type: type_,
+                exclude_types,
+                labels: label,
+                labels_any: label_any,
+                priority_min: priority_min.or(priority),
+                priority_max: priority_max.or(priority),
+                created_after,
+                created_before,
+                updated_after,
+                updated_before,
+                closed_after,
+                closed_before,
+                id_prefix,
+                text: contains,
+                sort,
+                reverse,
+                limit,
+                offset,
+            };
+            let issues = store.list_issues(&filter)?;
 
             use comfy_table::{Table, Cell};
             use comfy_table::modifiers::UTF8_ROUND_CORNERS;
@@ -217,39 +585,66 @@ fn main() -> anyhow::Result<()> {
         }
         Commands::Show { id } => {
             if let Some(issue) = store.get_issue(&id)? {
-                println!("ID:          {}", issue.id);
-                println!("Title:       {}", issue.title);
-                println!("Status:      {}", issue.status);
-                println!("Priority:    {}", issue.priority);
-                println!("Type:        {}", issue.issue_type);
-                if let Some(assignee) = &issue.assignee {
-                    println!("Assignee:    {}", assignee);
-                }
-                println!("Created:     {}", issue.created_at);
-                println!("Updated:     {}", issue.updated_at);
-                println!("------------------------------------------------------------");
-                println!("{}", issue.description);
+                print_issue(&issue);
+                print_links(&store.issue_links(&issue.id)?);
+            } else {
+                eprintln!("Issue not found: {}", id);
+            }
+        }
+        Commands::Search { query, status, assignee, type_, label, label_any, limit, sort, reverse, long } => {
+            let filter = beads_core::IssueFilter {
+                statuses: status.into_iter().collect(),
+                assignee,
+                issue_type: type_,
+                labels: label,
+                labels_any: label_any,
+                limit: Some(limit),
+                ..Default::default()
+            };
+            let mut results = store.search(&query, &filter)?;
+
+            match sort.as_deref() {
+                None => {}
+                Some("priority") => results.sort_by_key(|r| r.issue.priority),
+                Some("created") => results.sort_by_key(|r| std::cmp::Reverse(r.issue.created_at)),
+                Some("updated") => results.sort_by_key(|r| std::cmp::Reverse(r.issue.updated_at)),
+                Some("id") => results.sort_by(|a, b| a.issue.id.cmp(&b.issue.id)),
+                Some("title") => results.sort_by(|a, b| a.issue.title.cmp(&b.issue.title)),
+                Some(other) => anyhow::bail!("invalid sort field '{}': use priority, created, updated, id or title", other),
+            }
+            if reverse {
+                results.reverse();
+            }
 
-                if !issue.labels.is_empty() {
-                    println!("\nLabels: {}", issue.labels.join(", "));
-                }
+            if results.is_empty() {
+                println!("No issues found matching '{}'", query);
+                return Ok(());
+            }
 
-                if !issue.dependencies.is_empty() {
-                    println!("\nDependencies:");
-                    for dep in issue.dependencies {
-                        println!("  {} ({})", dep.depends_on_id, dep.type_);
+            if long {
+                println!("\nFound {} issues matching '{}':\n", results.len(), query);
+            } else {
+                println!("Found {} issues matching '{}':", results.len(), query);
+            }
+            for result in results {
+                let issue = &result.issue;
+                if long {
+                    println!("{} [P{}] [{}] {}", issue.id, issue.priority, issue.issue_type, issue.status);
+                    println!("  {}", issue.title);
+                    if let Some(assignee) = issue.assignee.as_deref().filter(|a| !a.is_empty()) {
+                        println!("  Assignee: {}", assignee);
                     }
-                }
-
-                if !issue.comments.is_empty() {
-                    println!("\nComments:");
-                    for comment in issue.comments {
-                        println!("  {} at {}:", comment.author, comment.created_at);
-                        println!("    {}", comment.text);
+                    if !issue.labels.is_empty() {
+                        println!("  Labels: {}", issue.labels.join(", "));
                     }
+                    println!("  Match: {}", result.snippet);
+                    println!();
+                } else {
+                    let assignee = issue.assignee.as_deref().filter(|a| !a.is_empty()).map(|a| format!(" @{}", a)).unwrap_or_default();
+                    let labels = if issue.labels.is_empty() { String::new() } else { format!(" [{}]", issue.labels.join(", ")) };
+                    println!("{} [P{}] [{}] {}{}{} - {}", issue.id, issue.priority, issue.issue_type, issue.status, assignee, labels, issue.title);
+                    println!("    {}", result.snippet);
                 }
-            } else {
-                eprintln!("Issue not found: {}", id);
             }
         }
         Commands::Update {
@@ -309,13 +704,18 @@ fn main() -> anyhow::Result<()> {
                 }
 
                 // Handle Dependencies
+                let graph = if add_dependency.is_empty() {
+                    beads_core::DependencyGraph::default()
+                } else {
+                    store.dependency_graph()?
+                };
                 for dep_str in add_dependency {
                     // Format: "ID" or "ID:TYPE"
                     let parts: Vec<&str> = dep_str.splitn(2, ':').collect();
                     let (dep_id, dep_type) = if parts.len() == 2 {
                         (parts[0], parts[1])
                     } else {
-                        (parts[0], "blocking")
+                        (parts[0], beads_core::graph::DEP_BLOCKS)
                     };
 
                     // Check if exists
@@ -324,14 +724,19 @@ fn main() -> anyhow::Result<()> {
                         .iter()
                         .any(|d| d.depends_on_id == dep_id && d.type_ == dep_type)
                     {
+                        if store.get_issue(dep_id)?.is_none() {
+                            anyhow::bail!("dependency target {} not found", dep_id);
+                        }
                         use beads_core::models::Dependency;
-                        issue.dependencies.push(Dependency {
+                        let dep = Dependency {
                             issue_id: issue.id.clone(),
                             depends_on_id: dep_id.to_string(),
                             type_: dep_type.to_string(),
                             created_at: Utc::now(),
                             created_by: user_name.clone(),
-                        });
+                        };
+                        beads_core::graph::validate_new_dependency(&graph, &dep)?;
+                        issue.dependencies.push(dep);
                         updated = true;
                     }
                 }
@@ -360,6 +765,8 @@ fn main() -> anyhow::Result<()> {
         Commands::Edit { id } => {
             if let Some(mut issue) = store.get_issue(&id)? {
                 let user_name = store.get_config("user.name")?.unwrap_or_else(|| "unknown".to_string());
+                // Refuse to save over changes made while the editor was open
+                let read_hash = issue.content_hash.clone();
 
                 let frontmatter = IssueFrontmatter {
                     title: issue.title.clone(),
@@ -417,25 +824,31 @@ fn main() -> anyhow::Result<()> {
                         // Convert new_fm.dependencies (Vec<FrontmatterDependency>) to Vec<Dependency>
                         // We try to preserve existing metadata if possible.
                         let mut new_deps = Vec::new();
+                        let graph = store.dependency_graph()?;
                         for fd in new_fm.dependencies {
                             // Find existing
                             if let Some(existing) = issue.dependencies.iter().find(|d| d.depends_on_id == fd.id && d.type_ == fd.dep_type) {
                                 new_deps.push(existing.clone());
                             } else {
                                 // Create new
+                                if store.get_issue(&fd.id)?.is_none() {
+                                    anyhow::bail!("dependency target {} not found", fd.id);
+                                }
                                 use beads_core::models::Dependency;
-                                new_deps.push(Dependency {
+                                let dep = Dependency {
                                     issue_id: issue.id.clone(),
                                     depends_on_id: fd.id,
                                     type_: fd.dep_type,
                                     created_at: Utc::now(),
                                     created_by: user_name.clone(),
-                                });
+                                };
+                                beads_core::graph::validate_new_dependency(&graph, &dep)?;
+                                new_deps.push(dep);
                             }
                         }
                         issue.dependencies = new_deps;
 
-                        store.update_issue(&issue).context("Failed to update issue")?;
+                        store.update_issue_if_unchanged(&issue, &read_hash).context("Failed to update issue")?;
                         println!("Updated issue {}", issue.id);
                     } else {
                         eprintln!("Invalid format: missing frontmatter delimiters");
@@ -464,6 +877,76 @@ fn main() -> anyhow::Result<()> {
                 eprintln!("Issue not found: {}", id);
             }
         }
+        Commands::Reopen { ids, reason } => {
+            let user = store.get_config("user.name")?.unwrap_or_else(|| "unknown".to_string());
+            for id in &ids {
+                let id = resolve_id(&store, id)?;
+                store.reopen_issue(&id, &user, &reason).context(format!("Failed to reopen {}", id))?;
+                if reason.is_empty() {
+                    println!("Reopened issue {}", id);
+                } else {
+                    println!("Reopened issue {}: {}", id, reason);
+                }
+            }
+        }
+        Commands::Delete { ids, reason, force } => {
+            let mut resolved = Vec::new();
+            for id in &ids {
+                let id = resolve_id(&store, id)?;
+                if !resolved.contains(&id) {
+                    resolved.push(id);
+                }
+            }
+
+            if !force {
+                let graph = store.dependency_graph()?;
+                println!("\n⚠️  DELETE PREVIEW\n");
+                println!("Issues to delete:");
+                for id in &resolved {
+                    println!("  {}", describe_issue(&store, id)?);
+                }
+
+                let mut links = 0;
+                let mut orphaned = Vec::new();
+                for id in &resolved {
+                    links += graph.dependencies_of(id).len();
+                    for dep in graph.dependents_of(id) {
+                        if resolved.contains(&dep.issue_id) {
+                            continue;
+                        }
+                        links += 1;
+                        if !orphaned.contains(&dep.issue_id) {
+                            orphaned.push(dep.issue_id.clone());
+                        }
+                    }
+                }
+                if links > 0 {
+                    println!("\nDependency links to remove: {}", links);
+                }
+                if !orphaned.is_empty() {
+                    println!("\nIssues that depend on them and will lose those links:");
+                    for id in &orphaned {
+                        println!("  {}", describe_issue(&store, id)?);
+                    }
+                }
+                println!("\nTo proceed, run: bd delete {} --force\n", resolved.join(" "));
+                return Ok(());
+            }
+
+            let user = store.get_config("user.name")?.unwrap_or_else(|| "unknown".to_string());
+            for id in &resolved {
+                let graph = store.dependency_graph()?;
+                let links = graph.dependencies_of(id).len() + graph.dependents_of(id).len();
+                store.delete_issue(id, &user, &reason)?;
+                println!("✓ Deleted {}", id);
+                println!("  Removed {} dependency link(s)", links);
+            }
+        }
+        Commands::Undelete { id } => {
+            let id = resolve_id(&store, &id)?;
+            store.undelete_issue(&id).context(format!("Failed to undelete {}", id))?;
+            println!("Restored issue {}", id);
+        }
         Commands::Export { output } => {
             let fs = StdFileSystem;
             let output_path = std::path::Path::new(&output);
@@ -473,12 +956,21 @@ fn main() -> anyhow::Result<()> {
         Commands::Import { input } => {
             let fs = StdFileSystem;
             let input_path = std::path::Path::new(&input);
-            store.import_from_jsonl(input_path, &fs).context(format!("Failed to import issues from {}", input))?;
-            println!("Imported issues from {}", input);
+            let stats = store.import_from_jsonl(input_path, &fs).context(format!("Failed to import issues from {}", input))?;
+            println!(
+                "Imported issues from {}: {} created, {} updated, {} unchanged",
+                input, stats.created, stats.updated, stats.unchanged
+            );
         }
-        Commands::Merge { output, base, left, right, debug } => {
+        Commands::Merge { output, base, left, right, debug, report } => {
+            if let Some(format) = report.as_deref().filter(|f| *f != "json") {
+                anyhow::bail!("invalid report format '{}': use json", format);
+            }
             let fs = StdFileSystem;
-            beads_core::merge::merge3way(&output, &base, &left, &right, debug, &fs)?;
+            let merge = beads_core::merge::merge3way(&output, &base, &left, &right, debug, &fs)?;
+            if report.is_some() {
+                println!("{}", serde_json::to_string_pretty(&merge)?);
+            }
         }
         Commands::Onboard => {
             // Check git init
@@ -547,71 +1039,407 @@ fn main() -> anyhow::Result<()> {
 
             println!("Onboarding complete!");
         }
-        Commands::Ready => {
-            // Get current user
-            let user = store.get_config("user.name")?;
-            let assignee = user.as_deref().unwrap_or("unassigned");
-
-            // List issues not closed, assigned to user or unassigned (if user not set?)
-            // Requirement: "alias for listing open issues assigned to user or unassigned"
-            // If we have a user, we filter by that user.
-            // If we don't have a user, maybe list unassigned?
-
-            // Let's implement: Status != closed AND Assignee = <user>
-            // But list_issues currently filters via exact match or unassigned.
-            // Store::list_issues doesn't support "NOT closed". It supports "status = ?"
-            // So we might need to filter in memory or fetch "open", "in_progress" separately?
-            // "open" is default status. "closed" is closed.
-            // We usually want everything NOT closed.
-            // Since `list_issues` takes specific status, we can't easily say "not closed".
-            // Let's fetch all and filter in memory for now, or fetch by common open statuses.
-            // Given the limited "list_issues" SQL generation I wrote (AND logic), fetching all then filtering is safest without changing Store again.
-            // Wait, I can pass None for status (all) and filter in loop.
-
-            let all_issues = store.list_issues(None, None, None, None, None, None)?;
-
-            println!("Ready issues for {}:", assignee);
-            println!("{:<10} {:<10} {:<10} {}", "ID", "STATUS", "PRIORITY", "TITLE");
-            println!("{:-<60}", "");
-
-            for issue in all_issues {
-                if issue.status == "closed" {
-                    continue;
-                }
-
-                let matches_assignee = if let Some(a) = &issue.assignee {
-                    if let Some(u) = &user {
-                        a == u
+        Commands::Ready { limit, assignee, unassigned } => {
+            let mut issues = store.ready_issues()?;
+
+            if unassigned {
+                issues.retain(|i| i.assignee.as_deref().unwrap_or("").is_empty());
+            } else if let Some(a) = &assignee {
+                issues.retain(|i| i.assignee.as_deref() == Some(a.as_str()));
+            }
+            if limit > 0 {
+                issues.truncate(limit);
+            }
+
+            if issues.is_empty() {
+                let has_open = !store
+                    .list_issues(&beads_core::IssueFilter {
+                        statuses: vec!["open".to_string(), "in_progress".to_string()],
+                        exclude_types: vec![beads_core::TYPE_MESSAGE.to_string()],
+                        limit: Some(1),
+                        ..Default::default()
+                    })?
+                    .is_empty();
+                if has_open {
+                    println!("\nNo ready work found (all issues have blocking dependencies)\n");
+                } else {
+                    println!("\nNo open issues\n");
+                }
+                return Ok(());
+            }
+
+            println!("\nReady work ({} issues with no blockers):\n", issues.len());
+            for (i, issue) in issues.iter().enumerate() {
+                println!("{}. [P{}] {}: {}", i + 1, issue.priority, issue.id, issue.title);
+                if let Some(est) = issue.estimated_minutes {
+                    println!("   Estimate: {} min", est);
+                }
+                if let Some(a) = issue.assignee.as_deref().filter(|a| !a.is_empty()) {
+                    println!("   Assignee: {}", a);
+                }
+            }
+            println!();
+        }
+        Commands::Blocked => {
+            let blocked = store.blocked_issues()?;
+
+            if blocked.is_empty() {
+                println!("\nNo blocked issues\n");
+                return Ok(());
+            }
+
+            println!("\nBlocked issues ({}):\n", blocked.len());
+            for entry in blocked {
+                let issue = &entry.issue;
+                println!("[P{}] {}: {}", issue.priority, issue.id, issue.title);
+                if let Some(parent) = &entry.blocked_via_parent {
+                    println!("  Blocked via parent {}: {} ({})", parent.id, parent.title, parent.status);
+                } else if entry.blockers.is_empty() {
+                    println!("  Status is blocked (no open dependencies)");
+                } else {
+                    println!("  Blocked by {} open dependencies:", entry.blockers.len());
+                    for blocker in &entry.blockers {
+                        println!("    {}: {} ({})", blocker.id, blocker.title, blocker.status);
+                    }
+                }
+                println!();
+            }
+        }
+        Commands::RenamePrefix { new_prefix, dry_run } => {
+            let old_prefix = store.get_config("issue_id_prefix")?.unwrap_or_else(|| "bd".to_string());
+            let moves = store.rename_prefix(&old_prefix, &new_prefix, dry_run)?;
+            let new_prefix = beads_core::rename::validate_prefix(&new_prefix)?;
+            if dry_run {
+                println!("DRY RUN: Would rename {} issue(s) from prefix '{}' to '{}'", moves.len(), old_prefix, new_prefix);
+                for (old_id, new_id) in moves.iter().take(5) {
+                    println!("  {} -> {}", old_id, new_id);
+                }
+                if moves.len() > 5 {
+                    println!("  ... and {} more", moves.len() - 5);
+                }
+            } else {
+                // Old IDs only disappear from the JSONL with a full export
+                let jsonl_path = db_path.with_file_name("issues.jsonl");
+                store.export_to_jsonl(&jsonl_path, &StdFileSystem).context("Failed to re-export issues")?;
+                println!("Renamed {} issue(s) from prefix '{}' to '{}'", moves.len(), old_prefix, new_prefix);
+            }
+        }
+        Commands::Template { command } => {
+            use beads_core::template;
+            let dir = db_path.with_file_name("templates");
+            let print_summary = |t: &template::Template| {
+                println!("  {}", t.name);
+                println!("    Type: {}, Priority: P{}", t.issue_type, t.priority);
+                if !t.labels.is_empty() {
+                    println!("    Labels: {}", t.labels.join(", "));
+                }
+                if !t.children.is_empty() {
+                    println!("    Children: {}", t.children.len());
+                }
+            };
+            match command {
+                TemplateCommands::List => {
+                    let (builtin, custom): (Vec<_>, Vec<_>) = template::list(&dir, &StdFileSystem)?.into_iter().partition(|t| t.builtin);
+                    if !builtin.is_empty() {
+                        println!("Built-in Templates:");
+                        builtin.iter().for_each(print_summary);
+                        println!();
+                    }
+                    if !custom.is_empty() {
+                        println!("Custom Templates:");
+                        custom.iter().for_each(print_summary);
+                        println!();
+                    }
+                }
+                TemplateCommands::Show { name } => {
+                    let t = template::load(&dir, &name, &StdFileSystem)?;
+                    println!("Template: {}", t.name);
+                    if t.title != template::TITLE_PLACEHOLDER {
+                        println!("Title: {}", t.title);
+                    }
+                    println!("Type: {}", t.issue_type);
+                    println!("Priority: P{}", t.priority);
+                    if !t.labels.is_empty() {
+                        println!("Labels: {}", t.labels.join(", "));
+                    }
+                    println!("\nDescription:\n{}", t.description);
+                    if !t.design.is_empty() {
+                        println!("\nDesign:\n{}", t.design);
+                    }
+                    if !t.acceptance_criteria.is_empty() {
+                        println!("\nAcceptance Criteria:\n{}", t.acceptance_criteria);
+                    }
+                    if !t.children.is_empty() {
+                        println!("\nChildren:");
+                        for child in &t.children {
+                            println!("  - {} ({})", child.title, child.issue_type);
+                        }
+                    }
+                }
+                TemplateCommands::Create { name } => {
+                    template::validate_name(&name)?;
+                    if let Ok(existing) = template::load(&dir, &name, &StdFileSystem) {
+                        if !existing.builtin {
+                            anyhow::bail!("template '{}' already exists", name);
+                        }
+                    }
+                    std::fs::create_dir_all(&dir)?;
+                    let path = dir.join(format!("{}.yaml", name));
+                    std::fs::write(&path, template::Template::skeleton(&name).to_yaml()?)?;
+                    println!("✓ Created template: {}", path.display());
+                    println!("Edit the file to customize your template.");
+                }
+            }
+        }
+        Commands::Relate { a, b } => {
+            let a = resolve_id(&store, &a)?;
+            let b = resolve_id(&store, &b)?;
+            store.relate_issues(&a, &b)?;
+            println!("Linked {} <-> {}", a, b);
+        }
+        Commands::Duplicate { id, of } => {
+            let id = resolve_id(&store, &id)?;
+            let of = resolve_id(&store, &of)?;
+            store.mark_duplicate(&id, &of)?;
+            println!("Marked {} as duplicate of {} (closed)", id, of);
+        }
+        Commands::Supersede { id, with } => {
+            let id = resolve_id(&store, &id)?;
+            let with = resolve_id(&store, &with)?;
+            store.mark_superseded(&id, &with)?;
+            println!("Marked {} as superseded by {} (closed)", id, with);
+        }
+        Commands::Mail { identity, command } => {
+            use beads_core::mail;
+            let identity = mail::identity(&store, identity.as_deref())?;
+            match command {
+                MailCommands::Send { to, subject, body, urgent } => {
+                    let priority = if urgent { mail::PRIORITY_URGENT } else { mail::PRIORITY_NORMAL };
+                    let id = new_issue_id(&store, None, &subject, &body, &identity)?;
+                    store.create_issue(&mail::new_message(&id, &to, &identity, &subject, &body, priority, Utc::now()))?;
+                    println!("Message sent: {}", id);
+                    println!("  To: {}", to);
+                    println!("  Subject: {}", subject);
+                    if urgent {
+                        println!("  Priority: URGENT");
+                    }
+                }
+                MailCommands::Inbox { from, priority } => {
+                    let mut messages = mail::inbox(&store, &identity)?;
+                    messages.retain(|m| from.as_ref().is_none_or(|f| &m.sender == f) && priority.is_none_or(|p| m.priority == p));
+                    if messages.is_empty() {
+                        println!("No messages for {}", identity);
+                        return Ok(());
+                    }
+                    println!("Inbox for {} ({} messages):\n", identity, messages.len());
+                    let now = Utc::now();
+                    for message in &messages {
+                        let flag = match message.priority {
+                            0 => " [URGENT]",
+                            1 => " [HIGH]",
+                            _ => "",
+                        };
+                        let age = now - message.created_at;
+                        let age = if age.num_hours() < 1 {
+                            format!("{}m ago", age.num_minutes())
+                        } else if age.num_days() < 1 {
+                            format!("{}h ago", age.num_hours())
+                        } else {
+                            format!("{}d ago", age.num_days())
+                        };
+                        println!("  {}: {}{}", message.id, message.title, flag);
+                        println!("      From: {} ({})", message.sender, age);
+                        if !message.replies_to.is_empty() {
+                            println!("      Re: {}", message.replies_to);
+                        }
+                        println!();
+                    }
+                }
+                MailCommands::Read { id } => {
+                    let message = mail::get_message(&store, &resolve_id(&store, &id)?)?;
+                    let rule = "─".repeat(66);
+                    println!("{}", rule);
+                    println!("ID:      {}", message.id);
+                    println!("From:    {}", message.sender);
+                    println!("To:      {}", message.assignee.as_deref().unwrap_or(""));
+                    println!("Subject: {}", message.title);
+                    println!("Time:    {}", message.created_at.format("%Y-%m-%d %H:%M:%S"));
+                    if message.priority <= 1 {
+                        println!("Priority: P{}", message.priority);
+                    }
+                    if !message.replies_to.is_empty() {
+                        println!("Re:      {}", message.replies_to);
+                    }
+                    println!("Status:  {}", message.status);
+                    println!("{}", rule);
+                    println!();
+                    println!("{}", message.description);
+                }
+                MailCommands::Ack { ids } => {
+                    let mut failed = 0;
+                    for id in &ids {
+                        match resolve_id(&store, id).and_then(|id| mail::acknowledge(&store, &id, Utc::now()).map(|_| id)) {
+                            Ok(id) => println!("Acknowledged: {}", id),
+                            Err(e) => {
+                                eprintln!("Error: {:#}", e);
+                                failed += 1;
+                            }
+                        }
+                    }
+                    if failed == ids.len() {
+                        anyhow::bail!("failed to acknowledge any messages");
+                    }
+                }
+                MailCommands::Reply { id, body, urgent } => {
+                    let original = mail::get_message(&store, &resolve_id(&store, &id)?)?;
+                    let priority = if urgent { mail::PRIORITY_URGENT } else { mail::PRIORITY_NORMAL };
+                    let subject = mail::reply_subject(&original.title);
+                    let reply_id = new_issue_id(&store, None, &subject, &body, &identity)?;
+                    let reply = mail::new_reply(&reply_id, &original, &identity, &body, priority, Utc::now())?;
+                    store.create_issue(&reply)?;
+                    println!("Reply sent: {}", reply_id);
+                    println!("  To: {}", original.sender);
+                    println!("  Re: {}", original.id);
+                    if urgent {
+                        println!("  Priority: URGENT");
+                    }
+                }
+                MailCommands::Cleanup { dry_run } => {
+                    let ids = mail::cleanup(&store, &identity, dry_run)?;
+                    if ids.is_empty() {
+                        println!("No acknowledged messages to delete");
+                    } else if dry_run {
+                        println!("Would delete {} acknowledged message(s):", ids.len());
+                        for id in &ids {
+                            println!("  {}", id);
+                        }
                     } else {
-                         // No user configured.
-                         // Should we show unassigned? Or everything?
-                         // "bd ready" implies "ready for ME".
-                         // If I don't know who ME is, I can't filter by assignee efficiently.
-                         // Maybe just show unassigned?
-                         // Let's assume matches_assignee = true if user is None (show all open?) or false?
-                         // Go with: if user is known, match it. If not, match nothing?
-                         // Or maybe prompt user to configure user.name?
-                         false
+                        println!("Deleted {} acknowledged message(s)", ids.len());
+                    }
+                }
+            }
+        }
+        Commands::Epic { command } => match command {
+            EpicCommands::Status { eligible_only } => {
+                let mut epics = store.epic_status()?;
+                if eligible_only {
+                    epics.retain(|e| e.eligible_for_close);
+                }
+                if epics.is_empty() {
+                    println!("No open epics found");
+                }
+                for status in &epics {
+                    let icon = if status.eligible_for_close { "✓" } else { "○" };
+                    println!("{} {}: {}", icon, status.epic.id, status.epic.title);
+                    println!(
+                        "   Progress: {}/{} children closed ({}%)",
+                        status.closed_children, status.total_children, status.percent_complete()
+                    );
+                    if status.eligible_for_close {
+                        println!("   Eligible for closure");
+                    }
+                    println!();
+                }
+            }
+            EpicCommands::CloseEligible { dry_run } => {
+                let eligible: Vec<_> = store.epic_status()?.into_iter().filter(|e| e.eligible_for_close).collect();
+                if eligible.is_empty() {
+                    println!("No epics eligible for closure");
+                } else if dry_run {
+                    println!("Would close {} epic(s):", eligible.len());
+                    for status in &eligible {
+                        println!("  - {}: {}", status.epic.id, status.epic.title);
                     }
                 } else {
-                     // Issue is unassigned.
-                     // Often "ready" queue includes unassigned issues one could pick up.
-                     // Let's include unassigned.
-                     true
-                };
+                    let now = Utc::now();
+                    for status in eligible.iter() {
+                        // Listed epics come without dependencies, which update_issue would drop
+                        let Some(mut epic) = store.get_issue(&status.epic.id)? else { continue };
+                        epic.status = "closed".to_string();
+                        epic.closed_at = Some(now);
+                        epic.updated_at = now;
+                        store.update_issue(&epic).context(format!("Failed to close {}", epic.id))?;
+                    }
+                    println!("✓ Closed {} epic(s)", eligible.len());
+                    for status in &eligible {
+                        println!("  - {}", status.epic.id);
+                    }
+                }
+            }
+        },
+        Commands::Compact { dry_run, all, id, tier, force } => {
+            use beads_core::{compact, GitOps};
+            let config = compact::CompactionConfig::from_config(|key| store.get_config(key))?;
+            let now = Utc::now();
+            let issues = match &id {
+                Some(id) => {
+                    let issue = store.get_issue(id)?.with_context(|| format!("Issue not found: {}", id))?;
+                    if let Some(reason) = compact::check_eligibility(&store, &issue, tier, &config, now, force)? {
+                        anyhow::bail!("cannot compact: {}", reason);
+                    }
+                    vec![issue]
+                }
+                None if all || dry_run => compact::candidates(&store, tier, &config, now)?,
+                None => anyhow::bail!("specify --all, --id <issue-id> or --dry-run"),
+            };
 
-                if matches_assignee {
-                    println!("{:<10} {:<10} {:<10} {}", issue.id, issue.status, issue.priority, issue.title);
+            if dry_run {
+                println!("DRY RUN: {} issue(s) eligible for tier {} compaction", issues.len(), tier);
+                for issue in &issues {
+                    let closed = issue.closed_at.map(|t| t.format("%Y-%m-%d").to_string()).unwrap_or_default();
+                    println!("  {} closed {} ({} bytes): {}", issue.id, closed, compact::compactable_size(issue), issue.title);
+                }
+            } else {
+                let beads_dir = db_path.parent().unwrap();
+                let git_root = beads_dir.parent().unwrap_or(std::path::Path::new("."));
+                let commit = beads_core::git::configured(&store, git_root)?.head_commit().unwrap_or(None);
+                let summarizer = compact::configured_summarizer(&store)?;
+                let (mut count, mut saved) = (0, 0);
+                for issue in &issues {
+                    match compact::compact_issue(&store, &issue.id, tier, summarizer.as_ref(), commit.as_deref(), now) {
+                        Ok(result) => {
+                            println!("Compacted {}: {} -> {} bytes", result.id, result.size_before, result.size_after);
+                            count += 1;
+                            saved += result.size_before - result.size_after;
+                        }
+                        Err(e) if id.is_none() => eprintln!("Skipped {}: {:#}", issue.id, e),
+                        Err(e) => return Err(e),
+                    }
+                }
+                println!("Compacted {} issue(s) at tier {}, saving {} bytes", count, tier, saved);
+                if count > 0 && commit.is_none() {
+                    eprintln!("Warning: no git commit to record; `bd restore` cannot recover these issues");
                 }
             }
         }
+        Commands::Restore { id } => {
+            let issue = store.get_issue(&id)?.with_context(|| format!("Issue not found: {}", id))?;
+            let beads_dir = db_path.parent().unwrap();
+            let git_root = beads_dir.parent().unwrap_or(std::path::Path::new("."));
+            let git = beads_core::git::configured(&store, git_root)?;
+            let original = beads_core::compact::restore_original(&git, git_root, &beads_dir.join("issues.jsonl"), &issue)?;
+            println!(
+                "Original of {} from commit {} (read-only; compacted at tier {})",
+                issue.id,
+                issue.compacted_at_commit.as_deref().unwrap_or_default(),
+                issue.compaction_level
+            );
+            println!();
+            print_issue(&original);
+        }
         Commands::Sync { squash, dry_run } => {
             let beads_dir = db_path.parent().unwrap();
             let git_root = beads_dir.parent().unwrap_or(std::path::Path::new("."));
-            let git = beads_core::StdGit::new(git_root);
+            let git = beads_core::git::configured(&store, git_root)?;
             let jsonl_path = beads_dir.join("issues.jsonl");
             let fs = StdFileSystem;
-            beads_core::sync::run_sync(&mut store, &git, git_root, &jsonl_path, &fs, squash, dry_run).context("Sync failed")?;
+            let report = beads_core::sync::run_sync(&mut store, &git, git_root, &jsonl_path, &fs, squash, dry_run).context("Sync failed")?;
+            if let Some(report) = report.filter(|r| !r.issues.is_empty()) {
+                println!("Resolved {} issue(s) from the remote and local changes automatically:", report.issues.len());
+                for issue in &report.issues {
+                    println!("  {}", issue.describe(["remote", "local"]));
+                }
+            }
             if dry_run {
                 println!("Sync complete (dry-run).");
             } else {
@@ -619,7 +1447,7 @@ fn main() -> anyhow::Result<()> {
             }
         }
         Commands::Stats => {
-            let issues = store.list_issues(None, None, None, None, None, None)?;
+            let issues = store.list_issues(&beads_core::IssueFilter::default())?;
             let total = issues.len();
             let mut by_status = std::collections::HashMap::new();
             let mut by_assignee = std::collections::HashMap::new();
@@ -657,6 +1485,7 @@ fn main() -> anyhow::Result<()> {
                 println!("  {:<12} {}", k, v);
             }
         }
+        Commands::Migrate { .. } => unreachable!("handled before the store is opened"),
         Commands::Config { command } => match command {
             ConfigCommands::Set { key, value } => {
                 store.set_config(&key, &value).context("Failed to set config")?;
@@ -676,7 +1505,171 @@ fn main() -> anyhow::Result<()> {
                 }
             }
         },
-        Commands::Create { title, mut description, type_, priority } => {
+        Commands::Dep { command } => match command {
+            DepCommands::Add { issue_id, depends_on_id, dep_type } => {
+                let from = resolve_id(&store, &issue_id)?;
+                let to = resolve_id(&store, &depends_on_id)?;
+                let user = store.get_config("user.name")?.unwrap_or_else(|| "unknown".to_string());
+
+                let dep = beads_core::Dependency {
+                    issue_id: from.clone(),
+                    depends_on_id: to.clone(),
+                    type_: dep_type.clone(),
+                    created_at: Utc::now(),
+                    created_by: user,
+                };
+                store.add_dependency(&dep)?;
+                println!("Added dependency: {} depends on {} ({})", from, to, dep_type);
+            }
+            DepCommands::Remove { issue_id, depends_on_id } => {
+                let from = resolve_id(&store, &issue_id)?;
+                let to = resolve_id(&store, &depends_on_id)?;
+                store.remove_dependency(&from, &to)?;
+                println!("Removed dependency: {} no longer depends on {}", from, to);
+            }
+            DepCommands::List { id } => {
+                let id = resolve_id(&store, &id)?;
+                let graph = store.dependency_graph()?;
+
+                let deps = graph.dependencies_of(&id);
+                if deps.is_empty() {
+                    println!("{} has no dependencies", id);
+                } else {
+                    println!("{} depends on:", id);
+                    for dep in deps {
+                        println!("  {} ({})", describe_issue(&store, &dep.depends_on_id)?, dep.type_);
+                    }
+                }
+
+                let dependents = graph.dependents_of(&id);
+                if !dependents.is_empty() {
+                    println!("\nDepended on by:");
+                    for dep in dependents {
+                        println!("  {} ({})", describe_issue(&store, &dep.issue_id)?, dep.type_);
+                    }
+                }
+            }
+            DepCommands::Tree { id, direction, max_depth } => {
+                use beads_core::graph::TreeDirection;
+
+                let id = resolve_id(&store, &id)?;
+                let directions: &[(TreeDirection, &str)] = match direction.as_str() {
+                    "down" => &[(TreeDirection::Down, "Depends on")],
+                    "up" => &[(TreeDirection::Up, "Depended on by")],
+                    "both" => &[(TreeDirection::Down, "Depends on"), (TreeDirection::Up, "Depended on by")],
+                    other => anyhow::bail!("invalid direction '{}': use down, up or both", other),
+                };
+                let graph = store.dependency_graph()?;
+
+                println!("\n{}", describe_issue(&store, &id)?);
+                for (dir, heading) in directions {
+                    let nodes = graph.tree(&id, *dir, max_depth);
+                    println!("\n{}:", heading);
+                    if nodes.is_empty() {
+                        println!("  (none)");
+                    }
+                    render_tree(&store, &nodes)?;
+                }
+                println!();
+            }
+            DepCommands::Cycles => {
+                let cycles = store.dependency_graph()?.cycles();
+                if cycles.is_empty() {
+                    println!("\nNo dependency cycles detected\n");
+                } else {
+                    println!("\nFound {} dependency cycles:\n", cycles.len());
+                    for (i, cycle) in cycles.iter().enumerate() {
+                        println!("{}. {} → {}", i + 1, cycle.join(" → "), cycle[0]);
+                        for id in cycle {
+                            println!("   - {}", describe_issue(&store, id)?);
+                        }
+                        println!();
+                    }
+                }
+            }
+        },
+        Commands::Comments { id, command } => match command {
+            Some(CommentCommands::Add { id, text, file }) => {
+                let id = resolve_id(&store, &id)?;
+                let text = match (text, file) {
+                    (_, Some(file)) if file.as_os_str() == "-" => read_stdin()?,
+                    (_, Some(file)) => std::fs::read_to_string(&file).context(format!("Failed to read {:?}", file))?,
+                    (Some(text), None) => text,
+                    (None, None) => read_stdin()?,
+                };
+                let text = text.trim();
+                if text.is_empty() {
+                    anyhow::bail!("comment text is empty");
+                }
+
+                let user = store.get_config("user.name")?.unwrap_or_else(|| "unknown".to_string());
+                store.add_comment(&id, &user, text)?;
+                println!("Comment added to {}", id);
+            }
+            None => {
+                let id = match id {
+                    Some(id) => resolve_id(&store, &id)?,
+                    None => anyhow::bail!("an issue ID is required"),
+                };
+                let comments = store.list_comments(&id)?;
+                if comments.is_empty() {
+                    println!("No comments on {}", id);
+                    return Ok(());
+                }
+                println!("\nComments on {}:\n", id);
+                for comment in comments {
+                    println!("[{}] {} at {}", comment.author, comment.text, comment.created_at.format("%Y-%m-%d %H:%M"));
+                    println!();
+                }
+            }
+        },
+        Commands::Create { title, mut description, type_, priority, label, parent, from_template } => {
+            let parent = parent.map(|p| resolve_id(&store, &p)).transpose()?;
+            if let Some(name) = from_template {
+                let template = beads_core::template::load(&db_path.with_file_name("templates"), &name, &StdFileSystem)?;
+                if description.is_empty() {
+                    description = template.description.clone();
+                }
+                let now = Utc::now();
+                let user = store.get_config("user.name")?.unwrap_or_else(|| "unknown".to_string());
+                let short_id = new_issue_id(&store, parent.as_deref(), &title, &description, &user)?;
+                let mut issues = template.instantiate(&short_id, &title, &user, now)?;
+
+                let issue = &mut issues[0];
+                issue.description = description;
+                if let Some(type_) = type_ {
+                    issue.issue_type = type_;
+                }
+                if let Some(priority) = priority {
+                    issue.priority = priority;
+                }
+                for l in label {
+                    if !issue.labels.contains(&l) {
+                        issue.labels.push(l);
+                    }
+                }
+                if let Some(parent) = parent {
+                    issue.dependencies.push(beads_core::Dependency {
+                        issue_id: short_id.clone(),
+                        depends_on_id: parent,
+                        type_: beads_core::graph::DEP_PARENT_CHILD.to_string(),
+                        created_at: now,
+                        created_by: user.clone(),
+                    });
+                }
+
+                store.create_issues(&issues).context("Failed to create issues")?;
+                for child in &issues[1..] {
+                    println!("Created child {}: {}", child.id, child.title);
+                }
+                println!("Created issue {}", short_id);
+                if auto_flush {
+                    flush_jsonl(&store, &db_path);
+                }
+                return Ok(());
+            }
+            let type_ = type_.unwrap_or_else(|| "bug".to_string());
+            let priority = priority.unwrap_or(2);
             // Interactive editing if description is empty
             if description.is_empty() {
                  let frontmatter = IssueFrontmatter {
@@ -685,7 +1678,7 @@ fn main() -> anyhow::Result<()> {
                     priority,
                     issue_type: type_.clone(),
                     assignee: None,
-                    labels: Vec::new(),
+                    labels: label.clone(),
                     dependencies: Vec::new(),
                 };
 
@@ -719,54 +1712,40 @@ fn main() -> anyhow::Result<()> {
 
                                  let now = Utc::now();
                                  // Use new_fm values
-                                 let prefix = store.get_config("issue_id_prefix")?.unwrap_or_else(|| "bd".to_string());
                                  let user = store.get_config("user.name")?.unwrap_or_else(|| "unknown".to_string());
-                                 let short_id = store.generate_unique_id(&prefix, &new_fm.title, &description, &user)?;
+                                 let short_id = new_issue_id(&store, parent.as_deref(), &new_fm.title, &description, &user)?;
 
                                  let issue = Issue {
                                     id: short_id.clone(),
-                                    content_hash: String::new(),
                                     title: new_fm.title,
                                     description,
-                                    design: String::new(),
-                                    acceptance_criteria: String::new(),
-                                    notes: String::new(),
                                     status: new_fm.status,
                                     priority: new_fm.priority,
                                     issue_type: new_fm.issue_type,
                                     assignee: new_fm.assignee,
-                                    estimated_minutes: None,
                                     created_at: now,
                                     updated_at: now,
-                                    closed_at: None,
-                                    external_ref: None,
-                                    sender: String::new(),
-                                    ephemeral: false,
-                                    replies_to: String::new(),
-                                    relates_to: Vec::new(),
-                                    duplicate_of: String::new(),
-                                    superseded_by: String::new(),
-
-                                    deleted_at: None,
-                                    deleted_by: String::new(),
-                                    delete_reason: String::new(),
-                                    original_type: String::new(),
-
                                     labels: new_fm.labels,
-                                    dependencies: new_fm.dependencies.into_iter().map(|fd| {
+                                    dependencies: new_fm.dependencies.into_iter()
+                                        .map(|fd| (fd.id, fd.dep_type))
+                                        .chain(parent.clone().map(|p| (p, beads_core::graph::DEP_PARENT_CHILD.to_string())))
+                                        .map(|(depends_on_id, type_)| {
                                         use beads_core::models::Dependency;
                                         Dependency {
                                             issue_id: short_id.clone(),
-                                            depends_on_id: fd.id,
-                                            type_: fd.dep_type,
+                                            depends_on_id,
+                                            type_,
                                             created_at: now,
                                             created_by: user.clone(),
                                         }
                                     }).collect(),
-                                    comments: Vec::new(),
+                                    ..Default::default()
                                 };
                                 store.create_issue(&issue).context("Failed to create issue")?;
                                 println!("Created issue {}", short_id);
+                                if auto_flush {
+                                    flush_jsonl(&store, &db_path);
+                                }
                                 return Ok(());
                              },
                              Err(e) => {
@@ -785,42 +1764,27 @@ fn main() -> anyhow::Result<()> {
             // If description IS empty, we returned or bailed above.
             // So this path is only for non-interactive creation.
             let now = Utc::now();
-            let prefix = store.get_config("issue_id_prefix")?.unwrap_or_else(|| "bd".to_string());
             let user = store.get_config("user.name")?.unwrap_or_else(|| "unknown".to_string());
-            let short_id = store.generate_unique_id(&prefix, &title, &description, &user)?;
+            let short_id = new_issue_id(&store, parent.as_deref(), &title, &description, &user)?;
 
             let issue = Issue {
                 id: short_id.clone(),
-                content_hash: String::new(),
                 title,
                 description,
-                design: String::new(),
-                acceptance_criteria: String::new(),
-                notes: String::new(),
                 status: "open".to_string(),
                 priority,
                 issue_type: type_,
-                assignee: None,
-                estimated_minutes: None,
                 created_at: now,
                 updated_at: now,
-                closed_at: None,
-                external_ref: None,
-                sender: String::new(),
-                ephemeral: false,
-                replies_to: String::new(),
-                relates_to: Vec::new(),
-                duplicate_of: String::new(),
-                superseded_by: String::new(),
-
-                deleted_at: None,
-                deleted_by: String::new(),
-                delete_reason: String::new(),
-                original_type: String::new(),
-
-                labels: Vec::new(),
-                dependencies: Vec::new(),
-                comments: Vec::new(),
+                labels: label,
+                dependencies: parent.into_iter().map(|p| beads_core::Dependency {
+                    issue_id: short_id.clone(),
+                    depends_on_id: p,
+                    type_: beads_core::graph::DEP_PARENT_CHILD.to_string(),
+                    created_at: now,
+                    created_by: user.clone(),
+                }).collect(),
+                ..Default::default()
             };
 
             store.create_issue(&issue).context("Failed to create issue")?;
@@ -828,9 +1792,34 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
+    if auto_flush {
+        flush_jsonl(&store, &db_path);
+    }
+
     Ok(())
 }
 
+/// Writes issues changed by this command to the JSONL next to the database.
+/// Only dirty issues are rewritten, so this is cheap when little changed.
+fn flush_jsonl(store: &SqliteStore, db_path: &std::path::Path) {
+    let jsonl_path = db_path.with_file_name("issues.jsonl");
+    if let Err(e) = store.export_dirty_to_jsonl(&jsonl_path, &StdFileSystem) {
+        eprintln!("Warning: failed to flush changes to {}: {:#}", jsonl_path.display(), e);
+    }
+}
+
+/// ID for a new issue: the next child of `parent` if there is one, otherwise
+/// a fresh hash ID with the configured prefix.
+fn new_issue_id(store: &impl Store, parent: Option<&str>, title: &str, description: &str, user: &str) -> anyhow::Result<String> {
+    match parent {
+        Some(parent) => store.next_child_id(parent),
+        None => {
+            let prefix = store.get_config("issue_id_prefix")?.unwrap_or_else(|| "bd".to_string());
+            store.generate_unique_id(&prefix, title, description, user)
+        }
+    }
+}
+
 fn find_db_path() -> PathBuf {
     let mut current = match std::env::current_dir() {
         Ok(c) => c,
@@ -851,3 +1840,160 @@ fn find_db_path() -> PathBuf {
     // But `PathBuf::from` is relative to process CWD.
     PathBuf::from(".beads/beads.db")
 }
+
+fn run_migrate(db_path: &std::path::Path, dry_run: bool) -> anyhow::Result<()> {
+    if !db_path.exists() {
+        anyhow::bail!("No database found at {:?}", db_path);
+    }
+    let mut store = SqliteStore::open_unmigrated(db_path)?;
+    let version = store.schema_version()?;
+    let pending = store.pending_migrations()?;
+    if pending.is_empty() {
+        println!("Database is up to date (schema version {})", version);
+        return Ok(());
+    }
+
+    println!("Database is at schema version {}; {} migration(s) pending:", version, pending.len());
+    for migration in &pending {
+        println!("  {}: {}", migration.version, migration.description);
+    }
+    if dry_run {
+        println!("\nDry run: no changes made");
+        return Ok(());
+    }
+
+    store.migrate()?;
+    println!("\nMigrated to schema version {}", store.schema_version()?);
+    Ok(())
+}
+
+/// Parses a priority given as "2" or "P2".
+fn parse_priority(s: &str) -> Result<i32, String> {
+    s.trim_start_matches(['P', 'p'])
+        .parse()
+        .map_err(|_| format!("invalid priority '{}': use 0-4 or P0-P4", s))
+}
+
+/// Parses a date flag given as YYYY-MM-DD (midnight UTC) or RFC 3339.
+fn parse_date(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| format!("invalid date '{}': use YYYY-MM-DD or RFC 3339", s))
+}
+
+/// Reads all of stdin, for commands that accept piped text.
+fn read_stdin() -> anyhow::Result<String> {
+    let mut text = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)?;
+    Ok(text)
+}
+
+/// Resolves a possibly shortened issue ID to the full ID.
+fn resolve_id(store: &impl Store, id: &str) -> anyhow::Result<String> {
+    match store.get_issue(id)? {
+        Some(issue) => Ok(issue.id),
+        None => anyhow::bail!("Issue not found: {}", id),
+    }
+}
+
+/// Prints an issue the way `bd show` does.
+fn print_issue(issue: &Issue) {
+    println!("ID:          {}", issue.id);
+    println!("Title:       {}", issue.title);
+    println!("Status:      {}", issue.status);
+    println!("Priority:    {}", issue.priority);
+    println!("Type:        {}", issue.issue_type);
+    if let Some(assignee) = &issue.assignee {
+        println!("Assignee:    {}", assignee);
+    }
+    println!("Created:     {}", issue.created_at);
+    println!("Updated:     {}", issue.updated_at);
+    if let Some(deleted_at) = issue.deleted_at {
+        println!("Deleted:     {} by {} ({})", deleted_at, issue.deleted_by, issue.delete_reason);
+    }
+    if let Some(compacted_at) = issue.compacted_at {
+        println!("Compacted:   tier {} at {} (see `bd restore {}`)", issue.compaction_level, compacted_at, issue.id);
+    }
+    println!("------------------------------------------------------------");
+    println!("{}", issue.description);
+    for (heading, text) in [("Design", &issue.design), ("Acceptance Criteria", &issue.acceptance_criteria), ("Notes", &issue.notes)] {
+        if !text.is_empty() {
+            println!("\n{}:\n{}", heading, text);
+        }
+    }
+
+    if !issue.labels.is_empty() {
+        println!("\nLabels: {}", issue.labels.join(", "));
+    }
+
+    if !issue.dependencies.is_empty() {
+        println!("\nDependencies:");
+        for dep in &issue.dependencies {
+            println!("  {} ({})", dep.depends_on_id, dep.type_);
+        }
+    }
+
+    if !issue.comments.is_empty() {
+        println!("\nComments:");
+        for comment in &issue.comments {
+            println!("  {} at {}:", comment.author, comment.created_at);
+            println!("    {}", comment.text);
+        }
+    }
+}
+
+/// Prints the links `bd show` lists under an issue, if it has any.
+fn print_links(links: &beads_core::IssueLinks) {
+    let mut lines = Vec::new();
+    for (label, summaries) in [
+        ("Related to", links.related.iter().collect::<Vec<_>>()),
+        ("Duplicate of", links.duplicate_of.iter().collect()),
+        ("Superseded by", links.superseded_by.iter().collect()),
+        ("Duplicated by", links.duplicates.iter().collect()),
+        ("Supersedes", links.supersedes.iter().collect()),
+    ] {
+        for summary in summaries {
+            lines.push(format!("  {}: {}: {} ({})", label, summary.id, summary.title, summary.status));
+        }
+    }
+    if !lines.is_empty() {
+        println!("\nLinks:");
+        for line in lines {
+            println!("{}", line);
+        }
+    }
+}
+
+/// "id: title [Pn] (status)", or just the ID for issues missing from the store.
+fn describe_issue(store: &impl Store, id: &str) -> anyhow::Result<String> {
+    Ok(match store.get_issue(id)? {
+        Some(issue) if issue.id == id => {
+            format!("{}: {} [P{}] ({})", issue.id, issue.title, issue.priority, issue.status)
+        }
+        _ => format!("{} (not found)", id),
+    })
+}
+
+fn render_tree(store: &impl Store, nodes: &[beads_core::graph::TreeNode]) -> anyhow::Result<()> {
+    // last[d] records whether the ancestor at depth d was the last of its siblings
+    let mut last: Vec<bool> = Vec::new();
+    for node in nodes {
+        last.truncate(node.depth - 1);
+        let mut prefix = String::new();
+        for &ancestor_last in &last {
+            prefix.push_str(if ancestor_last { "    " } else { "│   " });
+        }
+        prefix.push_str(if node.last { "└── " } else { "├── " });
+        last.push(node.last);
+
+        if node.seen {
+            println!("{}{} ({}, shown above)", prefix, node.id, node.dep_type);
+        } else {
+            println!("{}{} ({})", prefix, describe_issue(store, &node.id)?, node.dep_type);
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use predicates::prelude::*;
 use std::fs;
 use tempfile::TempDir;
@@ -9,7 +9,7 @@ fn test_onboard() -> Result<(), Box<dyn std::error::Error>> {
     let path = temp.path();
 
     // Run bd onboard in temp dir
-    let mut cmd = Command::cargo_bin("bd")?;
+    let mut cmd = cargo_bin_cmd!("bd");
     cmd.current_dir(path)
         .arg("onboard")
         .write_stdin("\n") // Accept default username
@@ -31,7 +31,7 @@ fn test_create_list_show_close() -> Result<(), Box<dyn std::error::Error>> {
     let path = temp.path();
 
     // Onboard first
-    Command::cargo_bin("bd")?
+    cargo_bin_cmd!("bd")
         .current_dir(path)
         .arg("onboard")
         .write_stdin("\n")
@@ -39,7 +39,7 @@ fn test_create_list_show_close() -> Result<(), Box<dyn std::error::Error>> {
         .success();
 
     // Create issue
-    let assert = Command::cargo_bin("bd")?
+    let assert = cargo_bin_cmd!("bd")
         .current_dir(path)
         .arg("create")
         .arg("Test Issue")
@@ -51,11 +51,11 @@ fn test_create_list_show_close() -> Result<(), Box<dyn std::error::Error>> {
     let output = assert.get_output();
     let stdout = String::from_utf8(output.stdout.clone())?;
     // "Created issue bd-..."
-    let id = stdout.trim().split_whitespace().last().unwrap();
+    let id = stdout.split_whitespace().last().unwrap();
     assert!(id.starts_with("bd-"));
 
     // List
-    Command::cargo_bin("bd")?
+    cargo_bin_cmd!("bd")
         .current_dir(path)
         .arg("list")
         .assert()
@@ -64,7 +64,7 @@ fn test_create_list_show_close() -> Result<(), Box<dyn std::error::Error>> {
         .stdout(predicate::str::contains("Test Issue"));
 
     // Show
-    Command::cargo_bin("bd")?
+    cargo_bin_cmd!("bd")
         .current_dir(path)
         .arg("show")
         .arg(id)
@@ -74,7 +74,7 @@ fn test_create_list_show_close() -> Result<(), Box<dyn std::error::Error>> {
         .stdout(predicate::str::contains("This is a test"));
 
     // Close
-    Command::cargo_bin("bd")?
+    cargo_bin_cmd!("bd")
         .current_dir(path)
         .arg("close")
         .arg(id)
@@ -83,7 +83,7 @@ fn test_create_list_show_close() -> Result<(), Box<dyn std::error::Error>> {
         .stdout(predicate::str::contains(format!("Closed issue {}", id)));
 
     // Verify status is closed
-    Command::cargo_bin("bd")?
+    cargo_bin_cmd!("bd")
         .current_dir(path)
         .arg("show")
         .arg(id)
@@ -100,7 +100,7 @@ fn test_path_handling() -> Result<(), Box<dyn std::error::Error>> {
     let root = temp.path();
 
     // Onboard at root
-    Command::cargo_bin("bd")?
+    cargo_bin_cmd!("bd")
         .current_dir(root)
         .arg("onboard")
         .write_stdin("\n")
@@ -108,7 +108,7 @@ fn test_path_handling() -> Result<(), Box<dyn std::error::Error>> {
         .success();
 
     // Create issue at root
-    Command::cargo_bin("bd")?
+    cargo_bin_cmd!("bd")
         .current_dir(root)
         .arg("create")
         .arg("Root Issue")
@@ -122,7 +122,7 @@ fn test_path_handling() -> Result<(), Box<dyn std::error::Error>> {
     fs::create_dir_all(&subdir)?;
 
     // Run list from subdir
-    Command::cargo_bin("bd")?
+    cargo_bin_cmd!("bd")
         .current_dir(&subdir)
         .arg("list")
         .assert()
@@ -131,3 +131,1371 @@ fn test_path_handling() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_ready_excludes_blocked() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    let mut ids = Vec::new();
+    for title in ["Blocker Task", "Blocked Task"] {
+        let output = cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["create", title, "--description", "desc"])
+            .output()?;
+        let stdout = String::from_utf8(output.stdout)?;
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["update", &ids[1], "--add-dependency", &ids[0]])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("ready")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Blocker Task"))
+        .stdout(predicate::str::contains("Blocked Task").not());
+
+    // Closing the blocker frees the dependent issue
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["close", &ids[0]])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("ready")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Blocked Task"));
+
+    Ok(())
+}
+
+#[test]
+fn test_blocked_lists_blockers() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("blocked")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No blocked issues"));
+
+    let mut ids = Vec::new();
+    for title in ["Schema Migration", "Deploy Feature", "Announce Feature"] {
+        let output = cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["create", title, "--description", "desc"])
+            .output()?;
+        let stdout = String::from_utf8(output.stdout)?;
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+
+    for (dep, error) in [
+        (
+            format!("{}:bogus", ids[0]),
+            "invalid dependency type: bogus",
+        ),
+        (
+            "bd-missing".to_string(),
+            "dependency target bd-missing not found",
+        ),
+    ] {
+        cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["update", &ids[1], "--add-dependency", &dep])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(error));
+    }
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args([
+            "update",
+            &ids[1],
+            "--add-dependency",
+            &format!("{}:blocks", ids[0]),
+        ])
+        .assert()
+        .success();
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args([
+            "update",
+            &ids[2],
+            "--add-dependency",
+            &format!("{}:parent-child", ids[1]),
+        ])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("blocked")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Blocked issues (2)"))
+        .stdout(predicate::str::contains(format!(
+            "{}: Deploy Feature",
+            ids[1]
+        )))
+        .stdout(predicate::str::contains(format!(
+            "Blocked by 1 open dependencies:\n    {}: Schema Migration (open)",
+            ids[0]
+        )))
+        .stdout(predicate::str::contains(format!(
+            "Blocked via parent {}: Deploy Feature (open)",
+            ids[1]
+        )));
+
+    Ok(())
+}
+
+#[test]
+fn test_dep_add_tree_and_cycle() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    let mut ids = Vec::new();
+    for title in ["Epic Work", "Sub Work"] {
+        let output = cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["create", title, "--description", "desc"])
+            .output()?;
+        let stdout = String::from_utf8(output.stdout)?;
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["dep", "add", &ids[1], &ids[0]])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Added dependency: {} depends on {} (blocks)",
+            ids[1], ids[0]
+        )));
+
+    // Closing the loop is rejected with the cycle path
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["dep", "add", &ids[0], &ids[1]])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "{} → {} → {}",
+            ids[0], ids[1], ids[0]
+        )));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["dep", "tree", &ids[0]])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "└── {}: Sub Work",
+            ids[1]
+        )));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["dep", "cycles"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No dependency cycles detected"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["dep", "remove", &ids[1], &ids[0]])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["dep", "list", &ids[1]])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("has no dependencies"));
+
+    Ok(())
+}
+
+#[test]
+fn test_delete_requires_force() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    let mut ids = Vec::new();
+    for title in ["Keep Me", "Drop Me"] {
+        let output = cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["create", title, "--description", "desc"])
+            .output()?;
+        let stdout = String::from_utf8(output.stdout)?;
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["dep", "add", &ids[0], &ids[1]])
+        .assert()
+        .success();
+
+    // Without --force only a preview is printed
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["delete", &ids[1]])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("DELETE PREVIEW"))
+        .stdout(predicate::str::contains(format!("{}: Keep Me", ids[0])));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &ids[1]])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Status:      open"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["delete", &ids[1], "--force", "--reason", "not needed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Deleted {}", ids[1])))
+        .stdout(predicate::str::contains("Removed 1 dependency link(s)"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &ids[1]])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Status:      tombstone"))
+        .stdout(predicate::str::contains("(not needed)"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Keep Me"))
+        .stdout(predicate::str::contains("Drop Me").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_reopen_and_undelete() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    let output = cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "Flaky Test", "--description", "desc"])
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["close", &id])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["reopen", &id, "--reason", "still flaky"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Reopened issue {}: still flaky",
+            id
+        )));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Status:      open"))
+        .stdout(predicate::str::contains("still flaky"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["reopen", &id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not closed"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["delete", &id, "--force"])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["undelete", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Restored issue {}", id)));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Status:      open"))
+        .stdout(predicate::str::contains("Type:        bug"));
+
+    Ok(())
+}
+
+#[test]
+fn test_comments_add_and_list() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    let output = cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "Needs Discussion", "--description", "desc"])
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["comments", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("No comments on {}", id)));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["comments", "add", &id, "from argument"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Comment added to {}", id)));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["comments", "add", &id])
+        .write_stdin("from stdin\n")
+        .assert()
+        .success();
+
+    let note = path.join("note.txt");
+    fs::write(&note, "from file")?;
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["comments", "add", &id, "--file", note.to_str().unwrap()])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["comments", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("from argument"))
+        .stdout(predicate::str::contains("from stdin"))
+        .stdout(predicate::str::contains("from file"));
+
+    Ok(())
+}
+
+#[test]
+fn test_search() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    for (title, description) in [
+        ("Login fails", "authentication error on submit"),
+        ("Slow dashboard", "charts take seconds"),
+    ] {
+        cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["create", title, "--description", description])
+            .assert()
+            .success();
+    }
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["search", "auth"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 1 issues matching 'auth'"))
+        .stdout(predicate::str::contains("- Login fails"))
+        .stdout(predicate::str::contains("[authentication]"))
+        .stdout(predicate::str::contains("Slow dashboard").not());
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["search", "auth", "--status", "closed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No issues found matching 'auth'"));
+
+    Ok(())
+}
+
+#[test]
+fn test_list_filters() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    let mut ids = Vec::new();
+    for (title, priority) in [("Urgent Fix", "0"), ("Later Cleanup", "3")] {
+        let output = cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["create", title, "--description", "desc", "-p", priority])
+            .output()?;
+        let stdout = String::from_utf8(output.stdout)?;
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["close", &ids[1]])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["list", "--not-status", "closed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Urgent Fix"))
+        .stdout(predicate::str::contains("Later Cleanup").not());
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["list", "--priority-min", "P2", "--contains", "cleanup"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Later Cleanup"))
+        .stdout(predicate::str::contains("Urgent Fix").not());
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args([
+            "list",
+            "--created-after",
+            "2000-01-01",
+            "--sort",
+            "priority",
+            "-n",
+            "1",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Urgent Fix"))
+        .stdout(predicate::str::contains("Later Cleanup").not());
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["list", "--created-after", "yesterday"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid date"));
+
+    Ok(())
+}
+
+#[test]
+fn test_migrate() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("migrate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Database is up to date"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["migrate", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Database is up to date"));
+
+    let empty = TempDir::new()?;
+    cargo_bin_cmd!("bd")
+        .current_dir(empty.path())
+        .args(["migrate", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No database found"));
+    assert!(!empty.path().join(".beads").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_import_reports_changes() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+    for title in ["First", "Second"] {
+        cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["create", title, "--description", "desc"])
+            .assert()
+            .success();
+    }
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["export", "-o", "out.jsonl"])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["import", "-i", "out.jsonl"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "0 created, 0 updated, 2 unchanged",
+        ));
+
+    let edited = fs::read_to_string(path.join("out.jsonl"))?.replacen("First", "Renamed", 1);
+    fs::write(path.join("out.jsonl"), edited)?;
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["import", "-i", "out.jsonl"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "0 created, 1 updated, 1 unchanged",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_merge_report() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "First", "--description", "desc"])
+        .assert()
+        .success();
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["export", "-o", "base.jsonl"])
+        .assert()
+        .success();
+    let base = fs::read_to_string(path.join("base.jsonl"))?;
+    fs::write(
+        path.join("left.jsonl"),
+        base.replacen("First", "Renamed", 1),
+    )?;
+    fs::write(path.join("right.jsonl"), &base)?;
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args([
+            "merge",
+            "out.jsonl",
+            "base.jsonl",
+            "left.jsonl",
+            "right.jsonl",
+            "--report",
+            "json",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"resolution\": \"merged\""))
+        .stdout(predicate::str::contains("\"title\": \"left\""));
+    assert!(fs::read_to_string(path.join("out.jsonl"))?.contains("Renamed"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args([
+            "merge",
+            "out.jsonl",
+            "base.jsonl",
+            "left.jsonl",
+            "right.jsonl",
+            "--report",
+            "yaml",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid report format"));
+
+    Ok(())
+}
+
+#[test]
+fn test_auto_flush() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+    let jsonl = path.join(".beads/issues.jsonl");
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    let output = cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "Flushed", "--description", "desc"])
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+    assert!(fs::read_to_string(&jsonl)?.contains(&id));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["--no-auto-flush", "close", &id])
+        .assert()
+        .success();
+    assert!(!fs::read_to_string(&jsonl)?.contains("\"closed\""));
+
+    // The next command that flushes picks up the pending change
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("list")
+        .assert()
+        .success();
+    assert!(fs::read_to_string(&jsonl)?.contains("\"closed\""));
+
+    Ok(())
+}
+
+#[test]
+fn test_auto_import_after_pull() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+    let jsonl = path.join(".beads/issues.jsonl");
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+    let output = cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "Original title", "--description", "desc"])
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+
+    // Someone else's edit arrives through git
+    let pulled = fs::read_to_string(&jsonl)?.replace("Original title", "Pulled title");
+    fs::write(&jsonl, pulled)?;
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["--no-auto-import", "show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Original title"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pulled title"))
+        .stderr(predicate::str::contains(
+            "Auto-imported 0 new and 1 updated",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_auto_import_fields_outside_content_hash() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+    let jsonl = path.join(".beads/issues.jsonl");
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+    let mut ids = Vec::new();
+    for title in ["First", "Second"] {
+        let output = cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["create", title, "--description", "desc"])
+            .output()?;
+        let stdout = String::from_utf8(output.stdout)?;
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+
+    // Another clone ran `bd relate`, which leaves the content hash alone
+    let pulled: Vec<String> = fs::read_to_string(&jsonl)?
+        .lines()
+        .map(|line| {
+            let mut issue: serde_json::Value = serde_json::from_str(line).unwrap();
+            if issue["id"] == ids[0].as_str() {
+                issue["relates_to"] = serde_json::json!([ids[1]]);
+            }
+            issue.to_string()
+        })
+        .collect();
+    fs::write(&jsonl, pulled.join("\n") + "\n")?;
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &ids[0]])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Related to"))
+        .stdout(predicate::str::contains(ids[1].as_str()))
+        .stderr(predicate::str::contains(
+            "Auto-imported 0 new and 1 updated",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_epic_children_and_close_eligible() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    let create = |args: &[&str]| -> Result<String, Box<dyn std::error::Error>> {
+        let output = cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .arg("create")
+            .args(args)
+            .args(["--description", "desc"])
+            .output()?;
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout)?;
+        Ok(stdout.split_whitespace().last().unwrap().to_string())
+    };
+    let epic = create(&["Big feature", "-t", "epic"])?;
+    let first = create(&["Part one", "-t", "task", "--parent", &epic])?;
+    let second = create(&["Part two", "-t", "task", "--parent", &epic])?;
+    assert_eq!(first, format!("{}.1", epic));
+    assert_eq!(second, format!("{}.2", epic));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["dep", "list", &first])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("{}: Big feature", epic)))
+        .stdout(predicate::str::contains("parent-child"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["close", &first])
+        .assert()
+        .success();
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["epic", "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Progress: 1/2 children closed (50%)",
+        ));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["epic", "close-eligible"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No epics eligible for closure"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["close", &second])
+        .assert()
+        .success();
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["epic", "close-eligible", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would close 1 epic(s)"));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["epic", "close-eligible"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Closed 1 epic(s)"));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &epic])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("closed"));
+
+    Ok(())
+}
+
+#[test]
+fn test_rename_prefix() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+    let jsonl = path.join(".beads/issues.jsonl");
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+    let output = cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "First", "--description", "desc"])
+        .output()?;
+    let id = String::from_utf8(output.stdout)?
+        .split_whitespace()
+        .last()
+        .unwrap()
+        .to_string();
+    let new_id = id.replacen("bd-", "kw-", 1);
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["rename-prefix", "Kw"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("lowercase"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["rename-prefix", "kw-", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("{} -> {}", id, new_id)));
+    assert!(fs::read_to_string(&jsonl)?.contains(&id));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["rename-prefix", "kw"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Renamed 1 issue(s) from prefix 'bd' to 'kw'",
+        ));
+    let exported = fs::read_to_string(&jsonl)?;
+    assert!(exported.contains(&new_id) && !exported.contains(&id));
+
+    // New issues use the new prefix
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "Second", "--description", "desc"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created issue kw-"));
+
+    Ok(())
+}
+
+#[test]
+fn test_compact_and_restore() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .current_dir(path)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+    };
+    git(&["init", "-q"])?;
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+    let description = format!("Crash on save.\n\n{}", "Stack trace line. ".repeat(30));
+    let output = cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "Crash", "--description", &description])
+        .output()?;
+    let id = String::from_utf8(output.stdout)?
+        .split_whitespace()
+        .last()
+        .unwrap()
+        .to_string();
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["close", &id])
+        .assert()
+        .success();
+    git(&["add", ".beads/issues.jsonl"])?;
+    assert!(git(&["commit", "-q", "-m", "issues"])?.status.success());
+
+    // Just closed, so only --force compacts it
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["compact", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 issue(s) eligible"));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["compact", "--id", &id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("closed less than 30 days ago"));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["compact", "--id", &id, "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Compacted 1 issue(s) at tier 1"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Compacted:   tier 1"))
+        .stdout(predicate::str::contains("Stack trace").not());
+    assert!(!fs::read_to_string(path.join(".beads/issues.jsonl"))?.contains("Stack trace"));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["restore", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Stack trace line."));
+
+    Ok(())
+}
+
+#[test]
+fn test_templates() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+    let show = |id: &str| -> Result<String, Box<dyn std::error::Error>> {
+        let output = cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["show", id])
+            .output()?;
+        Ok(String::from_utf8(output.stdout)?)
+    };
+    let created_id = |output: std::process::Output| -> String {
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .last()
+            .and_then(|line| line.split_whitespace().last())
+            .unwrap()
+            .to_string()
+    };
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["template", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Built-in Templates:"))
+        .stdout(predicate::str::contains("  epic"))
+        .stdout(predicate::str::contains("Custom Templates:").not());
+
+    // Built-in bug template, with the priority overridden and a label added
+    let output = cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args([
+            "create",
+            "Login fails",
+            "--from-template",
+            "bug",
+            "-p",
+            "0",
+            "-l",
+            "auth",
+        ])
+        .output()?;
+    let bug = created_id(output);
+    let shown = show(&bug)?;
+    assert!(shown.contains("Type:        bug"));
+    assert!(shown.contains("Priority:    0"));
+    assert!(shown.contains("## Steps to Reproduce"));
+    assert!(shown.contains("Labels: auth, bug"));
+
+    // The epic template creates its children too
+    let output = cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "Search", "--from-template", "epic"])
+        .output()?;
+    let stdout = String::from_utf8(output.stdout.clone())?;
+    let epic = created_id(output);
+    assert!(stdout.contains(&format!("Created child {}.1: Design: Search", epic)));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["epic", "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0/3 children closed"));
+
+    // Custom templates: a skeleton from `template create`, and a markdown one
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["template", "create", "perf"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created template"));
+    assert!(path.join(".beads/templates/perf.yaml").exists());
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["template", "create", "perf"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+    fs::write(
+        path.join(".beads/templates/spike.md"),
+        "---\ntitle: \"Spike: {title}\"\ntype: chore\n---\nWhat do we need to learn?\n\n# Design\nTime-box it.\n",
+    )?;
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["template", "show", "spike"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Title: Spike: {title}"))
+        .stdout(predicate::str::contains("Time-box it."));
+    let output = cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "Caching", "--from-template", "spike"])
+        .output()?;
+    let shown = show(&created_id(output))?;
+    assert!(shown.contains("Title:       Spike: Caching"));
+    assert!(shown.contains("Type:        chore"));
+    assert!(shown.contains("Design:\nTime-box it."));
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["template", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Custom Templates:\n  perf"));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["create", "X", "--from-template", "../secrets"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid template name"));
+
+    Ok(())
+}
+
+#[test]
+fn test_mail() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+    let bd = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("bd");
+        cmd.current_dir(path)
+            .env_remove("BEADS_IDENTITY")
+            .args(args);
+        cmd
+    };
+    let sent_id = |output: std::process::Output| -> String {
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().last())
+            .unwrap()
+            .to_string()
+    };
+
+    bd(&["onboard"]).write_stdin("\n").assert().success();
+    let work = sent_id(bd(&["create", "Real work", "--description", "desc"]).output()?);
+
+    let output = bd(&[
+        "mail",
+        "send",
+        "bob",
+        "-s",
+        "Deploy?",
+        "-m",
+        "Is main ready?",
+        "--identity",
+        "alice",
+    ])
+    .output()?;
+    assert!(output.status.success());
+    let question = sent_id(output);
+
+    // Messages stay out of list and ready
+    bd(&["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Real work"))
+        .stdout(predicate::str::contains("Deploy?").not());
+    bd(&["ready"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deploy?").not());
+    bd(&["list", "--type", "message"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deploy?"));
+
+    // An unread message is not open work waiting on a blocker
+    bd(&["close", &work]).assert().success();
+    bd(&["ready"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No open issues"));
+    bd(&["blocked"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No blocked issues"));
+
+    // Identity from the environment
+    bd(&["mail", "inbox"])
+        .env("BEADS_IDENTITY", "bob")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Inbox for bob (1 messages):"))
+        .stdout(predicate::str::contains(format!("{}: Deploy?", question)))
+        .stdout(predicate::str::contains("From: alice"));
+    bd(&["mail", "read", &question])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("To:      bob"))
+        .stdout(predicate::str::contains("Is main ready?"));
+
+    let output = bd(&["mail", "reply", &question, "-m", "Yes", "--identity", "bob"]).output()?;
+    assert!(output.status.success());
+    let reply = sent_id(output);
+    bd(&["mail", "inbox", "--identity", "alice"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("{}: Re: Deploy?", reply)))
+        .stdout(predicate::str::contains(format!("Re: {}", question)));
+
+    bd(&["mail", "ack", &question, "--identity", "bob"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Acknowledged: {}",
+            question
+        )));
+    bd(&["mail", "ack", &question, "--identity", "bob"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already acknowledged"));
+    bd(&["mail", "inbox", "--identity", "bob"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No messages for bob"));
+
+    bd(&["mail", "cleanup"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Deleted 1 acknowledged message(s)",
+        ));
+    bd(&["show", &question])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("tombstone"));
+    Ok(())
+}
+
+#[test]
+fn test_graph_links() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let path = temp.path();
+    let create = |title: &str| -> Result<String, Box<dyn std::error::Error>> {
+        let output = cargo_bin_cmd!("bd")
+            .current_dir(path)
+            .args(["create", title, "--description", "desc"])
+            .output()?;
+        Ok(String::from_utf8(output.stdout)?
+            .lines()
+            .last()
+            .and_then(|line| line.split_whitespace().last())
+            .unwrap()
+            .to_string())
+    };
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .arg("onboard")
+        .write_stdin("\n")
+        .assert()
+        .success();
+    let login = create("Login")?;
+    let sessions = create("Sessions")?;
+    let login_again = create("Login again")?;
+    let auth_v2 = create("Auth v2")?;
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["relate", &login, &sessions])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Linked {} <-> {}",
+            login, sessions
+        )));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["duplicate", &login_again, "--of", &login])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(closed)"));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["supersede", &login, "--with", &auth_v2])
+        .assert()
+        .success();
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["duplicate", &login, "--of", &login])
+        .assert()
+        .failure();
+
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &login])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Status:      closed"))
+        .stdout(predicate::str::contains(format!(
+            "Related to: {}: Sessions (open)",
+            sessions
+        )))
+        .stdout(predicate::str::contains(format!(
+            "Superseded by: {}: Auth v2 (open)",
+            auth_v2
+        )))
+        .stdout(predicate::str::contains(format!(
+            "Duplicated by: {}: Login again (closed)",
+            login_again
+        )));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &sessions])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Related to: {}: Login (closed)",
+            login
+        )));
+    cargo_bin_cmd!("bd")
+        .current_dir(path)
+        .args(["show", &auth_v2])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Supersedes: {}: Login (closed)",
+            login
+        )));
+    Ok(())
+}
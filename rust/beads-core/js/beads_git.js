@@ -34,3 +34,7 @@ export function git_has_remote() {
     console.log(`[JS] git_has_remote`);
     return false;
 }
+export function git_head_commit() {
+    console.log(`[JS] git_head_commit`);
+    return "";
+}
@@ -0,0 +1,83 @@
+//! Picking up a JSONL file that changed underneath the database, typically
+//! after a `git pull`.
+
+use crate::fs::FileSystem;
+use crate::models::ImportStats;
+use crate::store::{self, Store};
+use anyhow::Result;
+use chrono::SecondsFormat;
+use std::path::Path;
+
+/// Metadata key holding the JSONL's modification time when it was last checked.
+const MTIME_KEY: &str = "jsonl_mtime";
+
+/// What `auto_import` brought in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutoImport {
+    pub stats: ImportStats,
+    /// Dirty issues whose differing version in the file was ignored so that
+    /// unflushed local changes survive. The next flush writes them back out.
+    pub protected: Vec<String>,
+}
+
+/// Imports `jsonl_path` if it changed since the database last exported or
+/// imported it, and returns what changed. An unchanged modification time
+/// skips the check; otherwise the file's content hash decides. Issues with
+/// unflushed local changes are left alone.
+pub fn auto_import(
+    store: &mut impl Store,
+    jsonl_path: &Path,
+    fs: &impl FileSystem,
+) -> Result<Option<AutoImport>> {
+    if !fs.exists(jsonl_path) {
+        return Ok(None);
+    }
+    let mtime = fs
+        .modified(jsonl_path)?
+        .map(|t| t.to_rfc3339_opts(SecondsFormat::Nanos, true));
+    if mtime.is_some() && mtime == store.get_metadata(MTIME_KEY)? {
+        return Ok(None);
+    }
+
+    let contents = fs.read_to_string(jsonl_path)?;
+    let hash = store::jsonl_hash(contents.as_bytes());
+    let imported = if store.get_metadata(store::JSONL_HASH_KEY)?.as_deref() == Some(&hash) {
+        None
+    } else {
+        if has_conflict_markers(&contents) {
+            anyhow::bail!(
+                "{} contains unresolved git merge conflict markers; resolve them, or run \
+                 `bd export -o {}` to regenerate it from the database",
+                jsonl_path.display(),
+                jsonl_path.display()
+            );
+        }
+
+        let dirty = store.dirty_issue_ids()?;
+        let (local, incoming): (Vec<_>, Vec<_>) = store::parse_jsonl(&contents)?
+            .into_iter()
+            .partition(|issue| dirty.binary_search(&issue.id).is_ok());
+        let mut protected = Vec::new();
+        for issue in local {
+            let stored = store.get_issue(&issue.id)?;
+            if stored.is_some_and(|s| !s.same_fields(&issue)) {
+                protected.push(issue.id);
+            }
+        }
+
+        let stats = store.import_issues(incoming)?;
+        store.set_metadata(store::JSONL_HASH_KEY, &hash)?;
+        Some(AutoImport { stats, protected })
+    };
+
+    if let Some(mtime) = mtime {
+        store.set_metadata(MTIME_KEY, &mtime)?;
+    }
+    Ok(imported)
+}
+
+fn has_conflict_markers(contents: &str) -> bool {
+    contents.lines().any(|line| {
+        line.starts_with("<<<<<<< ") || line == "=======" || line.starts_with(">>>>>>> ")
+    })
+}
@@ -0,0 +1,526 @@
+//! Tiered compaction of long-closed issues (`bd compact`) and reading their
+//! originals back out of git (`bd restore`).
+//!
+//! Tier 1 replaces the text fields and comment thread of an issue closed for
+//! 30+ days with a short summary; tier 2 shrinks that summary to one line
+//! after 90+ days. Issues that open work still depends on are left alone.
+//! The commit `HEAD` pointed at when an issue was first compacted is kept in
+//! `compacted_at_commit`, whose JSONL still holds the full original.
+
+use crate::git::GitOps;
+use crate::graph::{self, DependencyGraph};
+use crate::models::{Issue, STATUS_CLOSED};
+use crate::store::{self, Store};
+use crate::util::parse_config_value;
+use crate::IssueFilter;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+pub const CONFIG_TIER1_DAYS: &str = "compact_tier1_days";
+pub const CONFIG_TIER1_DEP_LEVELS: &str = "compact_tier1_dep_levels";
+pub const CONFIG_TIER2_DAYS: &str = "compact_tier2_days";
+pub const CONFIG_TIER2_DEP_LEVELS: &str = "compact_tier2_dep_levels";
+/// Shell command that summarises an issue instead of `BuiltinSummarizer`.
+pub const CONFIG_SUMMARIZER_COMMAND: &str = "compact_summarizer_command";
+
+/// Longest tier 1 summary of the description, in characters.
+const TIER1_SUMMARY_CHARS: usize = 280;
+/// Longest tier 1 resolution line taken from the last comment.
+const TIER1_RESOLUTION_CHARS: usize = 200;
+/// Longest tier 2 summary, in characters.
+const TIER2_SUMMARY_CHARS: usize = 120;
+
+/// When issues become eligible for each tier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionConfig {
+    pub tier1_days: i64,
+    /// How many levels of dependents are searched for open work.
+    pub tier1_dep_levels: usize,
+    pub tier2_days: i64,
+    pub tier2_dep_levels: usize,
+}
+
+impl Default for CompactionConfig {
+    /// The Go bd's defaults.
+    fn default() -> Self {
+        CompactionConfig {
+            tier1_days: 30,
+            tier1_dep_levels: 2,
+            tier2_days: 90,
+            tier2_dep_levels: 5,
+        }
+    }
+}
+
+impl CompactionConfig {
+    /// Reads the config keys through `get`, keeping defaults for values that
+    /// are unset or do not parse.
+    pub fn from_config(get: impl Fn(&str) -> Result<Option<String>>) -> Result<Self> {
+        let mut config = CompactionConfig::default();
+        if let Some(days) = parse_config_value(&get, CONFIG_TIER1_DAYS)? {
+            config.tier1_days = days;
+        }
+        if let Some(levels) = parse_config_value(&get, CONFIG_TIER1_DEP_LEVELS)? {
+            config.tier1_dep_levels = levels;
+        }
+        if let Some(days) = parse_config_value(&get, CONFIG_TIER2_DAYS)? {
+            config.tier2_days = days;
+        }
+        if let Some(levels) = parse_config_value(&get, CONFIG_TIER2_DEP_LEVELS)? {
+            config.tier2_dep_levels = levels;
+        }
+        Ok(config)
+    }
+
+    fn days(&self, tier: i32) -> i64 {
+        if tier == 1 {
+            self.tier1_days
+        } else {
+            self.tier2_days
+        }
+    }
+
+    fn dep_levels(&self, tier: i32) -> usize {
+        if tier == 1 {
+            self.tier1_dep_levels
+        } else {
+            self.tier2_dep_levels
+        }
+    }
+}
+
+/// Writes the compacted text of an issue.
+pub trait Summarizer {
+    /// Summary that replaces the description of `issue` at `tier` (1 or 2).
+    fn summarize(&self, issue: &Issue, tier: i32) -> Result<String>;
+}
+
+/// Deterministic summariser that needs nothing outside bd. Tier 1 keeps the
+/// first paragraph of the description and the first line of the last
+/// comment as the resolution; tier 2 keeps the first line of that.
+pub struct BuiltinSummarizer;
+
+impl Summarizer for BuiltinSummarizer {
+    fn summarize(&self, issue: &Issue, tier: i32) -> Result<String> {
+        let description = issue.description.trim();
+        let source = if description.is_empty() {
+            issue.title.trim()
+        } else {
+            description
+        };
+
+        if tier >= 2 {
+            let line = source.lines().next().unwrap_or_default();
+            return Ok(truncate(line, TIER2_SUMMARY_CHARS));
+        }
+
+        let paragraph = source.split("\n\n").next().unwrap_or_default();
+        let mut summary = truncate(paragraph, TIER1_SUMMARY_CHARS);
+        let resolution = issue
+            .comments
+            .last()
+            .and_then(|c| c.text.trim().lines().next())
+            .map(|line| truncate(line, TIER1_RESOLUTION_CHARS));
+        if let Some(resolution) = resolution.filter(|r| !r.is_empty()) {
+            summary.push_str("\n\nResolution: ");
+            summary.push_str(&resolution);
+        }
+        Ok(summary)
+    }
+}
+
+/// Runs a shell command with the issue as JSON on stdin and `BD_COMPACT_TIER`
+/// in its environment, and uses its trimmed stdout as the summary. Falls back
+/// to `BuiltinSummarizer`, with a warning, when the command fails.
+pub struct CommandSummarizer {
+    pub command: String,
+}
+
+impl CommandSummarizer {
+    fn run(&self, issue: &Issue, tier: i32) -> Result<String> {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let mut child = Command::new(shell)
+            .args([flag, &self.command])
+            .env("BD_COMPACT_TIER", tier.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run summarizer '{}'", self.command))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // A command that ignores its input may exit before reading it
+            match stdin.write_all(serde_json::to_string(issue)?.as_bytes()) {
+                Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+                _ => {}
+            }
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "summarizer '{}' failed: {}",
+                self.command,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let summary = String::from_utf8(output.stdout)?.trim().to_string();
+        if summary.is_empty() {
+            anyhow::bail!("summarizer '{}' printed nothing", self.command);
+        }
+        Ok(summary)
+    }
+}
+
+impl Summarizer for CommandSummarizer {
+    fn summarize(&self, issue: &Issue, tier: i32) -> Result<String> {
+        self.run(issue, tier).or_else(|e| {
+            tracing::warn!("{:#}; using the built-in summary for {}", e, issue.id);
+            BuiltinSummarizer.summarize(issue, tier)
+        })
+    }
+}
+
+/// The summariser configured through `compact_summarizer_command`, or
+/// `BuiltinSummarizer` if none is.
+pub fn configured_summarizer(store: &impl Store) -> Result<Box<dyn Summarizer>> {
+    Ok(match store.get_config(CONFIG_SUMMARIZER_COMMAND)? {
+        Some(command) if !command.trim().is_empty() => Box::new(CommandSummarizer { command }),
+        _ => Box::new(BuiltinSummarizer),
+    })
+}
+
+/// Outcome of compacting one issue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compacted {
+    pub id: String,
+    pub tier: i32,
+    /// Size of the text fields and comments before this compaction.
+    pub size_before: usize,
+    pub size_after: usize,
+}
+
+/// Bytes of text that compaction can reclaim: the text fields and comments.
+pub fn compactable_size(issue: &Issue) -> usize {
+    issue.description.len()
+        + issue.design.len()
+        + issue.acceptance_criteria.len()
+        + issue.notes.len()
+        + issue.comments.iter().map(|c| c.text.len()).sum::<usize>()
+}
+
+/// Closed issues eligible for `tier` as of `now`, oldest closed first, with
+/// their comments.
+pub fn candidates(
+    store: &impl Store,
+    tier: i32,
+    config: &CompactionConfig,
+    now: DateTime<Utc>,
+) -> Result<Vec<Issue>> {
+    check_tier(tier)?;
+    let references = References::load(store)?;
+    let mut candidates = Vec::new();
+    for issue in store.list_issues(&IssueFilter::default())? {
+        if issue.status != STATUS_CLOSED
+            || ineligible_reason(&references, &issue, tier, config, now, false).is_some()
+        {
+            continue;
+        }
+        if let Some(issue) = store.get_issue(&issue.id)? {
+            candidates.push(issue);
+        }
+    }
+    candidates.sort_by(|a, b| a.closed_at.cmp(&b.closed_at).then(a.id.cmp(&b.id)));
+    Ok(candidates)
+}
+
+/// Why `issue` cannot be compacted at `tier`, or `None` if it can. `force`
+/// skips the age and open-dependent checks but never the status and level
+/// ones.
+pub fn check_eligibility(
+    store: &impl Store,
+    issue: &Issue,
+    tier: i32,
+    config: &CompactionConfig,
+    now: DateTime<Utc>,
+    force: bool,
+) -> Result<Option<String>> {
+    check_tier(tier)?;
+    let references = References::load(store)?;
+    Ok(ineligible_reason(
+        &references,
+        issue,
+        tier,
+        config,
+        now,
+        force,
+    ))
+}
+
+/// Replaces the text of issue `id` with its `tier` summary, clearing design,
+/// acceptance criteria, notes and comments, and records the compaction.
+/// `commit` is where `HEAD` is now; the first compaction's commit is kept so
+/// that `restore_original` finds the uncompacted issue. Fails without writing
+/// anything if the summary would not make the issue smaller.
+pub fn compact_issue(
+    store: &impl Store,
+    id: &str,
+    tier: i32,
+    summarizer: &dyn Summarizer,
+    commit: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Compacted> {
+    check_tier(tier)?;
+    let issue = store
+        .get_issue(id)?
+        .with_context(|| format!("issue {} not found", id))?;
+    let summary = summarizer.summarize(&issue, tier)?;
+    let size_before = compactable_size(&issue);
+    if summary.len() >= size_before {
+        anyhow::bail!(
+            "summary of {} would not shrink it ({} >= {} bytes)",
+            issue.id,
+            summary.len(),
+            size_before
+        );
+    }
+
+    let mut compacted = issue.clone();
+    compacted.description = summary;
+    compacted.design.clear();
+    compacted.acceptance_criteria.clear();
+    compacted.notes.clear();
+    compacted.comments.clear();
+    compacted.compaction_level = tier;
+    compacted.compacted_at = Some(now);
+    if compacted.compacted_at_commit.is_none() {
+        compacted.compacted_at_commit = commit.map(str::to_string);
+    }
+    if compacted.original_size == 0 {
+        compacted.original_size = size_before as i32;
+    }
+    compacted.updated_at = now;
+    store.apply_compaction(&compacted)?;
+
+    Ok(Compacted {
+        id: issue.id.clone(),
+        tier,
+        size_before,
+        size_after: compactable_size(&compacted),
+    })
+}
+
+/// The uncompacted version of `issue`, read from `jsonl_path` as it was at the
+/// commit recorded when it was first compacted.
+pub fn restore_original(
+    git: &impl GitOps,
+    git_root: &Path,
+    jsonl_path: &Path,
+    issue: &Issue,
+) -> Result<Issue> {
+    let commit = match &issue.compacted_at_commit {
+        Some(commit) if !commit.is_empty() => commit,
+        _ => anyhow::bail!("issue {} is not compacted (no git commit saved)", issue.id),
+    };
+    let rel_path = jsonl_path
+        .strip_prefix(git_root)
+        .with_context(|| {
+            format!(
+                "{} is not inside the git repository at {}",
+                jsonl_path.display(),
+                git_root.display()
+            )
+        })?
+        .to_string_lossy()
+        .replace('\\', "/");
+    let contents = git
+        .show(&format!("{}:{}", commit, rel_path))
+        .with_context(|| format!("reading {} at commit {}", rel_path, commit))?;
+    store::parse_jsonl(&contents)?
+        .into_iter()
+        .find(|i| i.id == issue.id)
+        .with_context(|| {
+            format!(
+                "issue {} not found in {} at commit {}",
+                issue.id, rel_path, commit
+            )
+        })
+}
+
+fn check_tier(tier: i32) -> Result<()> {
+    if !(1..=2).contains(&tier) {
+        anyhow::bail!("invalid compaction tier {} (use 1 or 2)", tier);
+    }
+    Ok(())
+}
+
+/// Who depends on whom, and which issues are still open work.
+struct References {
+    graph: DependencyGraph,
+    statuses: HashMap<String, String>,
+}
+
+impl References {
+    fn load(store: &impl Store) -> Result<Self> {
+        let statuses = store
+            .list_issues(&IssueFilter::default())?
+            .into_iter()
+            .map(|i| (i.id, i.status))
+            .collect();
+        Ok(References {
+            graph: store.dependency_graph()?,
+            statuses,
+        })
+    }
+
+    /// An open issue that depends on `id`, directly or through at most
+    /// `levels` hops of dependents.
+    fn open_dependent(&self, id: &str, levels: usize) -> Option<&str> {
+        let mut seen = HashSet::from([id]);
+        let mut frontier = vec![id];
+        for _ in 0..levels.max(1) {
+            let mut next = Vec::new();
+            for current in frontier {
+                for dep in self.graph.dependents_of(current) {
+                    let dependent = dep.issue_id.as_str();
+                    if !seen.insert(dependent) {
+                        continue;
+                    }
+                    if self
+                        .statuses
+                        .get(dependent)
+                        .is_some_and(|s| graph::is_active_status(s))
+                    {
+                        return Some(dependent);
+                    }
+                    next.push(dependent);
+                }
+            }
+            frontier = next;
+        }
+        None
+    }
+}
+
+fn ineligible_reason(
+    references: &References,
+    issue: &Issue,
+    tier: i32,
+    config: &CompactionConfig,
+    now: DateTime<Utc>,
+    force: bool,
+) -> Option<String> {
+    if issue.status != STATUS_CLOSED {
+        return Some(format!("{} is not closed", issue.id));
+    }
+    if issue.compaction_level >= tier {
+        return Some(format!(
+            "{} is already compacted (level {})",
+            issue.id, issue.compaction_level
+        ));
+    }
+    if issue.compaction_level < tier - 1 {
+        return Some(format!(
+            "{} must be compacted at tier {} first",
+            issue.id,
+            tier - 1
+        ));
+    }
+    if force {
+        return None;
+    }
+
+    let days = config.days(tier);
+    let cutoff = now - Duration::days(days);
+    if issue.closed_at.is_none_or(|closed| closed > cutoff) {
+        return Some(format!(
+            "{} was closed less than {} days ago",
+            issue.id, days
+        ));
+    }
+    references
+        .open_dependent(&issue.id, config.dep_levels(tier))
+        .map(|open| format!("{} is still referenced by open issue {}", issue.id, open))
+}
+
+/// `text` trimmed and cut to at most `max_chars` characters, ending in `…`
+/// when shortened.
+fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    cut.truncate(cut.trim_end().len());
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Comment;
+
+    fn closed_issue(description: &str) -> Issue {
+        Issue {
+            id: "bd-1".to_string(),
+            title: "Crash on save".to_string(),
+            description: description.to_string(),
+            status: STATUS_CLOSED.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_builtin_summarizer() -> Result<()> {
+        let mut issue =
+            closed_issue("Saving a file with a long name crashes.\n\nSteps:\n1. open\n2. save");
+        issue.comments.push(Comment {
+            id: 1,
+            issue_id: "bd-1".to_string(),
+            author: "alice".to_string(),
+            text: "Fixed by truncating names.\nSee PR 12.".to_string(),
+            created_at: Utc::now(),
+        });
+
+        let tier1 = BuiltinSummarizer.summarize(&issue, 1)?;
+        assert_eq!(
+            tier1,
+            "Saving a file with a long name crashes.\n\nResolution: Fixed by truncating names."
+        );
+        issue.description = tier1;
+        issue.comments.clear();
+        assert_eq!(
+            BuiltinSummarizer.summarize(&issue, 2)?,
+            "Saving a file with a long name crashes."
+        );
+
+        // Long text is cut on a character boundary
+        let long = closed_issue(&"é".repeat(400));
+        let summary = BuiltinSummarizer.summarize(&long, 1)?;
+        assert_eq!(summary.chars().count(), TIER1_SUMMARY_CHARS);
+        assert!(summary.ends_with('…'));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compaction_config_from_config() -> Result<()> {
+        let config = CompactionConfig::from_config(|key| {
+            Ok(match key {
+                CONFIG_TIER1_DAYS => Some("7".to_string()),
+                CONFIG_TIER2_DAYS => Some("soon".to_string()),
+                _ => None,
+            })
+        })?;
+        assert_eq!(config.tier1_days, 7);
+        assert_eq!(config.tier2_days, 90);
+        assert_eq!(config.tier1_dep_levels, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use crate::models::{Issue, STATUS_TOMBSTONE};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// Field to order listed issues by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortField {
+    /// Newest first.
+    #[default]
+    Created,
+    /// Most recently updated first.
+    Updated,
+    /// Most recently closed first; open issues last.
+    Closed,
+    /// Most urgent (P0) first, then newest.
+    Priority,
+    Id,
+    Title,
+}
+
+impl FromStr for SortField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "created" => SortField::Created,
+            "updated" => SortField::Updated,
+            "closed" => SortField::Closed,
+            "priority" => SortField::Priority,
+            "id" => SortField::Id,
+            "title" => SortField::Title,
+            other => anyhow::bail!(
+                "invalid sort field '{}': use created, updated, closed, priority, id or title",
+                other
+            ),
+        })
+    }
+}
+
+/// Criteria shared by the listing and search APIs. Unset fields match
+/// everything; tombstones are skipped unless `statuses` asks for them.
+#[derive(Debug, Clone, Default)]
+pub struct IssueFilter {
+    /// Status must be one of these.
+    pub statuses: Vec<String>,
+    /// Status must be none of these.
+    pub exclude_statuses: Vec<String>,
+    /// Exact assignee, or "unassigned" for issues without one.
+    pub assignee: Option<String>,
+    pub issue_type: Option<String>,
+    /// Type must be none of these.
+    pub exclude_types: Vec<String>,
+    /// Issue must carry every one of these labels.
+    pub labels: Vec<String>,
+    /// Issue must carry at least one of these labels.
+    pub labels_any: Vec<String>,
+    /// Inclusive priority bounds.
+    pub priority_min: Option<i32>,
+    pub priority_max: Option<i32>,
+    /// Inclusive timestamp bounds. Closed bounds skip issues that were never closed.
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    pub closed_after: Option<DateTime<Utc>>,
+    pub closed_before: Option<DateTime<Utc>>,
+    pub id_prefix: Option<String>,
+    /// Case-insensitive substring of the title or description.
+    pub text: Option<String>,
+    pub sort: SortField,
+    /// Flip the natural order of `sort`.
+    pub reverse: bool,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+impl IssueFilter {
+    /// Whether `issue` (with its labels loaded) passes every criterion.
+    /// Ordering and paging are left to `sort_and_page`.
+    pub fn matches(&self, issue: &Issue) -> bool {
+        if self.statuses.is_empty() {
+            if issue.status == STATUS_TOMBSTONE {
+                return false;
+            }
+        } else if !self.statuses.contains(&issue.status) {
+            return false;
+        }
+        if self.exclude_statuses.contains(&issue.status) {
+            return false;
+        }
+        match self.assignee.as_deref() {
+            Some("unassigned") if issue.assignee.as_deref().is_some_and(|a| !a.is_empty()) => {
+                return false
+            }
+            Some("unassigned") | None => {}
+            Some(assignee) if issue.assignee.as_deref() != Some(assignee) => return false,
+            Some(_) => {}
+        }
+        if self
+            .issue_type
+            .as_ref()
+            .is_some_and(|t| &issue.issue_type != t)
+        {
+            return false;
+        }
+        if self.exclude_types.contains(&issue.issue_type) {
+            return false;
+        }
+        if !self.labels.iter().all(|l| issue.labels.contains(l)) {
+            return false;
+        }
+        if !self.labels_any.is_empty() && !self.labels_any.iter().any(|l| issue.labels.contains(l))
+        {
+            return false;
+        }
+        if self.priority_min.is_some_and(|p| issue.priority < p)
+            || self.priority_max.is_some_and(|p| issue.priority > p)
+        {
+            return false;
+        }
+        if !in_range(
+            Some(issue.created_at),
+            self.created_after,
+            self.created_before,
+        ) || !in_range(
+            Some(issue.updated_at),
+            self.updated_after,
+            self.updated_before,
+        ) || !in_range(issue.closed_at, self.closed_after, self.closed_before)
+        {
+            return false;
+        }
+        if self
+            .id_prefix
+            .as_ref()
+            .is_some_and(|p| !issue.id.starts_with(p.as_str()))
+        {
+            return false;
+        }
+        if let Some(text) = &self.text {
+            let text = text.to_ascii_lowercase();
+            if !issue.title.to_ascii_lowercase().contains(&text)
+                && !issue.description.to_ascii_lowercase().contains(&text)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Orders matching issues by `sort`/`reverse` and applies `offset`/`limit`.
+    pub fn sort_and_page(&self, issues: &mut Vec<Issue>) {
+        issues.sort_by(|a, b| {
+            let ordering = self.compare(a, b);
+            if self.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        issues.drain(..self.offset.min(issues.len()));
+        if let Some(limit) = self.limit {
+            issues.truncate(limit);
+        }
+    }
+
+    fn compare(&self, a: &Issue, b: &Issue) -> Ordering {
+        let newest_first = b.created_at.cmp(&a.created_at);
+        match self.sort {
+            SortField::Created => newest_first,
+            SortField::Updated => b.updated_at.cmp(&a.updated_at),
+            // None sorts before Some, so compare b to a to put open issues last
+            SortField::Closed => b.closed_at.cmp(&a.closed_at),
+            SortField::Priority => a.priority.cmp(&b.priority).then(newest_first),
+            SortField::Id => a.id.cmp(&b.id),
+            SortField::Title => a.title.cmp(&b.title),
+        }
+        .then_with(|| a.id.cmp(&b.id))
+    }
+}
+
+fn in_range(
+    value: Option<DateTime<Utc>>,
+    after: Option<DateTime<Utc>>,
+    before: Option<DateTime<Utc>>,
+) -> bool {
+    if after.is_none() && before.is_none() {
+        return true;
+    }
+    match value {
+        Some(value) => after.is_none_or(|a| value >= a) && before.is_none_or(|b| value <= b),
+        None => false,
+    }
+}
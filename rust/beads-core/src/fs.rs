@@ -1,4 +1,5 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use std::io::{BufRead, Write};
 use std::path::Path;
 
@@ -10,6 +11,15 @@ pub trait FileSystem {
     fn exists(&self, path: &Path) -> bool;
     fn open_read(&self, path: &Path) -> Result<Box<dyn BufRead>>;
     fn open_write(&self, path: &Path) -> Result<Box<dyn Write>>;
+    /// Last modification time, or `None` where the platform cannot tell.
+    fn modified(&self, _path: &Path) -> Result<Option<DateTime<Utc>>> {
+        Ok(None)
+    }
+    /// Names of the files in a directory, or none where the platform cannot
+    /// list them.
+    fn list_dir(&self, _path: &Path) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -50,4 +60,20 @@ impl FileSystem for StdFileSystem {
         let file = std::fs::File::create(path)?;
         Ok(Box::new(file))
     }
+
+    fn modified(&self, path: &Path) -> Result<Option<DateTime<Utc>>> {
+        Ok(Some(std::fs::metadata(path)?.modified()?.into()))
+    }
+
+    fn list_dir(&self, path: &Path) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
 }
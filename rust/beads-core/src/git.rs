@@ -2,6 +2,11 @@ use anyhow::{bail, Context, Result};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Config key choosing how git is run: `cli` (the default) spawns the `git`
+/// binary, `gix` runs it in-process (see `GixGit`; experimental, and only
+/// for repositories whose remotes are on the local filesystem).
+pub const CONFIG_GIT_BACKEND: &str = "git_backend";
+
 pub trait GitOps {
     fn init(&self) -> Result<()>;
     fn add(&self, path: &Path) -> Result<()>;
@@ -12,6 +17,70 @@ pub trait GitOps {
     fn show(&self, revision: &str) -> Result<String>;
     fn rebase_continue(&self) -> Result<()>;
     fn has_remote(&self) -> Result<bool>;
+    /// Commit `HEAD` points at, or `None` before the first commit.
+    fn head_commit(&self) -> Result<Option<String>>;
+    /// The git directory shared by every worktree of the repository, as
+    /// `git rev-parse --git-common-dir` prints it but absolute.
+    fn common_dir(&self) -> Result<PathBuf>;
+    /// Ops on the worktree at `path` with `branch` checked out, creating it
+    /// as needed with only `sparse_dir` populated. A new branch starts from
+    /// the remote's copy; if the remote has none, it starts from `HEAD` and
+    /// is pushed there.
+    fn worktree(&self, path: &Path, branch: &str, sparse_dir: &Path) -> Result<Box<dyn GitOps>>;
+}
+
+impl<G: GitOps + ?Sized> GitOps for Box<G> {
+    fn init(&self) -> Result<()> {
+        (**self).init()
+    }
+    fn add(&self, path: &Path) -> Result<()> {
+        (**self).add(path)
+    }
+    fn commit(&self, message: &str) -> Result<()> {
+        (**self).commit(message)
+    }
+    fn pull_rebase(&self) -> Result<()> {
+        (**self).pull_rebase()
+    }
+    fn push(&self) -> Result<()> {
+        (**self).push()
+    }
+    fn status(&self) -> Result<String> {
+        (**self).status()
+    }
+    fn show(&self, revision: &str) -> Result<String> {
+        (**self).show(revision)
+    }
+    fn rebase_continue(&self) -> Result<()> {
+        (**self).rebase_continue()
+    }
+    fn has_remote(&self) -> Result<bool> {
+        (**self).has_remote()
+    }
+    fn head_commit(&self) -> Result<Option<String>> {
+        (**self).head_commit()
+    }
+    fn common_dir(&self) -> Result<PathBuf> {
+        (**self).common_dir()
+    }
+    fn worktree(&self, path: &Path, branch: &str, sparse_dir: &Path) -> Result<Box<dyn GitOps>> {
+        (**self).worktree(path, branch, sparse_dir)
+    }
+}
+
+/// The backend configured through `git_backend` for the repository at `root`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn configured(store: &impl crate::store::Store, root: &Path) -> Result<Box<dyn GitOps>> {
+    let backend = store.get_config(CONFIG_GIT_BACKEND)?.unwrap_or_default();
+    Ok(match backend.trim() {
+        "" | "cli" => Box::new(StdGit::new(root)),
+        "gix" => {
+            let git = crate::gix_git::GixGit::new(root);
+            git.check_remotes()?;
+            Box::new(git)
+        }
+        other => bail!("invalid {} '{}': use cli or gix", CONFIG_GIT_BACKEND, other),
+    })
 }
 
 pub struct StdGit {
@@ -31,6 +100,23 @@ impl StdGit {
         cmd.args(args);
         cmd
     }
+
+    /// Runs `git args` and returns its stdout, failing if git does.
+    fn run(&self, args: &[&str]) -> Result<String> {
+        let output = self
+            .command(args)
+            .output()
+            .with_context(|| format!("Failed to run git {}", args[0]))?;
+
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
 }
 
 impl GitOps for StdGit {
@@ -176,4 +262,67 @@ impl GitOps for StdGit {
         let stdout = String::from_utf8_lossy(&output.stdout);
         Ok(!stdout.trim().is_empty())
     }
+
+    fn head_commit(&self) -> Result<Option<String>> {
+        let output = self
+            .command(&["rev-parse", "--verify", "-q", "HEAD"])
+            .output()
+            .context("Failed to run git rev-parse")?;
+
+        if !output.status.success() {
+            return Ok(None);
+        }
+        let hash = String::from_utf8(output.stdout)?.trim().to_string();
+        Ok((!hash.is_empty()).then_some(hash))
+    }
+
+    fn common_dir(&self) -> Result<PathBuf> {
+        let dir = self.run(&["rev-parse", "--path-format=absolute", "--git-common-dir"])?;
+        Ok(PathBuf::from(dir.trim_end_matches(['\r', '\n'])))
+    }
+
+    fn worktree(&self, path: &Path, branch: &str, sparse_dir: &Path) -> Result<Box<dyn GitOps>> {
+        let worktree = StdGit::new(path);
+        if path.join(".git").exists() {
+            return Ok(Box::new(worktree));
+        }
+        // Forget worktrees whose directories were deleted.
+        self.run(&["worktree", "prune"])?;
+
+        let local_ref = format!("refs/heads/{}", branch);
+        let mut publish = false;
+        if self
+            .run(&["rev-parse", "--verify", "-q", &local_ref])
+            .is_err()
+        {
+            let remote = self.run(&["remote"])?.lines().next().map(str::to_string);
+            match remote {
+                Some(remote) => {
+                    let tracking = format!("{}/{}", remote, branch);
+                    let refspec = format!("{}:refs/remotes/{}", local_ref, tracking);
+                    if self.run(&["fetch", &remote, &refspec]).is_ok() {
+                        self.run(&["branch", "--track", branch, &tracking])?;
+                    } else {
+                        self.run(&["branch", branch, "HEAD"])?;
+                        self.run(&["config", &format!("branch.{}.remote", branch), &remote])?;
+                        self.run(&["config", &format!("branch.{}.merge", branch), &local_ref])?;
+                        publish = true;
+                    }
+                }
+                None => {
+                    self.run(&["branch", branch, "HEAD"])?;
+                }
+            }
+        }
+
+        let path_s = path.to_string_lossy();
+        self.run(&["worktree", "add", "--no-checkout", &path_s, branch])?;
+        let pattern = format!("/{}/", sparse_dir.to_string_lossy().replace('\\', "/"));
+        worktree.run(&["sparse-checkout", "set", "--no-cone", &pattern])?;
+        worktree.run(&["read-tree", "-mu", "HEAD"])?;
+        if publish {
+            worktree.push()?;
+        }
+        Ok(Box::new(worktree))
+    }
 }
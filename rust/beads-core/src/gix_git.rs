@@ -0,0 +1,1437 @@
+//! `GitOps` run in-process through gitoxide, without spawning `git`.
+//!
+//! Experimental: there is no network transport, so remotes must be
+//! repositories on the local filesystem (a path or a `file://` URL), and
+//! `check_remotes` refuses any other. Fetch and push copy objects between
+//! the two object databases directly. `pull_rebase` replays local commits
+//! onto the upstream branch with a three-way merge by file, then by line for
+//! files changed on both sides. Lines changed differently on both sides stop
+//! the rebase the way git does, with HEAD detached, the file's versions in
+//! index stages 1 to 3 and the rebase state in `.git/rebase-merge/`;
+//! `rebase_continue` picks it up from there. `worktree` lays out linked
+//! worktrees as `git worktree add` does; only sparse patterns of the form
+//! `/dir/` are understood.
+
+use crate::git::GitOps;
+use anyhow::{anyhow, bail, Context, Result};
+use gix::bstr::{BStr, BString, ByteSlice};
+use gix::index::entry::{Flags, Mode, Stage, Stat};
+use gix::objs::tree::{EntryKind, EntryMode};
+use gix::prelude::Write as _;
+use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+use gix::refs::{FullName, Target};
+use gix::remote::Direction;
+use gix::{ObjectId, Repository};
+use imara_diff::intern::InternedInput;
+use imara_diff::sources::byte_lines_with_terminator;
+use imara_diff::Algorithm;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Where a stopped rebase keeps its state, relative to the git directory.
+const REBASE_DIR: &str = "rebase-merge";
+
+/// A file in a tree: its mode and blob.
+type File = (EntryMode, ObjectId);
+/// The files of a tree, by path.
+type Files = BTreeMap<BString, File>;
+
+pub struct GixGit {
+    root: PathBuf,
+}
+
+impl GixGit {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Fails if a remote of the repository at the root, if there is one,
+    /// is not on the local filesystem, so `sync` stops before committing
+    /// rather than at the pull.
+    pub fn check_remotes(&self) -> Result<()> {
+        let Ok(repo) = self.open() else {
+            return Ok(());
+        };
+        for name in repo.remote_names() {
+            let remote = repo.find_remote(name.as_ref())?;
+            for direction in [Direction::Fetch, Direction::Push] {
+                if let Some(url) = remote
+                    .url(direction)
+                    .filter(|url| url.scheme != gix::url::Scheme::File)
+                {
+                    bail!(
+                        "{} gix is experimental and local-only: it cannot reach remote '{}' at {}, set {} to cli",
+                        crate::git::CONFIG_GIT_BACKEND,
+                        name,
+                        url.to_bstring(),
+                        crate::git::CONFIG_GIT_BACKEND
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Repository> {
+        gix::discover(&self.root)
+            .with_context(|| format!("not a git repository: {}", self.root.display()))
+    }
+
+    /// `path` (absolute, or relative to the root) relative to the worktree.
+    fn repo_path(&self, repo: &Repository, path: &Path) -> Result<BString> {
+        let workdir = fs::canonicalize(workdir(repo)?)?;
+        let path = self.root.join(path);
+        let name = path
+            .file_name()
+            .with_context(|| format!("invalid path: {}", path.display()))?;
+        let parent = path.parent().unwrap_or(Path::new("."));
+        let full = fs::canonicalize(parent)
+            .with_context(|| format!("no such directory: {}", parent.display()))?
+            .join(name);
+        let relative = full
+            .strip_prefix(&workdir)
+            .with_context(|| format!("{} is outside repository", path.display()))?;
+        Ok(gix::path::to_unix_separators_on_windows(gix::path::into_bstr(relative)).into_owned())
+    }
+}
+
+impl GitOps for GixGit {
+    fn init(&self) -> Result<()> {
+        if gix::open(&self.root).is_ok() {
+            return Ok(());
+        }
+        gix::init(&self.root).context("git init failed")?;
+        Ok(())
+    }
+
+    fn add(&self, path: &Path) -> Result<()> {
+        let repo = self.open()?;
+        let name = self.repo_path(&repo, path)?;
+        let full = workdir(&repo)?.join(gix::path::from_bstr(name.as_bstr()));
+        let mut index = load_index(&repo)?;
+        let tracked = index
+            .entries()
+            .iter()
+            .any(|e| e.path(&index) == name.as_bstr());
+        index.remove_entries(|_, p, _| p == name.as_bstr());
+        match fs::symlink_metadata(&full) {
+            Ok(meta) if meta.is_file() => {
+                let id = repo.write_blob(fs::read(&full)?)?.detach();
+                let stat = Stat::from_fs(&gix::index::fs::Metadata::from_path_no_follow(&full)?)?;
+                let mode = if is_executable(&meta) {
+                    Mode::FILE_EXECUTABLE
+                } else {
+                    Mode::FILE
+                };
+                index.dangerously_push_entry(stat, id, Flags::empty(), mode, name.as_bstr());
+                index.sort_entries();
+            }
+            Ok(_) => bail!("git add failed: {} is not a regular file", path.display()),
+            Err(_) if tracked => {}
+            Err(_) => bail!(
+                "git add failed: pathspec '{}' did not match any files",
+                path.display()
+            ),
+        }
+        write_index(&mut index)
+    }
+
+    fn commit(&self, message: &str) -> Result<()> {
+        let repo = self.open()?;
+        let index = load_index(&repo)?;
+        if has_conflicts(&index) {
+            bail!("git commit failed: you have unmerged paths");
+        }
+        let files = index_files(&index);
+        let head = head_id(&repo)?;
+        let head_files = match head {
+            Some(id) => commit_files(&repo, id)?,
+            None => Files::new(),
+        };
+        if files == head_files {
+            // Nothing to commit, like `git commit` on a clean tree.
+            return Ok(());
+        }
+        let tree = write_tree(&repo, &files)?;
+        repo.commit("HEAD", message, tree, head)
+            .context("git commit failed")?;
+        Ok(())
+    }
+
+    fn pull_rebase(&self) -> Result<()> {
+        let repo = self.open()?;
+        if repo.git_dir().join(REBASE_DIR).exists() {
+            bail!("git pull --rebase failed: a rebase is already in progress");
+        }
+        if !tracked_changes(&repo)?.is_empty() {
+            bail!("git pull --rebase failed: cannot pull with rebase: You have unstaged changes.");
+        }
+        let branch = current_branch(&repo)?;
+        let upstream = Upstream::of(&repo, &branch, Direction::Fetch)?;
+        let remote = upstream.open()?;
+        let Some(tip) = ref_id(&remote, &upstream.remote_ref)? else {
+            bail!(
+                "git pull --rebase failed: couldn't find remote ref {}",
+                upstream.remote_ref.as_bstr()
+            );
+        };
+        copy_objects(&remote, &repo, tip)?;
+        if let Some(tracking) = &upstream.tracking_ref {
+            repo.reference(tracking.clone(), tip, PreviousValue::Any, "pull --rebase")?;
+        }
+
+        let Some(head) = head_id(&repo)? else {
+            checkout(&repo, &Files::new(), &commit_files(&repo, tip)?)?;
+            repo.reference(branch, tip, PreviousValue::Any, "pull --rebase")?;
+            return Ok(());
+        };
+        if head == tip || is_ancestor(&repo, tip, head)? {
+            return Ok(());
+        }
+        let worktree = commit_files(&repo, head)?;
+        let state = Rebase {
+            head_name: branch,
+            onto: tip,
+            orig_head: head,
+            todo: commits_since(&repo, merge_base(&repo, head, tip)?, head)?,
+            done: Vec::new(),
+        };
+        state.replay(&repo, tip, worktree)
+    }
+
+    fn push(&self) -> Result<()> {
+        let repo = self.open()?;
+        let branch = current_branch(&repo)?;
+        let upstream = Upstream::of(&repo, &branch, Direction::Push)?;
+        let head = head_id(&repo)?.context("git push failed: no commits to push")?;
+        let remote = upstream.open()?;
+        let remote_tip = ref_id(&remote, &upstream.remote_ref)?;
+        if remote_tip == Some(head) {
+            return Ok(());
+        }
+        if let Some(tip) = remote_tip {
+            if !repo.has_object(tip) || !is_ancestor(&repo, tip, head)? {
+                bail!(
+                    "git push failed: updates were rejected because the remote contains work that you do not have locally; pull first"
+                );
+            }
+        }
+        if remote.work_dir().is_some() && remote.head_name()?.as_ref() == Some(&upstream.remote_ref)
+        {
+            bail!(
+                "git push failed: refusing to update checked out branch {}",
+                upstream.remote_ref.as_bstr()
+            );
+        }
+        copy_objects(&repo, &remote, head)?;
+        let expected = match remote_tip {
+            Some(tip) => PreviousValue::MustExistAndMatch(Target::Peeled(tip)),
+            None => PreviousValue::MustNotExist,
+        };
+        remote.reference(upstream.remote_ref.clone(), head, expected, "push")?;
+        if let Some(tracking) = &upstream.tracking_ref {
+            repo.reference(tracking.clone(), head, PreviousValue::Any, "update by push")?;
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> Result<String> {
+        let repo = self.open()?;
+        let mut lines = tracked_changes(&repo)?;
+        let index = load_index(&repo)?;
+        let tracked: BTreeSet<BString> = index
+            .entries()
+            .iter()
+            .map(|e| e.path(&index).to_owned())
+            .collect();
+        let mut excludes = repo.excludes(
+            &index,
+            None,
+            gix::worktree::stack::state::ignore::Source::WorktreeThenIdMappingIfNotSkipped,
+        )?;
+        let mut untracked = Vec::new();
+        find_untracked(
+            workdir(&repo)?,
+            BString::default(),
+            &tracked,
+            &mut excludes,
+            &mut untracked,
+        )?;
+        lines.extend(untracked.into_iter().map(|path| format!("?? {}", path)));
+        Ok(lines.into_iter().map(|line| line + "\n").collect())
+    }
+
+    fn show(&self, revision: &str) -> Result<String> {
+        let repo = self.open()?;
+        let invalid = || format!("git show failed: invalid object name '{}'", revision);
+        // `:<stage>:<path>` is looked up here: gix's rev-parse stops at stage 2.
+        let stage = match revision.as_bytes() {
+            [b':', b'1', b':', ..] => Some(Stage::Base),
+            [b':', b'2', b':', ..] => Some(Stage::Ours),
+            [b':', b'3', b':', ..] => Some(Stage::Theirs),
+            _ => None,
+        };
+        let object = match stage {
+            Some(stage) => {
+                let index = load_index(&repo)?;
+                let entry = index
+                    .entry_by_path_and_stage(revision[3..].into(), stage)
+                    .with_context(invalid)?;
+                repo.find_object(entry.id)?
+            }
+            None => repo
+                .rev_parse_single(revision)
+                .with_context(invalid)?
+                .object()?,
+        };
+        match object.kind {
+            gix::objs::Kind::Blob => Ok(String::from_utf8(object.detach().data)?),
+            gix::objs::Kind::Tree => {
+                let mut listing = format!("tree {}\n\n", revision);
+                for entry in object.into_tree().decode()?.entries {
+                    let slash = if entry.mode.is_tree() { "/" } else { "" };
+                    listing.push_str(&format!("{}{}\n", entry.filename, slash));
+                }
+                Ok(listing)
+            }
+            kind => bail!(
+                "git show failed: showing a {} is not supported in-process",
+                kind
+            ),
+        }
+    }
+
+    fn rebase_continue(&self) -> Result<()> {
+        let repo = self.open()?;
+        let mut state = Rebase::load(&repo)?;
+        let index = load_index(&repo)?;
+        if has_conflicts(&index) {
+            bail!("git rebase --continue failed: you must edit all merge conflicts and then mark them as resolved using git add");
+        }
+        let stopped = state
+            .todo
+            .first()
+            .copied()
+            .context("git rebase --continue failed: no commit to continue with")?;
+        let files = index_files(&index);
+        let mut current = head_id(&repo)?.context("git rebase --continue failed: no HEAD")?;
+        if files != commit_files(&repo, current)? {
+            let tree = write_tree(&repo, &files)?;
+            current = replay_commit(&repo, stopped, tree, current)?;
+        }
+        state.done.push(state.todo.remove(0));
+        state.replay(&repo, current, files)
+    }
+
+    fn has_remote(&self) -> Result<bool> {
+        Ok(!self.open()?.remote_names().is_empty())
+    }
+
+    fn head_commit(&self) -> Result<Option<String>> {
+        Ok(head_id(&self.open()?)?.map(|id| id.to_string()))
+    }
+
+    fn common_dir(&self) -> Result<PathBuf> {
+        let repo = self.open()?;
+        fs::canonicalize(repo.common_dir())
+            .with_context(|| format!("no git directory at {}", repo.common_dir().display()))
+    }
+
+    fn worktree(&self, path: &Path, branch: &str, sparse_dir: &Path) -> Result<Box<dyn GitOps>> {
+        let worktree = GixGit::new(path);
+        if path.join(".git").exists() {
+            return Ok(Box::new(worktree));
+        }
+        let repo = self.open()?;
+        let local: FullName = format!("refs/heads/{}", branch).try_into()?;
+        let mut config = String::new();
+        let mut publish = false;
+        let start = match ref_id(&repo, &local)? {
+            Some(id) => id,
+            None => {
+                let mut start = None;
+                if let Some(remote) = repo.remote_names().into_iter().next() {
+                    let remote = remote.to_string();
+                    let url =
+                        remote_path(&repo, &repo.find_remote(remote.as_str())?, Direction::Fetch)?;
+                    let remote_repo = gix::open(&url).with_context(|| {
+                        format!("cannot open remote repository {}", url.display())
+                    })?;
+                    start = ref_id(&remote_repo, &local)?;
+                    match start {
+                        Some(tip) => {
+                            copy_objects(&remote_repo, &repo, tip)?;
+                            let tracking = format!("refs/remotes/{}/{}", remote, branch);
+                            repo.reference(tracking, tip, PreviousValue::Any, "fetch")?;
+                        }
+                        None => publish = true,
+                    }
+                    config.push_str(&format!(
+                        "[branch \"{}\"]\n\tremote = {}\n\tmerge = {}\n",
+                        branch,
+                        remote,
+                        local.as_bstr()
+                    ));
+                }
+                let start = match start {
+                    Some(tip) => tip,
+                    None => head_id(&repo)?
+                        .context("cannot create a sync branch before the first commit")?,
+                };
+                repo.reference(
+                    local.clone(),
+                    start,
+                    PreviousValue::MustNotExist,
+                    "branch: Created",
+                )?;
+                start
+            }
+        };
+
+        // Register the worktree the way `git worktree add` does, with
+        // per-worktree config turning the sparse checkout on.
+        if repo.config_snapshot().boolean("extensions.worktreeConfig") != Some(true) {
+            config.push_str("[extensions]\n\tworktreeConfig = true\n");
+        }
+        let common = fs::canonicalize(repo.common_dir())?;
+        let name = path
+            .file_name()
+            .with_context(|| format!("invalid worktree path: {}", path.display()))?
+            .to_string_lossy()
+            .to_string();
+        let mut admin = common.join("worktrees").join(&name);
+        let mut n = 0;
+        while admin.exists() {
+            n += 1;
+            admin = common.join("worktrees").join(format!("{}{}", name, n));
+        }
+        fs::create_dir_all(admin.join("info"))?;
+        fs::create_dir_all(path)?;
+        let path = fs::canonicalize(path)?;
+        let sparse_dir =
+            gix::path::to_unix_separators_on_windows(gix::path::into_bstr(sparse_dir)).into_owned();
+        fs::write(admin.join("HEAD"), format!("ref: {}\n", local.as_bstr()))?;
+        fs::write(admin.join("commondir"), "../..\n")?;
+        fs::write(
+            admin.join("gitdir"),
+            format!("{}\n", path.join(".git").display()),
+        )?;
+        fs::write(
+            admin.join("info").join("sparse-checkout"),
+            format!("/{}/\n", sparse_dir),
+        )?;
+        fs::write(
+            admin.join("config.worktree"),
+            "[core]\n\tsparseCheckout = true\n\tsparseCheckoutCone = false\n",
+        )?;
+        fs::write(path.join(".git"), format!("gitdir: {}\n", admin.display()))?;
+        if !config.is_empty() {
+            let mut file = fs::OpenOptions::new()
+                .append(true)
+                .open(common.join("config"))?;
+            std::io::Write::write_all(&mut file, config.as_bytes())?;
+        }
+
+        let repo = worktree.open()?;
+        let files = commit_files(&repo, start)?;
+        checkout(&repo, &Files::new(), &files)?;
+        let mut index = index_of(&repo, &files)?;
+        index.sort_entries();
+        write_index(&mut index)?;
+        if publish {
+            worktree.push()?;
+        }
+        Ok(Box::new(worktree))
+    }
+}
+
+/// A rebase of `head_name` onto `onto`, with the commits still to replay.
+struct Rebase {
+    head_name: FullName,
+    onto: ObjectId,
+    orig_head: ObjectId,
+    todo: Vec<ObjectId>,
+    done: Vec<ObjectId>,
+}
+
+impl Rebase {
+    /// Replays the remaining commits on top of `current`, then moves the
+    /// branch there. `worktree` is what the worktree holds now. Stops with
+    /// an error at the first commit that conflicts.
+    fn replay(mut self, repo: &Repository, mut current: ObjectId, worktree: Files) -> Result<()> {
+        while let Some(&id) = self.todo.first() {
+            let commit = repo.find_object(id)?.try_into_commit()?;
+            let base = match commit.parent_ids().next() {
+                Some(parent) => commit_files(repo, parent.detach())?,
+                None => Files::new(),
+            };
+            let ours = commit_files(repo, current)?;
+            let theirs = commit_files(repo, id)?;
+            let (mut merged, changed_on_both) = merge_files(&base, &ours, &theirs);
+            let mut conflicts = Vec::new();
+            for path in changed_on_both {
+                match merge_file(repo, &path, [&base, &ours, &theirs])? {
+                    Some(file) => {
+                        merged.insert(path, file);
+                    }
+                    None => conflicts.push(path),
+                }
+            }
+            if !conflicts.is_empty() {
+                return self.stop(
+                    repo,
+                    current,
+                    &worktree,
+                    merged,
+                    &conflicts,
+                    [base, ours, theirs],
+                );
+            }
+            if merged != ours {
+                let tree = write_tree(repo, &merged)?;
+                current = replay_commit(repo, id, tree, current)?;
+            }
+            self.done.push(self.todo.remove(0));
+        }
+
+        let files = commit_files(repo, current)?;
+        checkout(repo, &worktree, &files)?;
+        let mut index = index_of(repo, &files)?;
+        index.sort_entries();
+        write_index(&mut index)?;
+        let message = format!("rebase (finish): returning to {}", self.head_name.as_bstr());
+        repo.reference(
+            self.head_name.clone(),
+            current,
+            PreviousValue::Any,
+            message.as_str(),
+        )?;
+        set_head(repo, Target::Symbolic(self.head_name), &message)?;
+        let dir = repo.git_dir().join(REBASE_DIR);
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Stops at the first commit of `todo`, which conflicts in `conflicts`
+    /// when replayed on `current`.
+    fn stop(
+        self,
+        repo: &Repository,
+        current: ObjectId,
+        worktree: &Files,
+        merged: Files,
+        conflicts: &[BString],
+        [base, ours, theirs]: [Files; 3],
+    ) -> Result<()> {
+        let id = self.todo[0];
+        let summary = commit_summary(repo, id)?;
+        set_head(repo, Target::Peeled(current), "rebase: conflict")?;
+        checkout(repo, worktree, &merged)?;
+
+        let mut index = index_of(repo, &merged)?;
+        let sparse = Sparse::load(repo)?;
+        let dir = workdir(repo)?;
+        for path in conflicts {
+            let sides = [
+                (&base, Stage::Base),
+                (&ours, Stage::Ours),
+                (&theirs, Stage::Theirs),
+            ];
+            for (files, stage) in sides {
+                if let Some((mode, id)) = files.get(path) {
+                    push_entry(repo, &sparse, &mut index, path.as_bstr(), *mode, *id, stage)?;
+                }
+            }
+            let read = |files: &Files| -> Result<Vec<u8>> {
+                match files.get(path) {
+                    Some((_, id)) => Ok(repo.find_object(*id)?.detach().data),
+                    None => Ok(Vec::new()),
+                }
+            };
+            let mut content = b"<<<<<<< HEAD\n".to_vec();
+            content.extend(with_newline(read(&ours)?));
+            content.extend_from_slice(b"=======\n");
+            content.extend(with_newline(read(&theirs)?));
+            content.extend(format!(">>>>>>> {} ({})\n", short(id), summary).into_bytes());
+            let full = dir.join(gix::path::from_bstr(path.as_bstr()));
+            if let Some(parent) = full.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(full, content)?;
+        }
+        index.sort_entries();
+        write_index(&mut index)?;
+        self.save(repo)?;
+
+        let paths: Vec<String> = conflicts.iter().map(|p| p.to_string()).collect();
+        bail!(
+            "git pull --rebase failed: could not apply {}... {}\nCONFLICT (content): Merge conflict in {}",
+            short(id),
+            summary,
+            paths.join(", ")
+        )
+    }
+
+    /// Writes the state to `.git/rebase-merge/`, in the layout git uses.
+    fn save(&self, repo: &Repository) -> Result<()> {
+        let dir = repo.git_dir().join(REBASE_DIR);
+        fs::create_dir_all(&dir)?;
+        let picks = |ids: &[ObjectId]| -> Result<String> {
+            let mut out = String::new();
+            for id in ids {
+                out.push_str(&format!("pick {} {}\n", id, commit_summary(repo, *id)?));
+            }
+            Ok(out)
+        };
+        let stopped = self.todo[0];
+        let mut done = self.done.clone();
+        done.push(stopped);
+        fs::write(
+            dir.join("head-name"),
+            format!("{}\n", self.head_name.as_bstr()),
+        )?;
+        fs::write(dir.join("onto"), format!("{}\n", self.onto))?;
+        fs::write(dir.join("orig-head"), format!("{}\n", self.orig_head))?;
+        fs::write(dir.join("stopped-sha"), format!("{}\n", stopped))?;
+        fs::write(dir.join("done"), picks(&done)?)?;
+        fs::write(dir.join("git-rebase-todo"), picks(&self.todo[1..])?)?;
+        let commit = repo.find_object(stopped)?.try_into_commit()?;
+        fs::write(dir.join("message"), commit.message_raw()?.as_bytes())?;
+        Ok(())
+    }
+
+    /// The state saved by `save`, with the stopped commit first in `todo`.
+    fn load(repo: &Repository) -> Result<Self> {
+        let dir = repo.git_dir().join(REBASE_DIR);
+        if !dir.exists() {
+            bail!("git rebase --continue failed: no rebase in progress");
+        }
+        let read = |name: &str| -> Result<String> {
+            Ok(fs::read_to_string(dir.join(name))
+                .with_context(|| format!("corrupt rebase state: missing {}", name))?
+                .trim()
+                .to_string())
+        };
+        let id = |hex: &str| -> Result<ObjectId> {
+            ObjectId::from_hex(hex.as_bytes())
+                .with_context(|| format!("corrupt rebase state: {}", hex))
+        };
+        let picks = |list: &str| -> Result<Vec<ObjectId>> {
+            list.lines()
+                .filter_map(|line| line.split_whitespace().nth(1))
+                .map(id)
+                .collect()
+        };
+        let mut done = picks(&read("done")?)?;
+        let stopped = id(&read("stopped-sha")?)?;
+        done.retain(|d| *d != stopped);
+        let mut todo = vec![stopped];
+        todo.extend(picks(&read("git-rebase-todo")?)?);
+        Ok(Self {
+            head_name: read("head-name")?.try_into()?,
+            onto: id(&read("onto")?)?,
+            orig_head: id(&read("orig-head")?)?,
+            todo,
+            done,
+        })
+    }
+}
+
+/// Where the current branch is fetched from or pushed to.
+struct Upstream {
+    /// Path of the remote repository.
+    path: PathBuf,
+    /// The branch in the remote repository.
+    remote_ref: FullName,
+    /// The local `refs/remotes/...` ref tracking it, if any.
+    tracking_ref: Option<FullName>,
+}
+
+impl Upstream {
+    fn of(repo: &Repository, branch: &FullName, direction: Direction) -> Result<Self> {
+        let short = branch.shorten();
+        let no_upstream = || anyhow!("there is no upstream branch configured for {}", short);
+        let remote = repo
+            .branch_remote(short, direction)
+            .ok_or_else(no_upstream)??;
+        let path = remote_path(repo, &remote, direction)?;
+        let remote_ref = repo
+            .branch_remote_ref_name(branch.as_ref(), direction)
+            .ok_or_else(no_upstream)??
+            .into_owned();
+        let tracking_ref = repo
+            .branch_remote_tracking_ref_name(branch.as_ref(), Direction::Fetch)
+            .transpose()?
+            .map(|name| name.into_owned());
+        Ok(Self {
+            path,
+            remote_ref,
+            tracking_ref,
+        })
+    }
+
+    fn open(&self) -> Result<Repository> {
+        gix::open(&self.path)
+            .with_context(|| format!("cannot open remote repository {}", self.path.display()))
+    }
+}
+
+/// The directories a sparse checkout populates, from `info/sparse-checkout`
+/// when `core.sparseCheckout` is on. Only directory patterns such as
+/// `/.beads/`, as used for sync worktrees, are supported.
+struct Sparse(Option<Vec<BString>>);
+
+impl Sparse {
+    fn load(repo: &Repository) -> Result<Self> {
+        let config = repo.config_snapshot();
+        if config.boolean("core.sparseCheckout") != Some(true) {
+            return Ok(Self(None));
+        }
+        let file = repo.git_dir().join("info").join("sparse-checkout");
+        let mut dirs = Vec::new();
+        for line in fs::read_to_string(&file).unwrap_or_default().lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let dir = line.trim_start_matches('/');
+            if !dir.ends_with('/') || dir.contains(['*', '?', '[', '!', '\\']) {
+                bail!(
+                    "unsupported sparse-checkout pattern '{}': in-process git only supports directories such as /dir/",
+                    line
+                );
+            }
+            dirs.push(BString::from(dir));
+        }
+        Ok(Self(Some(dirs)))
+    }
+
+    fn includes(&self, path: &BStr) -> bool {
+        match &self.0 {
+            Some(dirs) => dirs.iter().any(|dir| path.starts_with(dir)),
+            None => true,
+        }
+    }
+}
+
+/// Path of `remote`, which must be on the local filesystem.
+fn remote_path(
+    repo: &Repository,
+    remote: &gix::Remote<'_>,
+    direction: Direction,
+) -> Result<PathBuf> {
+    let url = remote.url(direction).context("remote has no URL")?;
+    if url.scheme != gix::url::Scheme::File {
+        bail!(
+            "in-process git only supports remotes on the local filesystem, not {}",
+            url.to_bstring()
+        );
+    }
+    Ok(workdir(repo)?.join(gix::path::from_bstr(url.path.as_bstr())))
+}
+
+fn workdir(repo: &Repository) -> Result<&Path> {
+    repo.work_dir().context("repository has no worktree")
+}
+
+fn head_id(repo: &Repository) -> Result<Option<ObjectId>> {
+    Ok(repo.head()?.id().map(|id| id.detach()))
+}
+
+fn current_branch(repo: &Repository) -> Result<FullName> {
+    repo.head_name()?
+        .context("not on a branch (HEAD is detached)")
+}
+
+fn set_head(repo: &Repository, target: Target, message: &str) -> Result<()> {
+    repo.edit_reference(RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: false,
+                message: message.into(),
+            },
+            expected: PreviousValue::Any,
+            new: target,
+        },
+        name: "HEAD".try_into()?,
+        deref: false,
+    })?;
+    Ok(())
+}
+
+/// The commit `name` points at in `repo`, if it exists.
+fn ref_id(repo: &Repository, name: &FullName) -> Result<Option<ObjectId>> {
+    match repo.try_find_reference(name.as_ref())? {
+        Some(mut reference) => Ok(Some(reference.peel_to_id_in_place()?.detach())),
+        None => Ok(None),
+    }
+}
+
+/// Copies `tip` and everything it references that `to` lacks from `from`.
+fn copy_objects(from: &Repository, to: &Repository, tip: ObjectId) -> Result<()> {
+    let mut pending = vec![tip];
+    let mut seen = HashSet::new();
+    while let Some(id) = pending.pop() {
+        if !seen.insert(id) || to.has_object(id) {
+            continue;
+        }
+        let object = from.find_object(id)?;
+        match object.kind {
+            gix::objs::Kind::Commit => {
+                let commit = gix::objs::CommitRef::from_bytes(&object.data)?;
+                pending.push(commit.tree());
+                pending.extend(commit.parents());
+            }
+            gix::objs::Kind::Tree => {
+                let tree = gix::objs::TreeRef::from_bytes(&object.data)?;
+                pending.extend(
+                    tree.entries
+                        .iter()
+                        .filter(|e| !e.mode.is_commit())
+                        .map(|e| e.oid.to_owned()),
+                );
+            }
+            gix::objs::Kind::Tag => {
+                pending.push(gix::objs::TagRef::from_bytes(&object.data)?.target());
+            }
+            gix::objs::Kind::Blob => {}
+        }
+        to.objects
+            .write_buf(object.kind, &object.data)
+            .map_err(|e| anyhow!(e))?;
+    }
+    Ok(())
+}
+
+fn is_ancestor(repo: &Repository, ancestor: ObjectId, descendant: ObjectId) -> Result<bool> {
+    for info in repo.rev_walk([descendant]).all()? {
+        if info?.id == ancestor {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The newest commit reachable from both `a` and `b`.
+fn merge_base(repo: &Repository, a: ObjectId, b: ObjectId) -> Result<ObjectId> {
+    let mut ancestors = HashSet::new();
+    for info in repo.rev_walk([a]).all()? {
+        ancestors.insert(info?.id);
+    }
+    let walk = repo
+        .rev_walk([b])
+        .sorting(gix::traverse::commit::simple::Sorting::ByCommitTimeNewestFirst)
+        .all()?;
+    for info in walk {
+        let id = info?.id;
+        if ancestors.contains(&id) {
+            return Ok(id);
+        }
+    }
+    bail!("git pull --rebase failed: refusing to merge unrelated histories")
+}
+
+/// The first-parent chain from `base` (exclusive) to `tip`, oldest first.
+fn commits_since(repo: &Repository, base: ObjectId, tip: ObjectId) -> Result<Vec<ObjectId>> {
+    let mut commits = Vec::new();
+    let mut current = tip;
+    while current != base {
+        commits.push(current);
+        let commit = repo.find_object(current)?.try_into_commit()?;
+        current = match commit.parent_ids().next() {
+            Some(parent) => parent.detach(),
+            None => break,
+        };
+    }
+    commits.reverse();
+    Ok(commits)
+}
+
+/// Writes a copy of commit `original` with `tree` on top of `parent`,
+/// keeping its author and message, and returns it.
+fn replay_commit(
+    repo: &Repository,
+    original: ObjectId,
+    tree: ObjectId,
+    parent: ObjectId,
+) -> Result<ObjectId> {
+    let original = repo.find_object(original)?.try_into_commit()?;
+    let decoded = original.decode()?;
+    let committer = repo
+        .committer()
+        .context("no committer identity configured (set user.name and user.email)")??
+        .to_owned();
+    let commit = gix::objs::Commit {
+        tree,
+        parents: vec![parent].into(),
+        author: decoded.author.to_owned(),
+        committer,
+        encoding: decoded.encoding.map(|e| e.to_owned()),
+        message: decoded.message.to_owned(),
+        extra_headers: Vec::new(),
+    };
+    Ok(repo.write_object(&commit)?.detach())
+}
+
+fn commit_summary(repo: &Repository, id: ObjectId) -> Result<String> {
+    let commit = repo.find_object(id)?.try_into_commit()?;
+    let summary = commit.message()?.summary().to_string();
+    Ok(summary)
+}
+
+fn short(id: ObjectId) -> String {
+    id.to_hex_with_len(7).to_string()
+}
+
+fn with_newline(mut data: Vec<u8>) -> Vec<u8> {
+    if !data.is_empty() && !data.ends_with(b"\n") {
+        data.push(b'\n');
+    }
+    data
+}
+
+/// Three-way merge of `ours` and `theirs` by file: a file changed on one
+/// side only takes that change. Files changed differently on both sides
+/// are left out of the result and returned for `merge_file`.
+fn merge_files(base: &Files, ours: &Files, theirs: &Files) -> (Files, Vec<BString>) {
+    let paths: BTreeSet<&BString> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+    let mut merged = Files::new();
+    let mut conflicts = Vec::new();
+    for path in paths {
+        let (b, o, t) = (base.get(path), ours.get(path), theirs.get(path));
+        let result = if o == t || b == t {
+            o
+        } else if b == o {
+            t
+        } else {
+            conflicts.push(path.clone());
+            continue;
+        };
+        if let Some(entry) = result {
+            merged.insert(path.clone(), *entry);
+        }
+    }
+    (merged, conflicts)
+}
+
+/// Merges the versions of `path` in `base`, `ours` and `theirs` line by
+/// line and writes the result. `None` if that conflicts, or if the file
+/// isn't a regular file on both sides with the same mode.
+fn merge_file(
+    repo: &Repository,
+    path: &BString,
+    [base, ours, theirs]: [&Files; 3],
+) -> Result<Option<File>> {
+    let (Some(&(mode, ours)), Some(&(theirs_mode, theirs))) = (ours.get(path), theirs.get(path))
+    else {
+        return Ok(None);
+    };
+    if mode != theirs_mode || !mode.is_blob() {
+        return Ok(None);
+    }
+    let read = |id: ObjectId| -> Result<Vec<u8>> { Ok(repo.find_object(id)?.detach().data) };
+    let base = match base.get(path) {
+        Some(&(_, id)) => read(id)?,
+        None => Vec::new(),
+    };
+    Ok(match merge_blob(&base, &read(ours)?, &read(theirs)?) {
+        Some(merged) => Some((mode, repo.write_blob(merged)?.detach())),
+        None => None,
+    })
+}
+
+/// Three-way merge of file contents by line, as `git merge-file` does:
+/// changes to separate lines are combined. `None` if both sides changed
+/// the same or adjacent lines differently, or the file is binary.
+fn merge_blob(base: &[u8], ours: &[u8], theirs: &[u8]) -> Option<Vec<u8>> {
+    if [base, ours, theirs].iter().any(|data| data.contains(&0)) {
+        return None;
+    }
+    let lines = |data: &[u8]| -> Vec<Vec<u8>> {
+        data.split_inclusive(|&b| b == b'\n')
+            .map(<[u8]>::to_vec)
+            .collect()
+    };
+    let base_lines = lines(base);
+    let side_lines = [lines(ours), lines(theirs)];
+    // (side, base lines, side lines) of each change against the base
+    let mut hunks: Vec<(usize, Range<usize>, Range<usize>)> = Vec::new();
+    for (side, data) in [ours, theirs].into_iter().enumerate() {
+        let input = InternedInput::new(
+            byte_lines_with_terminator(base),
+            byte_lines_with_terminator(data),
+        );
+        imara_diff::diff(
+            Algorithm::Histogram,
+            &input,
+            |before: Range<u32>, after: Range<u32>| {
+                hunks.push((
+                    side,
+                    before.start as usize..before.end as usize,
+                    after.start as usize..after.end as usize,
+                ));
+            },
+        );
+    }
+    hunks.sort_by_key(|(side, before, _)| (before.start, before.end, *side));
+
+    let mut merged = Vec::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i < hunks.len() {
+        // The changes from either side overlapping or touching this one
+        let start = hunks[i].1.start;
+        let mut end = hunks[i].1.end;
+        let mut j = i + 1;
+        while j < hunks.len() && hunks[j].1.start <= end {
+            end = end.max(hunks[j].1.end);
+            j += 1;
+        }
+        let region = &hunks[i..j];
+        // The base's lines start..end as `side` changed them
+        let text = |side: usize| {
+            let mut text = Vec::new();
+            let mut at = start;
+            for (_, before, after) in region.iter().filter(|(s, ..)| *s == side) {
+                text.extend(base_lines[at..before.start].concat());
+                text.extend(side_lines[side][after.clone()].concat());
+                at = before.end;
+            }
+            text.extend(base_lines[at..end].concat());
+            text
+        };
+        let changed_by = |side: usize| region.iter().any(|(s, ..)| *s == side);
+        let text = match (changed_by(0), changed_by(1)) {
+            (true, true) if text(0) != text(1) => return None,
+            (_, true) if !changed_by(0) => text(1),
+            _ => text(0),
+        };
+        merged.extend(base_lines[copied..start].concat());
+        merged.extend(text);
+        copied = end;
+        i = j;
+    }
+    merged.extend(base_lines[copied..].concat());
+    Some(merged)
+}
+
+fn commit_files(repo: &Repository, commit: ObjectId) -> Result<Files> {
+    let tree = repo
+        .find_object(commit)?
+        .try_into_commit()?
+        .tree_id()?
+        .detach();
+    let mut files = Files::new();
+    collect_files(repo, tree, "".into(), &mut files)?;
+    Ok(files)
+}
+
+fn collect_files(
+    repo: &Repository,
+    tree: ObjectId,
+    prefix: &BStr,
+    files: &mut Files,
+) -> Result<()> {
+    let tree = repo.find_object(tree)?.try_into_tree()?;
+    for entry in tree.decode()?.entries {
+        let mut path = prefix.to_owned();
+        if !path.is_empty() {
+            path.push(b'/');
+        }
+        path.extend_from_slice(entry.filename);
+        if entry.mode.is_tree() {
+            collect_files(repo, entry.oid.to_owned(), path.as_bstr(), files)?;
+        } else {
+            files.insert(path, (entry.mode, entry.oid.to_owned()));
+        }
+    }
+    Ok(())
+}
+
+/// Writes the trees holding `files` and returns the root's ID.
+fn write_tree(repo: &Repository, files: &Files) -> Result<ObjectId> {
+    let entries = files
+        .iter()
+        .map(|(path, entry)| (path.as_bstr(), *entry))
+        .collect();
+    write_subtree(repo, entries)
+}
+
+fn write_subtree(repo: &Repository, entries: Vec<(&BStr, File)>) -> Result<ObjectId> {
+    let mut tree = gix::objs::Tree::empty();
+    let mut dirs: BTreeMap<&BStr, Vec<(&BStr, File)>> = BTreeMap::new();
+    for (path, (mode, oid)) in entries {
+        match path.find_byte(b'/') {
+            Some(slash) => dirs
+                .entry(path[..slash].as_bstr())
+                .or_default()
+                .push((path[slash + 1..].as_bstr(), (mode, oid))),
+            None => tree.entries.push(gix::objs::tree::Entry {
+                mode,
+                filename: path.to_owned(),
+                oid,
+            }),
+        }
+    }
+    for (name, children) in dirs {
+        let oid = write_subtree(repo, children)?;
+        tree.entries.push(gix::objs::tree::Entry {
+            mode: EntryKind::Tree.into(),
+            filename: name.to_owned(),
+            oid,
+        });
+    }
+    tree.entries.sort();
+    Ok(repo.write_object(&tree)?.detach())
+}
+
+/// Updates the worktree from holding `from` to holding `to`, leaving out
+/// paths outside the sparse checkout.
+fn checkout(repo: &Repository, from: &Files, to: &Files) -> Result<()> {
+    let dir = workdir(repo)?;
+    let sparse = Sparse::load(repo)?;
+    for (path, (mode, id)) in to {
+        if from.get(path) == Some(&(*mode, *id))
+            || mode.is_commit()
+            || !sparse.includes(path.as_bstr())
+        {
+            continue;
+        }
+        let full = dir.join(gix::path::from_bstr(path.as_bstr()));
+        if let Some(parent) = full.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&full, repo.find_object(*id)?.detach().data)?;
+        set_executable(&full, mode.is_executable())?;
+    }
+    for path in from.keys().filter(|path| !to.contains_key(*path)) {
+        let full = dir.join(gix::path::from_bstr(path.as_bstr()));
+        match fs::remove_file(&full) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        // Drop directories left empty, as git does.
+        let mut parent = full.parent();
+        while let Some(p) = parent.filter(|p| *p != dir) {
+            if fs::remove_dir(p).is_err() {
+                break;
+            }
+            parent = p.parent();
+        }
+    }
+    Ok(())
+}
+
+fn load_index(repo: &Repository) -> Result<gix::index::File> {
+    if repo.index_path().exists() {
+        Ok(repo.open_index()?)
+    } else {
+        Ok(new_index(repo))
+    }
+}
+
+fn new_index(repo: &Repository) -> gix::index::File {
+    gix::index::File::from_state(
+        gix::index::State::new(repo.object_hash()),
+        repo.index_path(),
+    )
+}
+
+fn write_index(index: &mut gix::index::File) -> Result<()> {
+    // Without the tree cache, which would be stale after edits.
+    index.write(gix::index::write::Options {
+        extensions: gix::index::write::Extensions::Given {
+            tree_cache: false,
+            end_of_index_entry: true,
+        },
+        ..Default::default()
+    })?;
+    Ok(())
+}
+
+/// A new index holding `files`, unconflicted.
+fn index_of(repo: &Repository, files: &Files) -> Result<gix::index::File> {
+    let sparse = Sparse::load(repo)?;
+    let mut index = new_index(repo);
+    for (path, (mode, id)) in files {
+        push_entry(
+            repo,
+            &sparse,
+            &mut index,
+            path.as_bstr(),
+            *mode,
+            *id,
+            Stage::Unconflicted,
+        )?;
+    }
+    Ok(index)
+}
+
+/// Adds an entry for `path`, with the stat of the worktree file when it
+/// holds exactly this content, and marked skip-worktree when it is outside
+/// the sparse checkout.
+fn push_entry(
+    repo: &Repository,
+    sparse: &Sparse,
+    index: &mut gix::index::File,
+    path: &BStr,
+    mode: EntryMode,
+    id: ObjectId,
+    stage: Stage,
+) -> Result<()> {
+    let full = workdir(repo)?.join(gix::path::from_bstr(path));
+    let mut stat = Stat::default();
+    let mut flags = Flags::from_bits_retain((stage as u32) << 12);
+    if !sparse.includes(path) {
+        flags |= Flags::SKIP_WORKTREE | Flags::EXTENDED;
+    } else if stage == Stage::Unconflicted && worktree_id(repo, &full)? == Some(id) {
+        stat = Stat::from_fs(&gix::index::fs::Metadata::from_path_no_follow(&full)?)?;
+    }
+    let mode = if mode.is_executable() {
+        Mode::FILE_EXECUTABLE
+    } else if mode.is_link() {
+        Mode::SYMLINK
+    } else if mode.is_commit() {
+        Mode::COMMIT
+    } else {
+        Mode::FILE
+    };
+    index.dangerously_push_entry(stat, id, flags, mode, path);
+    Ok(())
+}
+
+fn has_conflicts(index: &gix::index::State) -> bool {
+    index
+        .entries()
+        .iter()
+        .any(|e| e.stage() != Stage::Unconflicted)
+}
+
+/// The unconflicted entries of `index`.
+fn index_files(index: &gix::index::State) -> Files {
+    index
+        .entries()
+        .iter()
+        .filter(|e| e.stage() == Stage::Unconflicted)
+        .filter_map(|e| {
+            Some((
+                e.path(index).to_owned(),
+                (e.mode.to_tree_entry_mode()?, e.id),
+            ))
+        })
+        .collect()
+}
+
+/// ID of the blob the worktree file at `full` would hash to, if it exists.
+fn worktree_id(repo: &Repository, full: &Path) -> Result<Option<ObjectId>> {
+    match fs::symlink_metadata(full) {
+        Ok(meta) if meta.is_file() => {
+            let data = fs::read(full)?;
+            Ok(Some(gix::objs::compute_hash(
+                repo.object_hash(),
+                gix::objs::Kind::Blob,
+                &data,
+            )))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `git status --porcelain` lines for tracked files, in path order.
+fn tracked_changes(repo: &Repository) -> Result<Vec<String>> {
+    let index = load_index(repo)?;
+    let head = match head_id(repo)? {
+        Some(id) => commit_files(repo, id)?,
+        None => Files::new(),
+    };
+    let staged = index_files(&index);
+    let mut stages: BTreeMap<BString, [bool; 3]> = BTreeMap::new();
+    let mut skipped = HashSet::new();
+    for entry in index.entries() {
+        let stage = entry.stage() as usize;
+        if stage > 0 {
+            stages.entry(entry.path(&index).to_owned()).or_default()[stage - 1] = true;
+        }
+        if entry.flags.contains(Flags::SKIP_WORKTREE) {
+            skipped.insert(entry.path(&index));
+        }
+    }
+
+    let dir = workdir(repo)?;
+    let paths: BTreeSet<&BString> = head
+        .keys()
+        .chain(staged.keys())
+        .chain(stages.keys())
+        .collect();
+    let mut lines = Vec::new();
+    for path in paths {
+        if let Some(stages) = stages.get(path) {
+            let code = match stages {
+                [false, true, true] => "AA",
+                [true, true, true] => "UU",
+                [true, true, false] => "UD",
+                [true, false, true] => "DU",
+                [false, true, false] => "AU",
+                [false, false, true] => "UA",
+                _ => "DD",
+            };
+            lines.push(format!("{} {}", code, path));
+            continue;
+        }
+        let x = match (head.get(path), staged.get(path)) {
+            (Some(_), None) => 'D',
+            (None, Some(_)) => 'A',
+            (Some(h), Some(s)) if h != s => 'M',
+            _ => ' ',
+        };
+        let y = match staged.get(path) {
+            Some(_) if skipped.contains(path.as_bstr()) => ' ',
+            Some((mode, id)) => {
+                let full = dir.join(gix::path::from_bstr(path.as_bstr()));
+                match worktree_id(repo, &full)? {
+                    None => 'D',
+                    Some(actual) if actual != *id => 'M',
+                    Some(_) if mode.is_executable() != is_executable(&fs::metadata(&full)?) => 'M',
+                    Some(_) => ' ',
+                }
+            }
+            None => ' ',
+        };
+        if x != ' ' || y != ' ' {
+            lines.push(format!("{}{} {}", x, y, path));
+        }
+    }
+    Ok(lines)
+}
+
+/// Collects untracked, unignored paths under `prefix`, listing a directory
+/// without tracked files as `dir/` like `git status` does.
+fn find_untracked(
+    root: &Path,
+    prefix: BString,
+    tracked: &BTreeSet<BString>,
+    excludes: &mut gix::AttributeStack<'_>,
+    out: &mut Vec<BString>,
+) -> Result<()> {
+    let dir = root.join(gix::path::from_bstr(prefix.as_bstr()));
+    let mut entries: Vec<_> = fs::read_dir(&dir)?.collect::<std::io::Result<_>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let name = gix::path::into_bstr(PathBuf::from(entry.file_name())).into_owned();
+        if prefix.is_empty() && name == ".git" {
+            continue;
+        }
+        let mut path = prefix.clone();
+        path.extend_from_slice(&name);
+        let is_dir = entry.file_type()?.is_dir();
+        let mode = if is_dir { Mode::DIR } else { Mode::FILE };
+        if tracked.contains(&path) {
+            continue;
+        }
+        if excludes.at_entry(path.as_bstr(), Some(mode))?.is_excluded() {
+            continue;
+        }
+        if is_dir {
+            path.push(b'/');
+            let before = out.len();
+            let has_tracked = tracked
+                .range(path.clone()..)
+                .next()
+                .is_some_and(|t| t.starts_with(&path));
+            if has_tracked {
+                find_untracked(root, path, tracked, excludes, out)?;
+            } else {
+                find_untracked(root, path.clone(), tracked, excludes, out)?;
+                if out.len() > before {
+                    out.truncate(before);
+                    out.push(path);
+                }
+            }
+        } else {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path, executable: bool) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = if executable { 0o755 } else { 0o644 };
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path, _executable: bool) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u8) -> File {
+        let mut bytes = [0u8; 20];
+        bytes[19] = n;
+        (
+            EntryKind::Blob.into(),
+            ObjectId::from_bytes_or_panic(&bytes),
+        )
+    }
+
+    fn files(entries: &[(&str, u8)]) -> Files {
+        entries
+            .iter()
+            .map(|(p, n)| (BString::from(*p), id(*n)))
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_files() {
+        let base = files(&[("a", 1), ("b", 1), ("c", 1), ("gone", 1)]);
+        let ours = files(&[("a", 2), ("b", 1), ("c", 2), ("new", 1)]);
+        let theirs = files(&[("a", 1), ("b", 3), ("c", 3)]);
+        let (merged, conflicts) = merge_files(&base, &ours, &theirs);
+        assert_eq!(merged, files(&[("a", 2), ("b", 3), ("new", 1)]));
+        assert_eq!(conflicts, vec![BString::from("c")]);
+    }
+
+    #[test]
+    fn test_merge_blob() {
+        let base = b"one\ntwo\nthree\nfour\nfive\n";
+        let ours = b"ONE\ntwo\nthree\nfour\nfive\n";
+        let theirs = b"one\ntwo\nthree\nfour\nFIVE\nsix";
+        assert_eq!(
+            merge_blob(base, ours, theirs).as_deref(),
+            Some(&b"ONE\ntwo\nthree\nfour\nFIVE\nsix"[..])
+        );
+        // The same change on both sides is taken once.
+        assert_eq!(merge_blob(base, ours, ours).as_deref(), Some(&ours[..]));
+        assert_eq!(merge_blob(b"", b"a\n", b"b\n"), None);
+        assert_eq!(merge_blob(base, b"one\nTWO\n", b"one\ntwo\nTHREE\n"), None);
+        assert_eq!(merge_blob(base, b"\0", base), None);
+    }
+}
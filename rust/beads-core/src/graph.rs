@@ -0,0 +1,583 @@
+use crate::models::{Dependency, Issue};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+// Dependency types (mirrors Go types.DependencyType)
+pub const DEP_BLOCKS: &str = "blocks";
+pub const DEP_RELATED: &str = "related";
+pub const DEP_PARENT_CHILD: &str = "parent-child";
+pub const DEP_DISCOVERED_FROM: &str = "discovered-from";
+pub const DEP_REPLIES_TO: &str = "replies-to";
+pub const DEP_RELATES_TO: &str = "relates-to";
+pub const DEP_DUPLICATES: &str = "duplicates";
+pub const DEP_SUPERSEDES: &str = "supersedes";
+
+// Older Rust builds wrote "blocking" as the default dependency type.
+const DEP_BLOCKS_LEGACY: &str = "blocking";
+
+// Same limit as the Go blocked_issues_cache CTE.
+const MAX_PROPAGATION_DEPTH: usize = 50;
+
+/// Dependency types the Go implementation accepts.
+pub const DEP_TYPES: [&str; 8] = [
+    DEP_BLOCKS,
+    DEP_RELATED,
+    DEP_PARENT_CHILD,
+    DEP_DISCOVERED_FROM,
+    DEP_REPLIES_TO,
+    DEP_RELATES_TO,
+    DEP_DUPLICATES,
+    DEP_SUPERSEDES,
+];
+
+/// Returns true for dependency types the Go implementation accepts.
+pub fn is_valid_type(dep_type: &str) -> bool {
+    DEP_TYPES.contains(&dep_type)
+}
+
+/// Returns true if the dependency type prevents the dependent issue from being worked on.
+pub fn is_blocking_type(dep_type: &str) -> bool {
+    dep_type == DEP_BLOCKS || dep_type == DEP_BLOCKS_LEGACY
+}
+
+/// Statuses that still block dependents (Go: open, in_progress, blocked).
+pub fn is_active_status(status: &str) -> bool {
+    matches!(status, "open" | "in_progress" | "blocked")
+}
+
+/// Statuses that `bd ready` considers workable.
+pub fn is_ready_status(status: &str) -> bool {
+    matches!(status, "open" | "in_progress")
+}
+
+/// Error returned when adding a dependency would close a cycle.
+///
+/// `path` starts and ends with the issue that would depend on itself.
+#[derive(Debug, thiserror::Error)]
+#[error("cannot add dependency: would create a cycle ({})", .path.join(" → "))]
+pub struct CycleError {
+    pub path: Vec<String>,
+}
+
+/// Which way `DependencyGraph::tree` walks from the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeDirection {
+    /// Issues the root depends on.
+    Down,
+    /// Issues that depend on the root.
+    Up,
+}
+
+/// One line of a rendered dependency tree, in pre-order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    pub id: String,
+    pub depth: usize,
+    /// Type of the edge connecting this node to its parent in the tree.
+    pub dep_type: String,
+    /// Last child of its parent (used for drawing connectors).
+    pub last: bool,
+    /// Already expanded elsewhere in the tree; children are not repeated.
+    pub seen: bool,
+}
+
+/// Directed dependency graph built from `Dependency` rows.
+///
+/// An edge `issue_id -> depends_on_id` means `issue_id` depends on `depends_on_id`.
+/// For `parent-child` edges the child is `issue_id` and the parent is `depends_on_id`.
+#[derive(Debug, Default, Clone)]
+pub struct DependencyGraph {
+    upstream: HashMap<String, Vec<Dependency>>,
+    downstream: HashMap<String, Vec<Dependency>>,
+}
+
+impl DependencyGraph {
+    pub fn new<'a, I>(deps: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Dependency>,
+    {
+        let mut graph = Self::default();
+        for dep in deps {
+            graph
+                .upstream
+                .entry(dep.issue_id.clone())
+                .or_default()
+                .push(dep.clone());
+            graph
+                .downstream
+                .entry(dep.depends_on_id.clone())
+                .or_default()
+                .push(dep.clone());
+        }
+        graph
+    }
+
+    pub fn from_issues(issues: &[Issue]) -> Self {
+        Self::new(issues.iter().flat_map(|i| i.dependencies.iter()))
+    }
+
+    /// Edges from `id` to the issues it depends on.
+    pub fn dependencies_of(&self, id: &str) -> &[Dependency] {
+        self.upstream.get(id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Edges from other issues that depend on `id`.
+    pub fn dependents_of(&self, id: &str) -> &[Dependency] {
+        self.downstream.get(id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// IDs of the issues `id` is the `parent-child` parent of.
+    pub fn children_of<'a>(&'a self, id: &str) -> impl Iterator<Item = &'a str> {
+        self.dependents_of(id)
+            .iter()
+            .filter(|d| d.type_ == DEP_PARENT_CHILD)
+            .map(|d| d.issue_id.as_str())
+    }
+
+    /// Shortest path `from -> ... -> to` following dependency edges of any type.
+    pub fn path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut prev: HashMap<&str, &str> = HashMap::new();
+        let mut queue: VecDeque<&str> = VecDeque::from([from]);
+        let mut visited: HashSet<&str> = HashSet::from([from]);
+
+        while let Some(id) = queue.pop_front() {
+            if id == to {
+                let mut path = vec![to.to_string()];
+                let mut cur = to;
+                while let Some(&p) = prev.get(cur) {
+                    path.push(p.to_string());
+                    cur = p;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for dep in self.dependencies_of(id) {
+                if visited.insert(&dep.depends_on_id) {
+                    prev.insert(&dep.depends_on_id, id);
+                    queue.push_back(&dep.depends_on_id);
+                }
+            }
+        }
+        None
+    }
+
+    /// Checks whether `issue_id` depending on `depends_on_id` would close a cycle.
+    ///
+    /// Like Go, every dependency type participates in cycle detection.
+    pub fn check_new_edge(&self, issue_id: &str, depends_on_id: &str) -> Result<(), CycleError> {
+        if issue_id == depends_on_id {
+            return Err(CycleError {
+                path: vec![issue_id.to_string(), issue_id.to_string()],
+            });
+        }
+        match self.path(depends_on_id, issue_id) {
+            Some(rest) => {
+                let mut path = vec![issue_id.to_string()];
+                path.extend(rest);
+                Err(CycleError { path })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Finds existing cycles, one per strongly connected component.
+    ///
+    /// Each cycle is listed from its smallest ID without repeating the start.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles = Vec::new();
+        for component in self.strongly_connected_components() {
+            let start = component.iter().next().unwrap();
+            let self_loop = self
+                .dependencies_of(start)
+                .iter()
+                .any(|d| &d.depends_on_id == start);
+            if component.len() == 1 && !self_loop {
+                continue;
+            }
+            if self_loop {
+                cycles.push(vec![start.clone()]);
+                continue;
+            }
+            // Walk from a successor inside the component back to start
+            let next = self
+                .dependencies_of(start)
+                .iter()
+                .map(|d| &d.depends_on_id)
+                .filter(|id| component.contains(*id))
+                .min()
+                .unwrap();
+            let mut cycle = vec![start.clone()];
+            if let Some(path) = self.path(next, start) {
+                cycle.extend(path.into_iter().take_while(|id| id != start));
+            }
+            cycles.push(cycle);
+        }
+        cycles.sort();
+        cycles
+    }
+
+    // Kosaraju's algorithm with explicit stacks to avoid deep recursion.
+    fn strongly_connected_components(&self) -> Vec<BTreeSet<String>> {
+        let mut nodes: BTreeSet<&str> = BTreeSet::new();
+        for deps in self.upstream.values() {
+            for dep in deps {
+                nodes.insert(&dep.issue_id);
+                nodes.insert(&dep.depends_on_id);
+            }
+        }
+
+        let mut order: Vec<&str> = Vec::new();
+        let mut visited: HashSet<&str> = HashSet::new();
+        for &node in &nodes {
+            if !visited.insert(node) {
+                continue;
+            }
+            let mut stack: Vec<(&str, usize)> = vec![(node, 0)];
+            while let Some((id, idx)) = stack.pop() {
+                let deps = self.dependencies_of(id);
+                if idx < deps.len() {
+                    stack.push((id, idx + 1));
+                    let next = deps[idx].depends_on_id.as_str();
+                    if visited.insert(next) {
+                        stack.push((next, 0));
+                    }
+                } else {
+                    order.push(id);
+                }
+            }
+        }
+
+        let mut assigned: HashSet<&str> = HashSet::new();
+        let mut components = Vec::new();
+        for &node in order.iter().rev() {
+            if !assigned.insert(node) {
+                continue;
+            }
+            let mut component = BTreeSet::new();
+            let mut stack = vec![node];
+            while let Some(id) = stack.pop() {
+                component.insert(id.to_string());
+                for dep in self.dependents_of(id) {
+                    if assigned.insert(&dep.issue_id) {
+                        stack.push(&dep.issue_id);
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+
+    /// Flattens the tree rooted at `root` in pre-order, excluding the root itself.
+    ///
+    /// Nodes reachable through several paths are expanded once; later occurrences
+    /// are marked `seen`, which also keeps cycles from looping forever.
+    pub fn tree(&self, root: &str, direction: TreeDirection, max_depth: usize) -> Vec<TreeNode> {
+        let mut nodes = Vec::new();
+        let mut expanded: HashSet<String> = HashSet::from([root.to_string()]);
+        self.walk_tree(root, 1, direction, max_depth, &mut expanded, &mut nodes);
+        nodes
+    }
+
+    fn walk_tree(
+        &self,
+        id: &str,
+        depth: usize,
+        direction: TreeDirection,
+        max_depth: usize,
+        expanded: &mut HashSet<String>,
+        nodes: &mut Vec<TreeNode>,
+    ) {
+        if depth > max_depth {
+            return;
+        }
+        let mut edges: Vec<(&str, &str)> = match direction {
+            TreeDirection::Down => self
+                .dependencies_of(id)
+                .iter()
+                .map(|d| (d.depends_on_id.as_str(), d.type_.as_str()))
+                .collect(),
+            TreeDirection::Up => self
+                .dependents_of(id)
+                .iter()
+                .map(|d| (d.issue_id.as_str(), d.type_.as_str()))
+                .collect(),
+        };
+        edges.sort();
+
+        let count = edges.len();
+        for (i, (child, dep_type)) in edges.into_iter().enumerate() {
+            let seen = !expanded.insert(child.to_string());
+            nodes.push(TreeNode {
+                id: child.to_string(),
+                depth,
+                dep_type: dep_type.to_string(),
+                last: i + 1 == count,
+                seen,
+            });
+            if !seen {
+                self.walk_tree(child, depth + 1, direction, max_depth, expanded, nodes);
+            }
+        }
+    }
+
+    /// Computes every blocked issue and what blocks it.
+    ///
+    /// An issue is blocked directly by a `blocks` dependency on an active issue,
+    /// and transitively when its parent (via `parent-child`) is blocked.
+    /// `statuses` maps issue ID to status; unknown blockers never block.
+    pub fn blocked(&self, statuses: &HashMap<String, String>) -> BTreeMap<String, Blockers> {
+        let mut blocked: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+        for deps in self.upstream.values() {
+            for dep in deps {
+                if !is_blocking_type(&dep.type_) {
+                    continue;
+                }
+                let active = statuses
+                    .get(&dep.depends_on_id)
+                    .is_some_and(|s| is_active_status(s));
+                if active {
+                    let blockers = blocked.entry(dep.issue_id.clone()).or_default();
+                    if !blockers.contains(&dep.depends_on_id) {
+                        blockers.push(dep.depends_on_id.clone());
+                    }
+                }
+            }
+        }
+
+        let mut blocked: BTreeMap<String, Blockers> = blocked
+            .into_iter()
+            .map(|(id, mut blockers)| {
+                blockers.sort();
+                (id, Blockers::Direct(blockers))
+            })
+            .collect();
+
+        // Propagate to children of blocked issues
+        let mut queue: VecDeque<(String, usize)> =
+            blocked.keys().map(|id| (id.clone(), 0)).collect();
+        while let Some((id, depth)) = queue.pop_front() {
+            if depth >= MAX_PROPAGATION_DEPTH {
+                continue;
+            }
+            for dep in self.dependents_of(&id) {
+                if dep.type_ != DEP_PARENT_CHILD || blocked.contains_key(&dep.issue_id) {
+                    continue;
+                }
+                blocked.insert(dep.issue_id.clone(), Blockers::Parent(id.clone()));
+                queue.push_back((dep.issue_id.clone(), depth + 1));
+            }
+        }
+        blocked
+    }
+}
+
+/// What keeps a blocked issue from being worked on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Blockers {
+    /// Active issues it has a `blocks` dependency on, sorted.
+    Direct(Vec<String>),
+    /// Its parent, which is blocked itself.
+    Parent(String),
+}
+
+impl Blockers {
+    /// The IDs of the issues blocking it.
+    pub fn ids(&self) -> &[String] {
+        match self {
+            Blockers::Direct(ids) => ids,
+            Blockers::Parent(id) => std::slice::from_ref(id),
+        }
+    }
+}
+
+/// Validates a dependency about to be added to `graph`: known type, not already
+/// present and not closing a cycle.
+pub fn validate_new_dependency(
+    graph: &DependencyGraph,
+    dep: &Dependency,
+) -> anyhow::Result<()> {
+    if !is_valid_type(&dep.type_) {
+        anyhow::bail!(
+            "invalid dependency type: {} (must be one of {})",
+            dep.type_,
+            DEP_TYPES.join(", ")
+        );
+    }
+    if graph
+        .dependencies_of(&dep.issue_id)
+        .iter()
+        .any(|d| d.depends_on_id == dep.depends_on_id && d.type_ == dep.type_)
+    {
+        anyhow::bail!(
+            "dependency {} -> {} ({}) already exists",
+            dep.issue_id,
+            dep.depends_on_id,
+            dep.type_
+        );
+    }
+    graph.check_new_edge(&dep.issue_id, &dep.depends_on_id)?;
+    Ok(())
+}
+
+/// Orders issues the way `bd ready` presents them: priority, then oldest first.
+pub fn sort_by_priority(issues: &mut [Issue]) {
+    issues.sort_by(|a, b| {
+        a.priority
+            .cmp(&b.priority)
+            .then_with(|| a.created_at.cmp(&b.created_at))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn dep(issue_id: &str, depends_on_id: &str, type_: &str) -> Dependency {
+        Dependency {
+            issue_id: issue_id.to_string(),
+            depends_on_id: depends_on_id.to_string(),
+            type_: type_.to_string(),
+            created_at: Utc::now(),
+            created_by: "test".to_string(),
+        }
+    }
+
+    fn statuses(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(id, s)| (id.to_string(), s.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_blocks_only_when_blocker_active() {
+        let deps = vec![dep("a", "b", DEP_BLOCKS), dep("c", "d", DEP_BLOCKS)];
+        let graph = DependencyGraph::new(&deps);
+        let st = statuses(&[("a", "open"), ("b", "open"), ("c", "open"), ("d", "closed")]);
+
+        let blocked = graph.blocked(&st);
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked["a"], Blockers::Direct(vec!["b".to_string()]));
+    }
+
+    #[test]
+    fn test_related_does_not_block() {
+        let deps = vec![
+            dep("a", "b", DEP_RELATED),
+            dep("a", "c", DEP_DISCOVERED_FROM),
+        ];
+        let graph = DependencyGraph::new(&deps);
+        let st = statuses(&[("a", "open"), ("b", "open"), ("c", "open")]);
+
+        assert!(graph.blocked(&st).is_empty());
+    }
+
+    #[test]
+    fn test_parent_child_propagation() {
+        // epic is blocked by x; task is a child of epic; subtask is a child of task
+        let deps = vec![
+            dep("epic", "x", DEP_BLOCKS),
+            dep("task", "epic", DEP_PARENT_CHILD),
+            dep("subtask", "task", DEP_PARENT_CHILD),
+        ];
+        let graph = DependencyGraph::new(&deps);
+        let st = statuses(&[
+            ("x", "in_progress"),
+            ("epic", "open"),
+            ("task", "open"),
+            ("subtask", "open"),
+        ]);
+
+        assert_eq!(graph.children_of("epic").collect::<Vec<_>>(), ["task"]);
+        assert_eq!(graph.children_of("x").count(), 0);
+
+        let blocked = graph.blocked(&st);
+        assert_eq!(blocked.len(), 3);
+        assert_eq!(blocked["epic"], Blockers::Direct(vec!["x".to_string()]));
+        assert_eq!(blocked["task"], Blockers::Parent("epic".to_string()));
+        assert_eq!(blocked["subtask"], Blockers::Parent("task".to_string()));
+
+        // Closing the blocker frees the whole subtree
+        let st = statuses(&[
+            ("x", "closed"),
+            ("epic", "open"),
+            ("task", "open"),
+            ("subtask", "open"),
+        ]);
+        assert!(graph.blocked(&st).is_empty());
+    }
+
+    #[test]
+    fn test_check_new_edge_reports_cycle_path() {
+        let deps = vec![dep("a", "b", DEP_BLOCKS), dep("b", "c", DEP_RELATED)];
+        let graph = DependencyGraph::new(&deps);
+
+        let err = graph.check_new_edge("c", "a").unwrap_err();
+        assert_eq!(err.path, vec!["c", "a", "b", "c"]);
+        assert_eq!(
+            err.to_string(),
+            "cannot add dependency: would create a cycle (c → a → b → c)"
+        );
+
+        assert!(graph.check_new_edge("a", "c").is_ok());
+        assert!(graph.check_new_edge("a", "a").is_err());
+    }
+
+    #[test]
+    fn test_cycles() {
+        let deps = vec![
+            dep("a", "b", DEP_BLOCKS),
+            dep("b", "c", DEP_BLOCKS),
+            dep("c", "a", DEP_PARENT_CHILD),
+            dep("d", "a", DEP_BLOCKS),
+            dep("e", "e", DEP_RELATED),
+        ];
+        let graph = DependencyGraph::new(&deps);
+
+        let cycles = graph.cycles();
+        assert_eq!(cycles, vec![vec!["a", "b", "c"], vec!["e"]]);
+    }
+
+    #[test]
+    fn test_tree_marks_repeated_nodes() {
+        // Diamond: a -> b, a -> c, b -> d, c -> d
+        let deps = vec![
+            dep("a", "b", DEP_BLOCKS),
+            dep("a", "c", DEP_BLOCKS),
+            dep("b", "d", DEP_BLOCKS),
+            dep("c", "d", DEP_BLOCKS),
+        ];
+        let graph = DependencyGraph::new(&deps);
+
+        let down = graph.tree("a", TreeDirection::Down, 50);
+        let ids: Vec<(&str, usize, bool)> = down
+            .iter()
+            .map(|n| (n.id.as_str(), n.depth, n.seen))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("b", 1, false),
+                ("d", 2, false),
+                ("c", 1, false),
+                ("d", 2, true)
+            ]
+        );
+        assert!(down[2].last);
+
+        let up = graph.tree("d", TreeDirection::Up, 1);
+        let ids: Vec<&str> = up.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_legacy_blocking_type() {
+        let deps = vec![dep("a", "b", "blocking")];
+        let graph = DependencyGraph::new(&deps);
+        let st = statuses(&[("a", "open"), ("b", "open")]);
+        assert!(graph.blocked(&st).contains_key("a"));
+    }
+}
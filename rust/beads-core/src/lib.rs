@@ -1,11 +1,24 @@
+pub mod autoimport;
+pub mod compact;
+pub mod filter;
 pub mod fs;
 pub mod git;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gix_git;
+pub mod graph;
+pub mod mail;
 pub mod merge;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod migrations;
 pub mod models;
+pub mod rename;
 pub mod store;
 pub mod sync;
+pub mod template;
 pub mod util;
 
+pub use filter::IssueFilter;
+pub use graph::DependencyGraph;
 pub use models::*;
 pub use store::Store;
 
@@ -15,6 +28,8 @@ pub use store::SqliteStore;
 pub use git::GitOps;
 #[cfg(not(target_arch = "wasm32"))]
 pub use git::StdGit;
+#[cfg(not(target_arch = "wasm32"))]
+pub use gix_git::GixGit;
 
 pub use fs::FileSystem;
 #[cfg(not(target_arch = "wasm32"))]
@@ -0,0 +1,196 @@
+//! Agent-to-agent messaging (`bd mail`).
+//!
+//! A message is an ephemeral issue of type `message`: the title is its
+//! subject, the description its body, the assignee its recipient and
+//! `sender` who sent it. It stays open until the recipient acknowledges it,
+//! after which `cleanup` can delete it.
+
+use crate::filter::IssueFilter;
+use crate::models::{Issue, STATUS_CLOSED, TYPE_MESSAGE};
+use crate::store::Store;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+/// Environment variable naming the identity `bd mail` acts as.
+pub const IDENTITY_ENV: &str = "BEADS_IDENTITY";
+/// Config key naming the identity `bd mail` acts as.
+pub const IDENTITY_KEY: &str = "identity";
+
+/// Priority of messages sent with `--urgent`.
+pub const PRIORITY_URGENT: i32 = 0;
+pub const PRIORITY_NORMAL: i32 = 2;
+
+/// Reason recorded on messages deleted by `cleanup`.
+const CLEANUP_REASON: &str = "acknowledged ephemeral issue cleanup";
+
+/// Who `bd mail` sends and reads as: `flag` if given, then `$BEADS_IDENTITY`,
+/// the `identity` config key, and `user.name`.
+pub fn identity(store: &impl Store, flag: Option<&str>) -> Result<String> {
+    if let Some(flag) = flag.filter(|f| !f.is_empty()) {
+        return Ok(flag.to_string());
+    }
+    if let Some(env) = std::env::var(IDENTITY_ENV).ok().filter(|e| !e.is_empty()) {
+        return Ok(env);
+    }
+    for key in [IDENTITY_KEY, "user.name"] {
+        if let Some(value) = store.get_config(key)?.filter(|v| !v.is_empty()) {
+            return Ok(value);
+        }
+    }
+    Ok("unknown".to_string())
+}
+
+/// A new, unread message from `from` to `to`.
+pub fn new_message(
+    id: &str,
+    to: &str,
+    from: &str,
+    subject: &str,
+    body: &str,
+    priority: i32,
+    now: DateTime<Utc>,
+) -> Issue {
+    Issue {
+        id: id.to_string(),
+        title: subject.to_string(),
+        description: body.to_string(),
+        status: "open".to_string(),
+        priority,
+        issue_type: TYPE_MESSAGE.to_string(),
+        assignee: Some(to.to_string()),
+        sender: from.to_string(),
+        ephemeral: true,
+        created_at: now,
+        updated_at: now,
+        ..Default::default()
+    }
+}
+
+/// A reply from `from` to the sender of `original`, threaded to it through
+/// `replies_to`.
+pub fn new_reply(
+    id: &str,
+    original: &Issue,
+    from: &str,
+    body: &str,
+    priority: i32,
+    now: DateTime<Utc>,
+) -> Result<Issue> {
+    if original.sender.is_empty() {
+        anyhow::bail!(
+            "message {} has no sender, cannot determine reply recipient",
+            original.id
+        );
+    }
+    let mut reply = new_message(
+        id,
+        &original.sender,
+        from,
+        &reply_subject(&original.title),
+        body,
+        priority,
+        now,
+    );
+    reply.replies_to = original.id.clone();
+    Ok(reply)
+}
+
+/// `subject` with a "Re: " prefix, unless it already has one.
+pub fn reply_subject(subject: &str) -> String {
+    if subject.to_ascii_lowercase().starts_with("re:") {
+        subject.to_string()
+    } else {
+        format!("Re: {}", subject)
+    }
+}
+
+/// Message `id`; fails if it is missing or some other kind of issue.
+pub fn get_message(store: &impl Store, id: &str) -> Result<Issue> {
+    let issue = store
+        .get_issue(id)?
+        .ok_or_else(|| anyhow::anyhow!("message not found: {}", id))?;
+    if issue.issue_type != TYPE_MESSAGE {
+        anyhow::bail!("{} is not a message (type: {})", id, issue.issue_type);
+    }
+    Ok(issue)
+}
+
+/// Unread messages to `identity`, most urgent first, then newest first.
+pub fn inbox(store: &impl Store, identity: &str) -> Result<Vec<Issue>> {
+    let mut messages = store.list_issues(&IssueFilter {
+        statuses: vec!["open".to_string()],
+        assignee: Some(identity.to_string()),
+        issue_type: Some(TYPE_MESSAGE.to_string()),
+        ..Default::default()
+    })?;
+    messages.sort_by(|a, b| {
+        a.priority
+            .cmp(&b.priority)
+            .then(b.created_at.cmp(&a.created_at))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    Ok(messages)
+}
+
+/// Marks message `id` read by closing it. Fails if it already is.
+pub fn acknowledge(store: &impl Store, id: &str, now: DateTime<Utc>) -> Result<()> {
+    let mut message = get_message(store, id)?;
+    if message.status == STATUS_CLOSED {
+        anyhow::bail!("{} is already acknowledged", id);
+    }
+    message.status = STATUS_CLOSED.to_string();
+    message.closed_at = Some(now);
+    message.updated_at = now;
+    store.update_issue(&message)
+}
+
+/// Deletes every closed ephemeral issue, i.e. acknowledged messages, and
+/// returns their IDs, sorted. `dry_run` only lists them.
+pub fn cleanup(store: &impl Store, actor: &str, dry_run: bool) -> Result<Vec<String>> {
+    let mut ids: Vec<String> = store
+        .list_issues(&IssueFilter {
+            statuses: vec![STATUS_CLOSED.to_string()],
+            ..Default::default()
+        })?
+        .into_iter()
+        .filter(|i| i.ephemeral)
+        .map(|i| i.id)
+        .collect();
+    ids.sort();
+    if !dry_run {
+        for id in &ids {
+            store.delete_issue(id, actor, CLEANUP_REASON)?;
+        }
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reply_subject() {
+        assert_eq!(reply_subject("Build broken"), "Re: Build broken");
+        assert_eq!(reply_subject("Re: Build broken"), "Re: Build broken");
+        assert_eq!(reply_subject("RE: Build broken"), "RE: Build broken");
+    }
+
+    #[test]
+    fn test_new_reply() -> Result<()> {
+        let now = Utc::now();
+        let original = new_message("bd-1", "bob", "alice", "Deploy?", "Ready?", 0, now);
+        assert!(original.ephemeral);
+        assert_eq!(original.assignee.as_deref(), Some("bob"));
+
+        let reply = new_reply("bd-2", &original, "bob", "Yes", PRIORITY_NORMAL, now)?;
+        assert_eq!(reply.assignee.as_deref(), Some("alice"));
+        assert_eq!(reply.sender, "bob");
+        assert_eq!(reply.title, "Re: Deploy?");
+        assert_eq!(reply.replies_to, "bd-1");
+
+        let anonymous = new_message("bd-3", "bob", "", "Hi", "", 2, now);
+        assert!(new_reply("bd-4", &anonymous, "bob", "Who?", 2, now).is_err());
+        Ok(())
+    }
+}
@@ -1,11 +1,16 @@
+use crate::filter::IssueFilter;
 use crate::fs::FileSystem;
-use crate::models::Issue;
-use crate::store::Store;
+use crate::graph::{self, Blockers, DependencyGraph};
+use crate::models::{
+    BlockedIssue, Comment, Dependency, ImportStats, Issue, IssueSummary, SearchResult,
+    STATUS_TOMBSTONE, TYPE_MESSAGE,
+};
+use crate::rename;
+use crate::store::{self, Store};
 use crate::util;
 use anyhow::Result;
 use chrono::Utc;
-use std::collections::{HashMap, HashSet};
-use std::io::{BufRead, Write};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 use std::sync::{Arc, RwLock};
 
@@ -19,9 +24,9 @@ pub struct MemoryStore {
 struct MemoryStoreInner {
     issues: HashMap<String, Issue>,
     config: HashMap<String, String>,
-    #[allow(dead_code)]
     metadata: HashMap<String, String>,
     dirty_issues: HashSet<String>,
+    child_counters: HashMap<String, u32>,
 }
 
 impl MemoryStore {
@@ -30,6 +35,56 @@ impl MemoryStore {
     }
 }
 
+impl MemoryStoreInner {
+    /// Stores `issue` with its content hash filled in.
+    fn insert(&mut self, mut issue: Issue) {
+        issue.content_hash = issue.compute_content_hash();
+        self.issues.insert(issue.id.clone(), issue);
+    }
+
+    fn graph(&self) -> DependencyGraph {
+        DependencyGraph::new(self.issues.values().flat_map(|i| i.dependencies.iter()))
+    }
+
+    /// Atomically replaces the JSONL with `contents` and records it as exported.
+    fn write_jsonl(
+        &mut self,
+        jsonl_path: &Path,
+        fs: &dyn FileSystem,
+        contents: &str,
+    ) -> Result<()> {
+        let temp_path = store::jsonl_temp_path(jsonl_path);
+        fs.write(&temp_path, contents.as_bytes())?;
+        fs.rename(&temp_path, jsonl_path)?;
+
+        self.metadata.insert(
+            store::JSONL_HASH_KEY.to_string(),
+            store::jsonl_hash(contents.as_bytes()),
+        );
+        self.dirty_issues.clear();
+        Ok(())
+    }
+
+    fn next_comment_id(&self) -> i64 {
+        self.issues
+            .values()
+            .flat_map(|i| i.comments.iter().map(|c| c.id))
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    fn blocked_map(&self) -> std::collections::BTreeMap<String, Blockers> {
+        let graph = self.graph();
+        let statuses: HashMap<String, String> = self
+            .issues
+            .values()
+            .map(|i| (i.id.clone(), i.status.clone()))
+            .collect();
+        graph.blocked(&statuses)
+    }
+}
+
 impl Store for MemoryStore {
     fn get_config(&self, key: &str) -> Result<Option<String>> {
         let inner = self.inner.read().unwrap();
@@ -44,7 +99,16 @@ impl Store for MemoryStore {
 
     fn update_issue(&self, issue: &Issue) -> Result<()> {
         let mut inner = self.inner.write().unwrap();
-        inner.issues.insert(issue.id.clone(), issue.clone());
+        inner.insert(issue.clone());
+        inner.dirty_issues.insert(issue.id.clone());
+        Ok(())
+    }
+
+    fn update_issue_if_unchanged(&self, issue: &Issue, expected_hash: &str) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        let stored = inner.issues.get(&issue.id).map(|i| i.content_hash.as_str());
+        store::check_content_hash(&issue.id, stored, expected_hash)?;
+        inner.insert(issue.clone());
         inner.dirty_issues.insert(issue.id.clone());
         Ok(())
     }
@@ -81,85 +145,66 @@ impl Store for MemoryStore {
         Ok(None)
     }
 
-    fn list_issues(
-        &self,
-        status: Option<&str>,
-        assignee: Option<&str>,
-        priority: Option<i32>,
-        issue_type: Option<&str>,
-        label: Option<&str>,
-        sort_by: Option<&str>,
-    ) -> Result<Vec<Issue>> {
+    fn list_issues(&self, filter: &IssueFilter) -> Result<Vec<Issue>> {
         let inner = self.inner.read().unwrap();
-        let mut issues: Vec<Issue> = inner.issues.values().cloned().collect();
+        let mut issues: Vec<Issue> = inner
+            .issues
+            .values()
+            .filter(|i| filter.matches(i))
+            .cloned()
+            .collect();
+        filter.sort_and_page(&mut issues);
+        Ok(issues)
+    }
+
+    fn import_issues(&mut self, issues: Vec<Issue>) -> Result<ImportStats> {
+        let mut stats = ImportStats::default();
+        let mut inner = self.inner.write().unwrap();
 
-        // Filtering
-        issues.retain(|issue| {
-            if let Some(s) = status {
-                if issue.status != s {
-                    return false;
+        for issue in issues {
+            match inner.issues.get_mut(&issue.id) {
+                Some(existing) if existing.same_fields(&issue) => {
+                    existing.labels = issue.labels;
+                    existing.dependencies = issue.dependencies;
+                    existing.comments = issue.comments;
+                    stats.unchanged += 1;
                 }
-            }
-            if let Some(a) = assignee {
-                if a == "unassigned" {
-                    if issue.assignee.is_some() && !issue.assignee.as_ref().unwrap().is_empty() {
-                        return false;
+                existing => {
+                    if existing.is_some() {
+                        stats.updated += 1;
+                    } else {
+                        stats.created += 1;
                     }
-                } else if issue.assignee.as_deref() != Some(a) {
-                    return false;
-                }
-            }
-            if let Some(p) = priority {
-                if issue.priority != p {
-                    return false;
-                }
-            }
-            if let Some(t) = issue_type {
-                if issue.issue_type != t {
-                    return false;
+                    inner.insert(issue);
                 }
             }
-            if let Some(l) = label {
-                if !issue.labels.contains(&l.to_string()) {
-                    return false;
-                }
-            }
-            true
-        });
-
-        // Sorting
-        match sort_by {
-            Some("updated") => issues.sort_by(|a, b| b.updated_at.cmp(&a.updated_at)),
-            Some("created") => issues.sort_by(|a, b| b.created_at.cmp(&a.created_at)),
-            Some("priority") => issues.sort_by(|a, b| {
-                a.priority
-                    .cmp(&b.priority)
-                    .then_with(|| b.created_at.cmp(&a.created_at))
-            }),
-            _ => issues.sort_by(|a, b| b.created_at.cmp(&a.created_at)),
         }
+        Ok(stats)
+    }
 
-        Ok(issues)
+    fn get_metadata(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.inner.read().unwrap().metadata.get(key).cloned())
     }
 
-    fn import_from_jsonl(&mut self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<()> {
-        if !fs.exists(jsonl_path) {
-            return Ok(());
-        }
-        let reader = fs.open_read(jsonl_path)?;
+    fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
         let mut inner = self.inner.write().unwrap();
-
-        for line in reader.lines() {
-            let line = line?;
-            if line.trim().is_empty() {
-                continue;
-            }
-            let issue: Issue = serde_json::from_str(&line)?;
-            inner.issues.insert(issue.id.clone(), issue);
-        }
+        inner.metadata.insert(key.to_string(), value.to_string());
         Ok(())
     }
 
+    fn dirty_issue_ids(&self) -> Result<Vec<String>> {
+        let mut ids: Vec<String> = self
+            .inner
+            .read()
+            .unwrap()
+            .dirty_issues
+            .iter()
+            .cloned()
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
     fn generate_unique_id(
         &self,
         prefix: &str,
@@ -167,72 +212,467 @@ impl Store for MemoryStore {
         description: &str,
         creator: &str,
     ) -> Result<String> {
+        let top_level = {
+            let inner = self.inner.read().unwrap();
+            inner
+                .issues
+                .keys()
+                .filter(|id| store::is_top_level_id(prefix, id))
+                .count()
+        };
+        let base_length = store::adaptive_id_length(self, top_level)?;
         let inner = self.inner.read().unwrap();
-        let created_at = Utc::now();
-        let base_length = 6;
-        let max_length = 8;
-
-        for length in base_length..=max_length {
-            for nonce in 0..10 {
-                let candidate = util::generate_hash_id(
-                    prefix,
-                    title,
-                    description,
-                    creator,
-                    created_at,
-                    length,
-                    nonce,
-                );
-                if !inner.issues.contains_key(&candidate) {
-                    return Ok(candidate);
-                }
-            }
-        }
-        Err(anyhow::anyhow!(
-            "Failed to generate unique ID after retries"
-        ))
+        util::generate_unique_hash_id(
+            prefix,
+            title,
+            description,
+            creator,
+            Utc::now(),
+            base_length,
+            |candidate| Ok(inner.issues.contains_key(candidate)),
+        )
+    }
+
+    fn next_child_id(&self, parent_id: &str) -> Result<String> {
+        let mut inner = self.inner.write().unwrap();
+        let status = inner.issues.get(parent_id).map(|i| i.status.as_str());
+        store::check_child_parent(parent_id, status)?;
+
+        let highest = inner
+            .issues
+            .keys()
+            .filter_map(|id| store::child_number(parent_id, id))
+            .max()
+            .unwrap_or(0);
+        let counter = inner
+            .child_counters
+            .entry(parent_id.to_string())
+            .or_default();
+        *counter = (*counter + 1).max(highest + 1);
+        Ok(format!("{}.{}", parent_id, counter))
     }
 
     fn create_issue(&self, issue: &Issue) -> Result<()> {
         let mut inner = self.inner.write().unwrap();
-        inner.issues.insert(issue.id.clone(), issue.clone());
+        inner.insert(issue.clone());
         inner.dirty_issues.insert(issue.id.clone());
         Ok(())
     }
 
+    fn create_issues(&self, issues: &[Issue]) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        for issue in issues {
+            inner.insert(issue.clone());
+            inner.dirty_issues.insert(issue.id.clone());
+        }
+        Ok(())
+    }
+
+    fn update_issues(&self, issues: &[Issue]) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        for issue in issues {
+            inner.insert(issue.clone());
+            inner.dirty_issues.insert(issue.id.clone());
+        }
+        Ok(())
+    }
+
     fn export_to_jsonl(&self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<()> {
         let mut inner = self.inner.write().unwrap();
 
-        let mut issues: Vec<_> = inner.issues.values().cloned().collect();
+        let mut issues: Vec<_> = inner.issues.values().collect();
         issues.sort_by(|a, b| a.id.cmp(&b.id));
 
-        // Use a temp path or string buffer? The fs trait works with paths.
-        // Assuming we can write to a temp file in the same dir.
-        let dir = jsonl_path.parent().unwrap_or_else(|| Path::new("."));
-        let file_name = jsonl_path.file_name().unwrap_or_default();
-        let temp_path = dir.join(format!(".{}.tmp", file_name.to_string_lossy()));
+        let mut contents = String::new();
+        for issue in issues {
+            contents.push_str(&serde_json::to_string(issue)?);
+            contents.push('\n');
+        }
+        inner.write_jsonl(jsonl_path, fs, &contents)
+    }
 
-        {
-            let file = fs.open_write(&temp_path)?;
-            let mut writer = std::io::BufWriter::new(file);
+    fn export_dirty_to_jsonl(&self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<()> {
+        if self.inner.read().unwrap().dirty_issues.is_empty() && fs.exists(jsonl_path) {
+            return Ok(());
+        }
+        let existing = if fs.exists(jsonl_path) {
+            Some(fs.read_to_string(jsonl_path)?)
+        } else {
+            None
+        };
 
-            for issue in &issues {
-                let json = serde_json::to_string(issue)?;
-                writeln!(writer, "{}", json)?;
+        let mut inner = self.inner.write().unwrap();
+        let patched = match existing {
+            Some(existing)
+                if inner.metadata.get(store::JSONL_HASH_KEY)
+                    == Some(&store::jsonl_hash(existing.as_bytes())) =>
+            {
+                let changed: BTreeMap<String, Option<Issue>> = inner
+                    .dirty_issues
+                    .iter()
+                    .map(|id| (id.clone(), inner.issues.get(id).cloned()))
+                    .collect();
+                store::patch_jsonl(&existing, &changed)?
+            }
+            _ => None,
+        };
+        match patched {
+            Some(contents) => inner.write_jsonl(jsonl_path, fs, &contents),
+            None => {
+                drop(inner);
+                self.export_to_jsonl(jsonl_path, fs)
             }
-            // flush handled by drop usually, but explicit flush is safer if we read immediately
-            writer.flush()?;
         }
+    }
 
-        fs.rename(&temp_path, jsonl_path)?;
+    fn ready_issues(&self) -> Result<Vec<Issue>> {
+        let inner = self.inner.read().unwrap();
+        let blocked = inner.blocked_map();
+
+        let mut issues: Vec<Issue> = inner
+            .issues
+            .values()
+            .filter(|i| {
+                graph::is_ready_status(&i.status)
+                    && i.issue_type != TYPE_MESSAGE
+                    && !blocked.contains_key(&i.id)
+            })
+            .cloned()
+            .collect();
+        graph::sort_by_priority(&mut issues);
+        Ok(issues)
+    }
+
+    fn blocked_issues(&self) -> Result<Vec<BlockedIssue>> {
+        let inner = self.inner.read().unwrap();
+        let mut blocked = inner.blocked_map();
+
+        let mut issues: Vec<Issue> = inner
+            .issues
+            .values()
+            .filter(|i| {
+                graph::is_active_status(&i.status)
+                    && i.issue_type != TYPE_MESSAGE
+                    && (blocked.contains_key(&i.id) || i.status == "blocked")
+            })
+            .cloned()
+            .collect();
+        graph::sort_by_priority(&mut issues);
 
-        // Update metadata hash (simplified for memory store)
-        // In real SqliteStore we read back and hash. Here we can probably skip or emulate.
-        // But to be consistent with Store behavior:
+        Ok(issues
+            .into_iter()
+            .map(|issue| {
+                let summary = |id: &String| inner.issues.get(id).map(IssueSummary::from);
+                let (blockers, blocked_via_parent) = match blocked.remove(&issue.id) {
+                    Some(Blockers::Direct(ids)) => (ids.iter().filter_map(summary).collect(), None),
+                    Some(Blockers::Parent(id)) => (Vec::new(), summary(&id)),
+                    None => (Vec::new(), None),
+                };
+                BlockedIssue {
+                    issue,
+                    blockers,
+                    blocked_via_parent,
+                }
+            })
+            .collect())
+    }
 
-        // Clear dirty issues
-        inner.dirty_issues.clear();
+    fn add_dependency(&self, dep: &Dependency) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        if !inner.issues.contains_key(&dep.issue_id) {
+            anyhow::bail!("issue {} not found", dep.issue_id);
+        }
+        if !inner.issues.contains_key(&dep.depends_on_id) {
+            anyhow::bail!("dependency target {} not found", dep.depends_on_id);
+        }
+        graph::validate_new_dependency(&inner.graph(), dep)?;
 
+        if let Some(issue) = inner.issues.get_mut(&dep.issue_id) {
+            issue.dependencies.push(dep.clone());
+        }
+        inner.dirty_issues.insert(dep.issue_id.clone());
+        inner.dirty_issues.insert(dep.depends_on_id.clone());
         Ok(())
     }
+
+    fn remove_dependency(&self, issue_id: &str, depends_on_id: &str) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        let removed = match inner.issues.get_mut(issue_id) {
+            Some(issue) => {
+                let before = issue.dependencies.len();
+                issue
+                    .dependencies
+                    .retain(|d| d.depends_on_id != depends_on_id);
+                before != issue.dependencies.len()
+            }
+            None => false,
+        };
+        if !removed {
+            anyhow::bail!(
+                "dependency from {} to {} does not exist",
+                issue_id,
+                depends_on_id
+            );
+        }
+        inner.dirty_issues.insert(issue_id.to_string());
+        inner.dirty_issues.insert(depends_on_id.to_string());
+        Ok(())
+    }
+
+    fn dependency_graph(&self) -> Result<DependencyGraph> {
+        Ok(self.inner.read().unwrap().graph())
+    }
+
+    fn delete_issue(&self, id: &str, actor: &str, reason: &str) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        let now = Utc::now();
+        let unlinked = match inner.issues.get_mut(id) {
+            None => anyhow::bail!("issue {} not found", id),
+            Some(issue) if issue.status == STATUS_TOMBSTONE => {
+                anyhow::bail!("issue {} is already deleted", id)
+            }
+            Some(issue) => {
+                issue.status = STATUS_TOMBSTONE.to_string();
+                issue.closed_at = None;
+                issue.deleted_at = Some(now);
+                issue.deleted_by = actor.to_string();
+                issue.delete_reason = reason.to_string();
+                issue.original_type = issue.issue_type.clone();
+                issue.updated_at = now;
+                issue.content_hash = issue.compute_content_hash();
+                std::mem::take(&mut issue.dependencies)
+            }
+        };
+
+        let mut dirty: Vec<String> = unlinked.into_iter().map(|d| d.depends_on_id).collect();
+        for issue in inner.issues.values_mut() {
+            let before = issue.dependencies.len();
+            issue.dependencies.retain(|d| d.depends_on_id != id);
+            if issue.dependencies.len() != before {
+                dirty.push(issue.id.clone());
+            }
+        }
+        dirty.push(id.to_string());
+        inner.dirty_issues.extend(dirty);
+        Ok(())
+    }
+
+    fn reopen_issue(&self, id: &str, actor: &str, reason: &str) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        let next_comment_id = inner.next_comment_id();
+        let issue = match inner.issues.get_mut(id) {
+            Some(issue) => issue,
+            None => anyhow::bail!("issue {} not found", id),
+        };
+        if issue.status != "closed" {
+            anyhow::bail!("issue {} is not closed (status: {})", id, issue.status);
+        }
+
+        let now = Utc::now();
+        issue.status = "open".to_string();
+        issue.closed_at = None;
+        issue.updated_at = now;
+        issue.content_hash = issue.compute_content_hash();
+        if !reason.is_empty() {
+            issue.comments.push(Comment {
+                id: next_comment_id,
+                issue_id: id.to_string(),
+                author: actor.to_string(),
+                text: reason.to_string(),
+                created_at: now,
+            });
+        }
+        inner.dirty_issues.insert(id.to_string());
+        Ok(())
+    }
+
+    fn undelete_issue(&self, id: &str) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        let issue = match inner.issues.get_mut(id) {
+            Some(issue) => issue,
+            None => anyhow::bail!("issue {} not found", id),
+        };
+        issue.issue_type = store::restorable_type(issue)?;
+        issue.status = "open".to_string();
+        issue.deleted_at = None;
+        issue.deleted_by.clear();
+        issue.delete_reason.clear();
+        issue.original_type.clear();
+        issue.updated_at = Utc::now();
+        issue.content_hash = issue.compute_content_hash();
+        inner.dirty_issues.insert(id.to_string());
+        Ok(())
+    }
+
+    fn add_comment(&self, issue_id: &str, author: &str, text: &str) -> Result<Comment> {
+        let mut inner = self.inner.write().unwrap();
+        let comment = Comment {
+            id: inner.next_comment_id(),
+            issue_id: issue_id.to_string(),
+            author: author.to_string(),
+            text: text.to_string(),
+            created_at: Utc::now(),
+        };
+        match inner.issues.get_mut(issue_id) {
+            Some(issue) => issue.comments.push(comment.clone()),
+            None => anyhow::bail!("issue {} not found", issue_id),
+        }
+        inner.dirty_issues.insert(issue_id.to_string());
+        Ok(comment)
+    }
+
+    fn list_comments(&self, issue_id: &str) -> Result<Vec<Comment>> {
+        let inner = self.inner.read().unwrap();
+        let mut comments = inner
+            .issues
+            .get(issue_id)
+            .map(|i| i.comments.clone())
+            .unwrap_or_default();
+        comments.sort_by_key(|c| (c.created_at, c.id));
+        Ok(comments)
+    }
+
+    fn apply_compaction(&self, issue: &Issue) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        let mut issue = issue.clone();
+        issue.comments.clear();
+        inner.dirty_issues.insert(issue.id.clone());
+        inner.insert(issue);
+        Ok(())
+    }
+
+    fn delete_comment(&self, comment_id: i64) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        let issue_id = inner.issues.values_mut().find_map(|issue| {
+            let pos = issue.comments.iter().position(|c| c.id == comment_id)?;
+            issue.comments.remove(pos);
+            Some(issue.id.clone())
+        });
+        match issue_id {
+            Some(issue_id) => {
+                inner.dirty_issues.insert(issue_id);
+                Ok(())
+            }
+            None => anyhow::bail!("comment {} not found", comment_id),
+        }
+    }
+
+    fn rename_prefix(
+        &self,
+        old_prefix: &str,
+        new_prefix: &str,
+        dry_run: bool,
+    ) -> Result<Vec<(String, String)>> {
+        let new_prefix = rename::validate_prefix(new_prefix)?;
+        if new_prefix == old_prefix {
+            anyhow::bail!(
+                "new prefix is the same as the current prefix: {}",
+                old_prefix
+            );
+        }
+        let mut inner = self.inner.write().unwrap();
+        let issues: Vec<Issue> = inner.issues.values().cloned().collect();
+        let rename::Plan { moves, changed } = rename::plan(&issues, old_prefix, &new_prefix)?;
+        if dry_run {
+            return Ok(moves);
+        }
+
+        for (old_id, issue) in changed {
+            inner.issues.remove(&old_id);
+            inner.dirty_issues.remove(&old_id);
+            if let Some(counter) = inner.child_counters.remove(&old_id) {
+                inner.child_counters.insert(issue.id.clone(), counter);
+            }
+            inner.dirty_issues.insert(issue.id.clone());
+            inner.insert(issue);
+        }
+        inner
+            .config
+            .insert("issue_id_prefix".to_string(), new_prefix);
+        Ok(moves)
+    }
+
+    fn search(&self, query: &str, filter: &IssueFilter) -> Result<Vec<SearchResult>> {
+        // Plain case-insensitive substring matching stands in for FTS5 here
+        let words: Vec<String> = query
+            .split_whitespace()
+            .map(|w| w.to_ascii_lowercase())
+            .collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let inner = self.inner.read().unwrap();
+        let mut results = Vec::new();
+        for issue in inner.issues.values().filter(|i| filter.matches(i)) {
+            let mut fields = vec![
+                issue.id.as_str(),
+                &issue.title,
+                &issue.description,
+                &issue.design,
+                &issue.acceptance_criteria,
+                &issue.notes,
+            ];
+            fields.extend(issue.comments.iter().map(|c| c.text.as_str()));
+            let lowered: Vec<String> = fields.iter().map(|f| f.to_ascii_lowercase()).collect();
+
+            let counts: Vec<usize> = words
+                .iter()
+                .map(|w| lowered.iter().map(|f| f.matches(w.as_str()).count()).sum())
+                .collect();
+            if counts.contains(&0) {
+                continue;
+            }
+
+            let snippet = lowered
+                .iter()
+                .zip(&fields)
+                .find_map(|(low, field)| {
+                    low.find(words[0].as_str())
+                        .map(|pos| snippet(field, pos, words[0].len()))
+                })
+                .unwrap_or_default();
+            results.push(SearchResult {
+                issue: issue.clone(),
+                rank: -(counts.iter().sum::<usize>() as f64),
+                snippet,
+            });
+        }
+
+        results.sort_by(|a, b| {
+            a.rank
+                .total_cmp(&b.rank)
+                .then_with(|| a.issue.priority.cmp(&b.issue.priority))
+                .then_with(|| b.issue.created_at.cmp(&a.issue.created_at))
+        });
+        results.drain(..filter.offset.min(results.len()));
+        if let Some(limit) = filter.limit {
+            results.truncate(limit);
+        }
+        Ok(results)
+    }
+}
+
+/// Excerpt of `text` around the match at `pos..pos + len`, with the match in
+/// brackets, mirroring the snippets produced by the SQLite store.
+fn snippet(text: &str, pos: usize, len: usize) -> String {
+    const CONTEXT: usize = 40;
+    let mut start = pos.saturating_sub(CONTEXT);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (pos + len + CONTEXT).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let excerpt = format!(
+        "{}{}[{}]{}{}",
+        if start > 0 { "…" } else { "" },
+        &text[start..pos],
+        &text[pos..pos + len],
+        &text[pos + len..end],
+        if end < text.len() { "…" } else { "" },
+    );
+    excerpt.replace('\n', " ")
 }
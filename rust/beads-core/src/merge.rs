@@ -1,18 +1,173 @@
 use crate::fs::FileSystem;
-use crate::models::{Comment, Dependency, Issue};
+use crate::models::{Comment, Dependency, Issue, STATUS_TOMBSTONE};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Utc};
-use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::{BufRead, BufWriter, Write};
 use std::path::Path;
 
-const STATUS_TOMBSTONE: &str = "tombstone";
 const STATUS_CLOSED: &str = "closed";
 
 // Constants from Go implementation
-const DEFAULT_TOMBSTONE_TTL_DAYS: i64 = 30;
+pub const DEFAULT_TOMBSTONE_TTL_DAYS: i64 = 30;
 const CLOCK_SKEW_GRACE_HOURS: i64 = 1;
 
+/// One of the two sides of a merge.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// What a merge did with one issue. The report's `side` says whose version
+/// or action it was.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueResolution {
+    /// On both sides and merged field by field.
+    Merged,
+    /// New on one side only, taken as is.
+    Added,
+    /// Deleted outright on one side; the deletion won over the other side.
+    Deleted,
+    /// One side's tombstone won over an edit or an older tombstone.
+    Tombstone,
+    /// One side's live issue won over the other side's expired tombstone.
+    TombstoneExpired,
+}
+
+/// How a merge settled one field changed on at least one side. Fields
+/// neither side changed keep the base value and are not reported.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldResolution {
+    /// Taken from the left side: only it changed the field, or a rule
+    /// preferred it (closed over open, the more urgent priority, the side
+    /// compacted further).
+    Left,
+    Right,
+    /// Both sides made the same change.
+    Both,
+    /// Both sides changed it differently; the side updated last won.
+    LeftNewer,
+    RightNewer,
+    /// Both sides' notes were kept, left first.
+    Concatenated,
+    /// Additions and removals from both sides were applied (labels,
+    /// dependencies, related issues, comments).
+    Merged,
+}
+
+/// An entry of a set-valued field removed on one side but changed or added
+/// back on the other. The side updated last wins.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct EntryConflict {
+    pub field: &'static str,
+    pub entry: String,
+    /// Whether the entry survived the merge.
+    pub kept: bool,
+    /// The side that won.
+    pub side: Side,
+}
+
+/// The decisions a merge made about one issue.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct IssueReport {
+    pub id: String,
+    pub resolution: IssueResolution,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<&'static str, FieldResolution>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<EntryConflict>,
+}
+
+impl IssueReport {
+    fn new(id: &str, resolution: IssueResolution, side: Option<Side>) -> Self {
+        IssueReport {
+            id: id.to_string(),
+            resolution,
+            side,
+            fields: BTreeMap::new(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    /// Whether `side` had a hand in it, rather than the other side's
+    /// version being kept as is.
+    pub fn involves(&self, side: Side) -> bool {
+        let other_only = match side {
+            Side::Left => FieldResolution::Right,
+            Side::Right => FieldResolution::Left,
+        };
+        match self.resolution {
+            IssueResolution::Merged => {
+                !self.conflicts.is_empty() || self.fields.values().any(|f| *f != other_only)
+            }
+            _ => self.side == Some(side),
+        }
+    }
+
+    /// One line for people, naming the sides `names`, e.g.
+    /// `["remote", "local"]`.
+    pub fn describe(&self, names: [&str; 2]) -> String {
+        let name = |side: Option<Side>| match side {
+            Some(Side::Left) => names[0],
+            Some(Side::Right) => names[1],
+            None => "",
+        };
+        let what = match self.resolution {
+            IssueResolution::Merged => self
+                .fields
+                .iter()
+                .filter(|(field, _)| !self.conflicts.iter().any(|c| c.field == **field))
+                .map(|(field, resolution)| match resolution {
+                    FieldResolution::Left => format!("{} from {}", field, names[0]),
+                    FieldResolution::Right => format!("{} from {}", field, names[1]),
+                    FieldResolution::Both => format!("{} changed alike on both", field),
+                    FieldResolution::LeftNewer => format!("{} from {} (newer)", field, names[0]),
+                    FieldResolution::RightNewer => format!("{} from {} (newer)", field, names[1]),
+                    FieldResolution::Concatenated => format!("{} concatenated", field),
+                    FieldResolution::Merged => format!("{} merged", field),
+                })
+                .chain(self.conflicts.iter().map(|c| {
+                    format!(
+                        "{} {} {} ({} newer)",
+                        c.field,
+                        c.entry,
+                        if c.kept { "kept" } else { "removed" },
+                        name(Some(c.side))
+                    )
+                }))
+                .collect::<Vec<_>>()
+                .join(", "),
+            IssueResolution::Added => format!("added on {}", name(self.side)),
+            IssueResolution::Deleted => format!("deleted on {}", name(self.side)),
+            IssueResolution::Tombstone => format!("deletion on {} won", name(self.side)),
+            IssueResolution::TombstoneExpired => {
+                format!("{} version kept over expired deletion", name(self.side))
+            }
+        };
+        format!("{}: {}", self.id, what)
+    }
+}
+
+/// Everything a merge decided, by issue ID. Issues identical on all sides
+/// are left out.
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    pub issues: Vec<IssueReport>,
+}
+
+impl MergeReport {
+    pub fn get(&self, id: &str) -> Option<&IssueReport> {
+        self.issues.iter().find(|i| i.id == id)
+    }
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 struct IssueKey {
     id: String,
@@ -31,6 +186,8 @@ struct IssueKey {
                     // So models::Issue::sender is the one.
 }
 
+/// Merges the issues in the base, left and right JSONL files into
+/// `output_path` and reports what it decided.
 pub fn merge3way(
     output_path: &str,
     base_path: &str,
@@ -38,7 +195,7 @@ pub fn merge3way(
     right_path: &str,
     debug: bool,
     fs: &impl FileSystem,
-) -> Result<()> {
+) -> Result<MergeReport> {
     if debug {
         eprintln!("=== DEBUG MODE ===");
         eprintln!("Output path: {}", output_path);
@@ -59,12 +216,12 @@ pub fn merge3way(
         eprintln!();
     }
 
-    let (result, conflicts) = merge_logic(base_issues, left_issues, right_issues);
+    let (result, report) = merge_logic(base_issues, left_issues, right_issues);
 
     if debug {
         eprintln!("Merge complete:");
         eprintln!("  Merged issues: {}", result.len());
-        eprintln!("  Conflicts: {}", conflicts.len());
+        eprintln!("  Resolved: {}", report.issues.len());
         eprintln!();
     }
 
@@ -79,15 +236,11 @@ pub fn merge3way(
         writeln!(writer, "{}", json)?;
     }
 
-    for conflict in conflicts {
-        writeln!(writer, "{}", conflict)?;
-    }
-
     if debug {
         eprintln!("Output written to: {}", output_path);
     }
 
-    Ok(())
+    Ok(report)
 }
 
 fn read_issues(path: &str, fs: &impl FileSystem) -> Result<Vec<Issue>> {
@@ -119,7 +272,9 @@ fn is_tombstone(issue: &Issue) -> bool {
     issue.status == STATUS_TOMBSTONE
 }
 
-fn is_expired_tombstone(issue: &Issue, ttl_days: i64) -> bool {
+/// True once a tombstone is older than `ttl_days` (0 means the default TTL)
+/// plus a small clock-skew grace period. Expired tombstones may be dropped.
+pub fn is_expired_tombstone(issue: &Issue, ttl_days: i64) -> bool {
     if !is_tombstone(issue) {
         return false;
     }
@@ -142,7 +297,7 @@ fn is_expired_tombstone(issue: &Issue, ttl_days: i64) -> bool {
     Utc::now() > expiration_time
 }
 
-fn merge_logic(base: Vec<Issue>, left: Vec<Issue>, right: Vec<Issue>) -> (Vec<Issue>, Vec<String>) {
+fn merge_logic(base: Vec<Issue>, left: Vec<Issue>, right: Vec<Issue>) -> (Vec<Issue>, MergeReport) {
     let mut base_map: HashMap<IssueKey, Issue> = HashMap::new();
     for i in base {
         base_map.insert(make_key(&i), i);
@@ -165,7 +320,7 @@ fn merge_logic(base: Vec<Issue>, left: Vec<Issue>, right: Vec<Issue>) -> (Vec<Is
     let mut processed_keys: HashSet<IssueKey> = HashSet::new();
     let mut processed_ids: HashSet<String> = HashSet::new();
     let mut result = Vec::new();
-    let mut conflicts = Vec::new();
+    let mut report = MergeReport::default();
 
     let mut all_keys: HashSet<IssueKey> = HashSet::new();
     all_keys.extend(base_map.keys().cloned());
@@ -215,226 +370,348 @@ fn merge_logic(base: Vec<Issue>, left: Vec<Issue>, right: Vec<Issue>) -> (Vec<Is
         let left_tombstone = in_left && is_tombstone(left_issue.as_ref().unwrap());
         let right_tombstone = in_right && is_tombstone(right_issue.as_ref().unwrap());
 
-        if in_base && in_left && in_right {
-            let left_i = left_issue.unwrap();
-            let right_i = right_issue.unwrap();
-            let base_i = base_issue.unwrap();
-
-            if left_tombstone && right_tombstone {
-                result.push(merge_tombstones(left_i, right_i));
-            } else if left_tombstone && !right_tombstone {
-                if is_expired_tombstone(&left_i, DEFAULT_TOMBSTONE_TTL_DAYS) {
-                    result.push(right_i);
-                } else {
-                    result.push(left_i);
-                }
-            } else if right_tombstone && !left_tombstone {
-                if is_expired_tombstone(&right_i, DEFAULT_TOMBSTONE_TTL_DAYS) {
-                    result.push(left_i);
-                } else {
-                    result.push(right_i);
-                }
-            } else {
-                let (merged, conflict) = merge_issue(base_i, left_i, right_i);
-                if !conflict.is_empty() {
-                    conflicts.push(conflict);
-                } else {
-                    result.push(merged);
-                }
-            }
-        } else if !in_base && in_left && in_right {
+        if in_left && in_right {
             let left_i = left_issue.unwrap();
             let right_i = right_issue.unwrap();
 
-            if left_tombstone && right_tombstone {
-                result.push(merge_tombstones(left_i, right_i));
-            } else if left_tombstone && !right_tombstone {
-                if is_expired_tombstone(&left_i, DEFAULT_TOMBSTONE_TTL_DAYS) {
-                    result.push(right_i);
+            if left_tombstone || right_tombstone {
+                let (side, resolution) = if left_tombstone && right_tombstone {
+                    (
+                        later_tombstone(&left_i, &right_i),
+                        IssueResolution::Tombstone,
+                    )
+                } else if left_tombstone {
+                    if is_expired_tombstone(&left_i, DEFAULT_TOMBSTONE_TTL_DAYS) {
+                        (Side::Right, IssueResolution::TombstoneExpired)
+                    } else {
+                        (Side::Left, IssueResolution::Tombstone)
+                    }
+                } else if is_expired_tombstone(&right_i, DEFAULT_TOMBSTONE_TTL_DAYS) {
+                    (Side::Left, IssueResolution::TombstoneExpired)
                 } else {
-                    result.push(left_i);
-                }
-            } else if right_tombstone && !left_tombstone {
-                if is_expired_tombstone(&right_i, DEFAULT_TOMBSTONE_TTL_DAYS) {
-                    result.push(left_i);
-                } else {
-                    result.push(right_i);
+                    (Side::Right, IssueResolution::Tombstone)
+                };
+                // The same deletion on both sides is nothing to report.
+                if !(left_tombstone && right_tombstone && left_i.deleted_at == right_i.deleted_at) {
+                    report
+                        .issues
+                        .push(IssueReport::new(&key.id, resolution, Some(side)));
                 }
+                result.push(match side {
+                    Side::Left => left_i,
+                    Side::Right => right_i,
+                });
             } else {
-                // Merge with empty base
-                let empty_base = Issue {
+                // Merge with an empty base if neither had it before
+                let base_i = base_issue.unwrap_or_else(|| Issue {
                     id: left_i.id.clone(),
                     created_at: left_i.created_at,
                     sender: left_i.sender.clone(),
-                    // ... set defaults for others
-                    content_hash: String::new(),
-                    title: String::new(),
-                    description: String::new(),
-                    design: String::new(),
-                    acceptance_criteria: String::new(),
-                    notes: String::new(),
-                    status: String::new(),
-                    priority: 0,
-                    issue_type: String::new(),
-                    assignee: None,
-                    estimated_minutes: None,
-                    updated_at: left_i.updated_at, // Use left's updated at as base? No, empty base should have defaults.
-                    closed_at: None,
-                    external_ref: None,
-                    ephemeral: false,
-                    replies_to: String::new(),
-                    relates_to: Vec::new(),
-                    duplicate_of: String::new(),
-                    superseded_by: String::new(),
-                    deleted_at: None,
-                    deleted_by: String::new(),
-                    delete_reason: String::new(),
-                    original_type: String::new(),
-                    labels: Vec::new(),
-                    dependencies: Vec::new(),
-                    comments: Vec::new(),
-                };
-                let (merged, _) = merge_issue(empty_base, left_i, right_i);
+                    updated_at: left_i.updated_at,
+                    ..Default::default()
+                });
+                let (merged, merge) = merge_issue(base_i, left_i, right_i);
+                if !merge.fields.is_empty() {
+                    report.issues.push(merge);
+                }
                 result.push(merged);
             }
-        } else if in_base && in_left && !in_right {
+        } else if in_base && in_left {
             // Deleted in right
             if left_tombstone {
                 result.push(left_issue.unwrap());
+            } else {
+                // Implicitly deleted in right -> delete wins
+                report.issues.push(IssueReport::new(
+                    &key.id,
+                    IssueResolution::Deleted,
+                    Some(Side::Right),
+                ));
             }
-            // Else implicitly deleted in right -> delete wins
-        } else if in_base && !in_left && in_right {
+        } else if in_base && in_right {
             // Deleted in left
             if right_tombstone {
                 result.push(right_issue.unwrap());
+            } else {
+                // Implicitly deleted in left -> delete wins
+                report.issues.push(IssueReport::new(
+                    &key.id,
+                    IssueResolution::Deleted,
+                    Some(Side::Left),
+                ));
             }
-            // Else implicitly deleted in left -> delete wins
-        } else if !in_base && in_left && !in_right {
+        } else if in_left {
+            report.issues.push(IssueReport::new(
+                &key.id,
+                IssueResolution::Added,
+                Some(Side::Left),
+            ));
             result.push(left_issue.unwrap());
-        } else if !in_base && !in_left && in_right {
+        } else if in_right {
+            report.issues.push(IssueReport::new(
+                &key.id,
+                IssueResolution::Added,
+                Some(Side::Right),
+            ));
             result.push(right_issue.unwrap());
         }
     }
 
-    (result, conflicts)
+    report.issues.sort_by(|a, b| a.id.cmp(&b.id));
+    (result, report)
 }
 
-fn merge_tombstones(left: Issue, right: Issue) -> Issue {
+/// The side whose tombstone wins when both deleted an issue: the later
+/// deletion, or left if neither records when.
+fn later_tombstone(left: &Issue, right: &Issue) -> Side {
     if left.deleted_at.is_none() && right.deleted_at.is_none() {
-        return left;
+        return Side::Left;
     }
     if left.deleted_at.is_none() {
-        return right;
+        return Side::Right;
     }
     if right.deleted_at.is_none() {
-        return left;
+        return Side::Left;
     }
     if is_time_after(left.deleted_at, right.deleted_at) {
-        return left;
+        return Side::Left;
     }
-    right
+    Side::Right
 }
 
-fn merge_issue(base: Issue, left: Issue, right: Issue) -> (Issue, String) {
-    let mut result = base.clone();
+/// Entries of a set a merge had to decide between removal on one side and
+/// change on the other, with whether each was kept.
+type Removals = Vec<(String, bool)>;
+
+/// How a field of an issue is merged. Except under `Rule`, a change made on
+/// one side only is taken as is and the strategy settles clashes, where both
+/// sides changed the field differently.
+enum Strategy<T> {
+    /// The side updated last wins.
+    UpdatedAt,
+    /// The side `prefer` picks from the two whole issues wins, so fields
+    /// that belong together come from the same side.
+    Prefer(fn(&Issue, &Issue) -> Side),
+    /// `rule(base, left, right)` decides every case, e.g. closed beats open.
+    Rule(fn(&T, &T, &T) -> T),
+    /// Text from both sides is joined.
+    Concat(fn(&T, &T) -> T),
+    /// `merge(base, left, right, newer)` merges entries as a set.
+    Set(fn(&T, &T, &T, Side) -> (T, Removals)),
+}
 
-    // Merge fields
-    result.title = merge_field_by_updated_at(
-        &base.title,
-        &left.title,
-        &right.title,
-        &left.updated_at,
-        &right.updated_at,
-    );
-    result.description = merge_field_by_updated_at(
-        &base.description,
-        &left.description,
-        &right.description,
-        &left.updated_at,
-        &right.updated_at,
-    );
-    result.notes = merge_notes(&base.notes, &left.notes, &right.notes);
-    result.status = merge_status(&base.status, &left.status, &right.status);
-    result.priority = merge_priority(base.priority, left.priority, right.priority);
-    result.issue_type = merge_field(&base.issue_type, &left.issue_type, &right.issue_type);
+/// The three versions of an issue being merged, and the decisions made so
+/// far.
+struct FieldMerge<'a> {
+    base: &'a Issue,
+    left: &'a Issue,
+    right: &'a Issue,
+    report: IssueReport,
+}
 
-    result.updated_at = max_time(left.updated_at, right.updated_at);
+impl FieldMerge<'_> {
+    /// Merges the field `get` reads with `strategy` and records how, as
+    /// `field`, unless neither side changed it.
+    fn merge<T: Clone + PartialEq>(
+        &mut self,
+        field: &'static str,
+        get: fn(&Issue) -> &T,
+        strategy: Strategy<T>,
+    ) -> T {
+        let (base, left, right) = (get(self.base), get(self.left), get(self.right));
+        let clash = left != base && right != base && left != right;
+        let pick = |side| match side {
+            Side::Left => left.clone(),
+            Side::Right => right.clone(),
+        };
+        let merged = match strategy {
+            Strategy::Rule(rule) => rule(base, left, right),
+            _ if left == base => right.clone(),
+            _ if !clash => left.clone(),
+            Strategy::UpdatedAt => pick(newer(self.left, self.right)),
+            Strategy::Prefer(prefer) => pick(prefer(self.left, self.right)),
+            Strategy::Concat(join) => join(left, right),
+            Strategy::Set(merge) => {
+                let side = newer(self.left, self.right);
+                let (merged, entries) = merge(base, left, right, side);
+                for (entry, kept) in entries {
+                    self.report.conflicts.push(EntryConflict {
+                        field,
+                        entry,
+                        kept,
+                        side,
+                    });
+                }
+                merged
+            }
+        };
+
+        let resolution = if left == base && right == base {
+            return merged;
+        } else if left == right {
+            FieldResolution::Both
+        } else if merged == *left {
+            match strategy {
+                Strategy::UpdatedAt if clash => FieldResolution::LeftNewer,
+                _ => FieldResolution::Left,
+            }
+        } else if merged == *right {
+            match strategy {
+                Strategy::UpdatedAt if clash => FieldResolution::RightNewer,
+                _ => FieldResolution::Right,
+            }
+        } else {
+            match strategy {
+                Strategy::Concat(_) => FieldResolution::Concatenated,
+                _ => FieldResolution::Merged,
+            }
+        };
+        self.report.fields.insert(field, resolution);
+        merged
+    }
+}
 
-    if result.status == STATUS_CLOSED {
-        result.closed_at = max_time_opt(left.closed_at, right.closed_at);
+/// The side updated last; right on a tie.
+fn newer(left: &Issue, right: &Issue) -> Side {
+    if left.updated_at > right.updated_at {
+        Side::Left
     } else {
-        result.closed_at = None;
-    }
-
-    result.dependencies = merge_dependencies(&left.dependencies, &right.dependencies);
-    result.labels = merge_labels(&left.labels, &right.labels);
-    result.comments = merge_comments(&left.comments, &right.comments);
-
-    // Copy tombstone fields if status became tombstone
-    if result.status == STATUS_TOMBSTONE {
-        if is_time_after(left.deleted_at, right.deleted_at) {
-            result.deleted_at = left.deleted_at;
-            result.deleted_by = left.deleted_by;
-            result.delete_reason = left.delete_reason;
-            result.original_type = left.original_type;
-        } else if right.deleted_at.is_some() {
-            result.deleted_at = right.deleted_at;
-            result.deleted_by = right.deleted_by;
-            result.delete_reason = right.delete_reason;
-            result.original_type = right.original_type;
-        } else if left.deleted_at.is_some() {
-            result.deleted_at = left.deleted_at;
-            result.deleted_by = left.deleted_by;
-            result.delete_reason = left.delete_reason;
-            result.original_type = left.original_type;
-        }
+        Side::Right
     }
-
-    (result, String::new())
 }
 
-fn merge_field(base: &str, left: &str, right: &str) -> String {
-    if base == left && base != right {
-        return right.to_string();
-    }
-    if base == right && base != left {
-        return left.to_string();
+/// The side compacted to the higher tier, or more recently; right on a tie.
+fn further_compacted(left: &Issue, right: &Issue) -> Side {
+    if (left.compaction_level, left.compacted_at) > (right.compaction_level, right.compacted_at) {
+        Side::Left
+    } else {
+        Side::Right
     }
-    left.to_string()
 }
 
-fn merge_field_by_updated_at(
-    base: &str,
-    left: &str,
-    right: &str,
-    left_updated: &DateTime<Utc>,
-    right_updated: &DateTime<Utc>,
-) -> String {
-    if base == left && base != right {
-        return right.to_string();
-    }
-    if base == right && base != left {
-        return left.to_string();
-    }
-    if left == right {
-        return left.to_string();
-    }
-    if *left_updated > *right_updated {
-        return left.to_string();
-    }
-    right.to_string()
+fn merge_issue(base: Issue, left: Issue, right: Issue) -> (Issue, IssueReport) {
+    use Strategy::*;
+
+    let mut m = FieldMerge {
+        base: &base,
+        left: &left,
+        right: &right,
+        report: IssueReport::new(&left.id, IssueResolution::Merged, None),
+    };
+    // Every field is listed, so a new one cannot be forgotten here.
+    let status = m.merge::<String>(
+        "status",
+        |i| &i.status,
+        Rule(|b, l, r| merge_status(b, l, r)),
+    );
+    let closed_at = if status == STATUS_CLOSED {
+        m.merge(
+            "closed_at",
+            |i| &i.closed_at,
+            Rule(|_, l, r| max_time_opt(*l, *r)),
+        )
+    } else {
+        None
+    };
+    let result = Issue {
+        id: left.id.clone(),
+        content_hash: String::new(),
+        title: m.merge("title", |i| &i.title, UpdatedAt),
+        description: m.merge("description", |i| &i.description, UpdatedAt),
+        design: m.merge("design", |i| &i.design, UpdatedAt),
+        acceptance_criteria: m.merge("acceptance_criteria", |i| &i.acceptance_criteria, UpdatedAt),
+        notes: m.merge("notes", |i| &i.notes, Concat(|l, r| concat_notes(l, r))),
+        status,
+        priority: m.merge(
+            "priority",
+            |i| &i.priority,
+            Rule(|b, l, r| merge_priority(*b, *l, *r)),
+        ),
+        issue_type: m.merge::<String>(
+            "issue_type",
+            |i| &i.issue_type,
+            Rule(|b, l, r| merge_field(b, l, r)),
+        ),
+        assignee: m.merge("assignee", |i| &i.assignee, UpdatedAt),
+        estimated_minutes: m.merge("estimated_minutes", |i| &i.estimated_minutes, UpdatedAt),
+        created_at: m.merge("created_at", |i| &i.created_at, Rule(|_, l, r| *l.min(r))),
+        // Bookkeeping, not reported
+        updated_at: max_time(left.updated_at, right.updated_at),
+        closed_at,
+        external_ref: m.merge("external_ref", |i| &i.external_ref, UpdatedAt),
+        compaction_level: m.merge(
+            "compaction_level",
+            |i| &i.compaction_level,
+            Prefer(further_compacted),
+        ),
+        compacted_at: m.merge(
+            "compacted_at",
+            |i| &i.compacted_at,
+            Prefer(further_compacted),
+        ),
+        compacted_at_commit: m.merge(
+            "compacted_at_commit",
+            |i| &i.compacted_at_commit,
+            Prefer(further_compacted),
+        ),
+        original_size: m.merge(
+            "original_size",
+            |i| &i.original_size,
+            Prefer(further_compacted),
+        ),
+        sender: m.merge("sender", |i| &i.sender, UpdatedAt),
+        ephemeral: m.merge("ephemeral", |i| &i.ephemeral, UpdatedAt),
+        replies_to: m.merge("replies_to", |i| &i.replies_to, UpdatedAt),
+        relates_to: m.merge(
+            "relates_to",
+            |i| &i.relates_to,
+            Set(|b, l, r, newer| merge_set(b, l, r, newer, String::clone)),
+        ),
+        duplicate_of: m.merge("duplicate_of", |i| &i.duplicate_of, UpdatedAt),
+        superseded_by: m.merge("superseded_by", |i| &i.superseded_by, UpdatedAt),
+        deleted_at: m.merge("deleted_at", |i| &i.deleted_at, Prefer(later_tombstone)),
+        deleted_by: m.merge("deleted_by", |i| &i.deleted_by, Prefer(later_tombstone)),
+        delete_reason: m.merge(
+            "delete_reason",
+            |i| &i.delete_reason,
+            Prefer(later_tombstone),
+        ),
+        original_type: m.merge(
+            "original_type",
+            |i| &i.original_type,
+            Prefer(later_tombstone),
+        ),
+        labels: m.merge(
+            "labels",
+            |i| &i.labels,
+            Set(|b, l, r, newer| merge_set(b, l, r, newer, String::clone)),
+        ),
+        dependencies: m.merge(
+            "dependencies",
+            |i| &i.dependencies,
+            Set(|b, l, r, newer| merge_set(b, l, r, newer, dependency_key)),
+        ),
+        comments: m.merge(
+            "comments",
+            |i| &i.comments,
+            Set(|b, l, r, newer| merge_comments(b, l, r, newer)),
+        ),
+    };
+    (result, m.report)
 }
 
-fn merge_notes(base: &str, left: &str, right: &str) -> String {
+fn merge_field(base: &str, left: &str, right: &str) -> String {
     if base == left && base != right {
         return right.to_string();
     }
     if base == right && base != left {
         return left.to_string();
     }
-    if left == right {
-        return left.to_string();
-    }
+    left.to_string()
+}
+
+/// Both sides' notes, left first; just one if the other is empty.
+fn concat_notes(left: &str, right: &str) -> String {
     if left.is_empty() {
         return right.to_string();
     }
@@ -504,48 +781,95 @@ fn max_time_opt(t1: Option<DateTime<Utc>>, t2: Option<DateTime<Utc>>) -> Option<
     }
 }
 
-fn merge_dependencies(left: &[Dependency], right: &[Dependency]) -> Vec<Dependency> {
+/// Three-way merge of a set of entries identified by `key`: additions and
+/// removals from either side are kept, in left's order and then right's. An
+/// entry removed on one side but changed or added back on the other goes the
+/// way of the `newer` side; those are returned too.
+fn merge_set<E: Clone + PartialEq>(
+    base: &[E],
+    left: &[E],
+    right: &[E],
+    newer: Side,
+    key: fn(&E) -> String,
+) -> (Vec<E>, Removals) {
+    let find = |entries: &[E], k: &str| entries.iter().find(|e| key(e) == k).cloned();
     let mut seen = HashSet::new();
     let mut result = Vec::new();
+    let mut conflicts = Vec::new();
 
-    for dep in left.iter().chain(right.iter()) {
-        let key = format!("{}:{}:{}", dep.issue_id, dep.depends_on_id, dep.type_);
-        if !seen.contains(&key) {
-            seen.insert(key);
-            result.push(dep.clone());
+    for k in left.iter().chain(right).map(key) {
+        if !seen.insert(k.clone()) {
+            continue;
         }
+        let (b, l, r) = (find(base, &k), find(left, &k), find(right, &k));
+        let merged = match (l, r) {
+            (Some(l), Some(r)) => Some(if l == r || b.as_ref() == Some(&r) {
+                l
+            } else if b.as_ref() == Some(&l) || newer == Side::Right {
+                r
+            } else {
+                l
+            }),
+            // Added on one side
+            (Some(kept), None) | (None, Some(kept)) if b.is_none() => Some(kept),
+            // Removed on one side, left alone on the other
+            (Some(kept), None) | (None, Some(kept)) if b.as_ref() == Some(&kept) => None,
+            // Removed on one side, changed on the other
+            (Some(l), None) => {
+                conflicts.push((k, newer == Side::Left));
+                (newer == Side::Left).then_some(l)
+            }
+            (None, Some(r)) => {
+                conflicts.push((k, newer == Side::Right));
+                (newer == Side::Right).then_some(r)
+            }
+            (None, None) => None,
+        };
+        result.extend(merged);
     }
-    result
+    (result, conflicts)
 }
 
-fn merge_labels(left: &[String], right: &[String]) -> Vec<String> {
-    let mut seen = HashSet::new();
-    let mut result = Vec::new();
-
-    for label in left.iter().chain(right.iter()) {
-        if !seen.contains(label) {
-            seen.insert(label.clone());
-            result.push(label.clone());
-        }
-    }
-    result
+/// How a dependency is told apart from its issue's others, e.g.
+/// `bd-1 -> bd-2 (blocks)` for bd-1 depending on bd-2.
+fn dependency_key(dep: &Dependency) -> String {
+    format!("{} -> {} ({})", dep.issue_id, dep.depends_on_id, dep.type_)
 }
 
-fn merge_comments(left: &[Comment], right: &[Comment]) -> Vec<Comment> {
-    let mut seen = HashSet::new();
-    let mut result = Vec::new();
+/// How a comment is told apart from its issue's others: author and text,
+/// as Go keys them.
+fn comment_key(comment: &Comment) -> String {
+    format!("{}:{}", comment.author, comment.text)
+}
 
-    for c in left.iter().chain(right.iter()) {
-        // Key by author + text (approximate deduping)
-        // We ignore timestamp for deduping to handle slight skews or edits?
-        // Go uses author+text.
-        let key = format!("{}:{}", c.author, c.text);
-        if !seen.contains(&key) {
-            seen.insert(key);
-            result.push(c.clone());
-        }
-    }
-    result
+/// `merge_set` for comments. Their IDs are assigned by each database, so
+/// the same comment can carry different ones on each side: they are left
+/// out of the comparison, and the result keeps the right side's, else the
+/// left's.
+fn merge_comments(
+    base: &[Comment],
+    left: &[Comment],
+    right: &[Comment],
+    newer: Side,
+) -> (Vec<Comment>, Removals) {
+    let bare = |comments: &[Comment]| -> Vec<Comment> {
+        comments
+            .iter()
+            .map(|c| Comment { id: 0, ..c.clone() })
+            .collect()
+    };
+    let (merged, removals) = merge_set(&bare(base), &bare(left), &bare(right), newer, comment_key);
+    let merged = merged
+        .into_iter()
+        .map(|mut c| {
+            let key = comment_key(&c);
+            if let Some(original) = right.iter().chain(left).find(|o| comment_key(o) == key) {
+                c.id = original.id;
+            }
+            c
+        })
+        .collect();
+    (merged, removals)
 }
 
 #[cfg(test)]
@@ -571,6 +895,10 @@ mod tests {
             updated_at: Utc.timestamp_opt(updated_at, 0).unwrap(),
             closed_at: None,
             external_ref: None,
+            compaction_level: 0,
+            compacted_at: None,
+            compacted_at_commit: None,
+            original_size: 0,
             sender: "user".to_string(),
             ephemeral: false,
             replies_to: String::new(),
@@ -601,10 +929,12 @@ mod tests {
         let left = create_issue("1", "Left Title", now - 100);
         let right = create_issue("1", "Base Title", now - 200);
 
-        let (result, conflicts) = merge_logic(vec![base], vec![left], vec![right]);
+        let (result, report) = merge_logic(vec![base], vec![left], vec![right]);
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].title, "Left Title");
-        assert!(conflicts.is_empty());
+        let fields = &report.get("1").unwrap().fields;
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields["title"], FieldResolution::Left);
     }
 
     #[test]
@@ -614,10 +944,12 @@ mod tests {
         let left = create_issue("1", "Left Title", now - 200);
         let right = create_issue("1", "Right Title", now - 100);
 
-        let (result, conflicts) = merge_logic(vec![base], vec![left], vec![right]);
+        let (result, report) = merge_logic(vec![base], vec![left], vec![right]);
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].title, "Right Title");
-        assert!(conflicts.is_empty());
+        let merge = report.get("1").unwrap();
+        assert_eq!(merge.resolution, IssueResolution::Merged);
+        assert_eq!(merge.fields["title"], FieldResolution::RightNewer);
     }
 
     #[test]
@@ -627,10 +959,12 @@ mod tests {
         let left = create_tombstone("1", now - 200);
         let right = create_issue("1", "Right Title", now - 100);
 
-        let (result, conflicts) = merge_logic(vec![base], vec![left], vec![right]);
+        let (result, report) = merge_logic(vec![base], vec![left], vec![right]);
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].status, STATUS_TOMBSTONE);
-        assert!(conflicts.is_empty());
+        let merge = report.get("1").unwrap();
+        assert_eq!(merge.resolution, IssueResolution::Tombstone);
+        assert_eq!(merge.side, Some(Side::Left));
     }
 
     #[test]
@@ -640,7 +974,7 @@ mod tests {
         let left = vec![create_issue("L1", "Left Only", now)];
         let right = vec![create_issue("R1", "Right Only", now)];
 
-        let (result, conflicts) = merge_logic(base, left, right);
+        let (result, report) = merge_logic(base, left, right);
         assert_eq!(result.len(), 2);
 
         let mut sorted = result;
@@ -648,6 +982,317 @@ mod tests {
 
         assert_eq!(sorted[0].id, "L1");
         assert_eq!(sorted[1].id, "R1");
-        assert!(conflicts.is_empty());
+        assert_eq!(report.issues[0].resolution, IssueResolution::Added);
+        assert_eq!(report.issues[0].side, Some(Side::Left));
+        assert_eq!(report.issues[1].side, Some(Side::Right));
+    }
+
+    #[test]
+    fn test_merge_report() {
+        let now = Utc::now().timestamp();
+        let base = create_issue("1", "Title", now - 300);
+        let mut left = create_issue("1", "Title", now - 200);
+        left.notes = "left note".to_string();
+        left.labels = vec!["ui".to_string()];
+        left.status = STATUS_CLOSED.to_string();
+        left.closed_at = Some(left.updated_at);
+        let mut right = create_issue("1", "Title", now - 100);
+        right.notes = "right note".to_string();
+        right.labels = vec!["backend".to_string()];
+        right.priority = 1;
+        let unchanged = create_issue("2", "Same", now - 300);
+
+        let (result, report) = merge_logic(
+            vec![base, unchanged.clone()],
+            vec![left, unchanged.clone()],
+            vec![right, unchanged],
+        );
+        assert_eq!(result.len(), 2);
+        assert_eq!(report.issues.len(), 1, "unchanged issues are left out");
+        let merge = report.get("1").unwrap();
+        assert_eq!(
+            serde_json::to_value(merge).unwrap(),
+            serde_json::json!({
+                "id": "1",
+                "resolution": "merged",
+                "fields": {
+                    "closed_at": "left",
+                    "labels": "merged",
+                    "notes": "concatenated",
+                    "priority": "right",
+                    "status": "left",
+                },
+            })
+        );
+        assert_eq!(
+            merge.describe(["remote", "local"]),
+            "1: closed_at from remote, labels merged, notes concatenated, \
+             priority from local, status from remote"
+        );
+        assert!(merge.involves(Side::Left) && merge.involves(Side::Right));
+
+        let mut local = IssueReport::new("1", IssueResolution::Merged, None);
+        local.fields.insert("priority", FieldResolution::Right);
+        assert!(!local.involves(Side::Left) && local.involves(Side::Right));
+        let added = IssueReport::new("2", IssueResolution::Added, Some(Side::Right));
+        assert!(!added.involves(Side::Left) && added.involves(Side::Right));
+    }
+
+    fn dependency(issue_id: &str, depends_on_id: &str) -> Dependency {
+        Dependency {
+            issue_id: issue_id.to_string(),
+            depends_on_id: depends_on_id.to_string(),
+            type_: "blocks".to_string(),
+            created_at: Utc.timestamp_opt(1000, 0).unwrap(),
+            created_by: "user".to_string(),
+        }
+    }
+
+    fn comment(id: i64, text: &str) -> Comment {
+        Comment {
+            id,
+            issue_id: "1".to_string(),
+            author: "user".to_string(),
+            text: text.to_string(),
+            created_at: Utc.timestamp_opt(1000, 0).unwrap(),
+        }
+    }
+
+    /// Sets every field that can clash to a value particular to `side`.
+    fn edit_everything(issue: &mut Issue, side: &str, compaction_level: i32) {
+        issue.title = format!("{} title", side);
+        issue.description = format!("{} description", side);
+        issue.design = format!("{} design", side);
+        issue.acceptance_criteria = format!("{} criteria", side);
+        issue.notes = format!("{} notes", side);
+        issue.assignee = Some(side.to_string());
+        issue.estimated_minutes = Some(compaction_level * 30);
+        issue.external_ref = Some(format!("gh-{}", side));
+        issue.compaction_level = compaction_level;
+        issue.compacted_at = Some(issue.updated_at);
+        issue.compacted_at_commit = Some(format!("{}-commit", side));
+        issue.original_size = compaction_level * 100;
+        issue.sender = side.to_string();
+        issue.replies_to = format!("{}-msg", side);
+        issue.relates_to = vec![format!("{}-related", side)];
+        issue.duplicate_of = format!("{}-dup", side);
+        issue.superseded_by = format!("{}-new", side);
+        issue.labels = vec![side.to_string()];
+        issue.dependencies = vec![dependency("1", side)];
+        issue.comments = vec![comment(compaction_level.into(), side)];
+    }
+
+    #[test]
+    fn test_merge_concurrent_edits_every_field() {
+        let now = Utc::now().timestamp();
+        let mut base = create_issue("1", "Title", now - 300);
+        base.priority = 2;
+        // Left is compacted further but right was updated later.
+        let mut left = base.clone();
+        left.updated_at = Utc.timestamp_opt(now - 200, 0).unwrap();
+        edit_everything(&mut left, "left", 2);
+        left.status = "in_progress".to_string();
+        left.priority = 3;
+        left.issue_type = "feature".to_string();
+        let mut right = base.clone();
+        right.updated_at = Utc.timestamp_opt(now - 100, 0).unwrap();
+        edit_everything(&mut right, "right", 1);
+        right.status = "blocked".to_string();
+        right.priority = 1;
+        right.issue_type = "task".to_string();
+
+        let (merged, IssueReport { fields, .. }) = merge_issue(base, left.clone(), right.clone());
+        use FieldResolution::*;
+        let expected = BTreeMap::from([
+            // Last writer wins
+            ("title", RightNewer),
+            ("description", RightNewer),
+            ("design", RightNewer),
+            ("acceptance_criteria", RightNewer),
+            ("assignee", RightNewer),
+            ("estimated_minutes", RightNewer),
+            ("external_ref", RightNewer),
+            ("sender", RightNewer),
+            ("replies_to", RightNewer),
+            ("duplicate_of", RightNewer),
+            ("superseded_by", RightNewer),
+            // Fixed rules: left on a status or type clash, the more urgent
+            // priority, and the compaction fields from the further
+            // compacted side
+            ("status", Left),
+            ("issue_type", Left),
+            ("priority", Right),
+            ("compaction_level", Left),
+            ("compacted_at", Left),
+            ("compacted_at_commit", Left),
+            ("original_size", Left),
+            // Both kept
+            ("notes", Concatenated),
+            ("relates_to", Merged),
+            ("labels", Merged),
+            ("dependencies", Merged),
+            ("comments", Merged),
+        ]);
+        assert_eq!(fields, expected);
+
+        assert_eq!(merged.title, "right title");
+        assert_eq!(merged.design, "right design");
+        assert_eq!(merged.acceptance_criteria, "right criteria");
+        assert_eq!(merged.assignee.as_deref(), Some("right"));
+        assert_eq!(merged.estimated_minutes, Some(30));
+        assert_eq!(merged.external_ref.as_deref(), Some("gh-right"));
+        assert_eq!(merged.duplicate_of, "right-dup");
+        assert_eq!(merged.superseded_by, "right-new");
+        assert_eq!(merged.status, "in_progress");
+        assert_eq!(merged.priority, 1);
+        assert_eq!(merged.compaction_level, 2);
+        assert_eq!(merged.compacted_at, left.compacted_at);
+        assert_eq!(merged.compacted_at_commit.as_deref(), Some("left-commit"));
+        assert_eq!(merged.original_size, 200);
+        assert_eq!(merged.notes, "left notes\n\n---\n\nright notes");
+        assert_eq!(merged.relates_to, vec!["left-related", "right-related"]);
+        assert_eq!(merged.labels, vec!["left", "right"]);
+        assert_eq!(merged.dependencies.len(), 2);
+        assert_eq!(merged.comments.len(), 2);
+        assert_eq!(merged.updated_at, right.updated_at);
+    }
+
+    #[test]
+    fn test_merge_one_sided_edits_every_field() {
+        let now = Utc::now().timestamp();
+        let base = create_issue("1", "Title", now - 300);
+        let mut edited = base.clone();
+        edited.updated_at = Utc.timestamp_opt(now - 200, 0).unwrap();
+        edit_everything(&mut edited, "edited", 1);
+        edited.status = STATUS_CLOSED.to_string();
+        edited.closed_at = Some(edited.updated_at);
+        edited.ephemeral = true;
+        edited.priority = 1;
+        edited.issue_type = "feature".to_string();
+        // The untouched side was saved later, which must not matter.
+        let mut untouched = base.clone();
+        untouched.updated_at = Utc.timestamp_opt(now - 100, 0).unwrap();
+
+        for (left, right, side) in [
+            (&edited, &untouched, FieldResolution::Left),
+            (&untouched, &edited, FieldResolution::Right),
+        ] {
+            let (merged, IssueReport { fields, .. }) =
+                merge_issue(base.clone(), left.clone(), right.clone());
+            assert_eq!(fields.len(), 25, "{:?}", fields);
+            assert!(fields.values().all(|r| *r == side), "{:?}", fields);
+            // Every field the edit touched comes through.
+            let mut expected = edited.clone();
+            expected.updated_at = untouched.updated_at;
+            assert_eq!(
+                serde_json::to_value(&merged).unwrap(),
+                serde_json::to_value(&expected).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_added_on_both_sides_keeps_fields() {
+        let now = Utc::now().timestamp();
+        let mut left = create_issue("1", "Title", now - 200);
+        left.design = "Left design".to_string();
+        let mut right = create_issue("1", "Title", now - 100);
+        right.assignee = Some("bob".to_string());
+        right.labels = vec!["ui".to_string()];
+
+        let (result, report) = merge_logic(vec![], vec![left], vec![right]);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].design, "Left design");
+        assert_eq!(result[0].assignee.as_deref(), Some("bob"));
+        assert_eq!(result[0].labels, vec!["ui"]);
+        let fields = &report.get("1").unwrap().fields;
+        assert_eq!(fields["design"], FieldResolution::Left);
+        assert_eq!(fields["assignee"], FieldResolution::Right);
+    }
+
+    #[test]
+    fn test_merge_sets_honor_removals() {
+        let now = Utc::now().timestamp();
+        let mut base = create_issue("1", "Title", now - 300);
+        base.labels = vec!["ui".to_string(), "bug".to_string()];
+        base.relates_to = vec!["2".to_string()];
+        base.dependencies = vec![dependency("1", "a"), dependency("1", "b")];
+        let mut left = base.clone();
+        left.updated_at = Utc.timestamp_opt(now - 200, 0).unwrap();
+        left.labels = vec!["ui".to_string(), "p1".to_string()];
+        left.relates_to = vec![];
+        left.dependencies = vec![dependency("1", "b")];
+        let mut right = base.clone();
+        right.updated_at = Utc.timestamp_opt(now - 100, 0).unwrap();
+        right.labels.push("backend".to_string());
+        // Right removed 1 -> a and added it back; left only removed it.
+        let mut readded = dependency("1", "a");
+        readded.created_at = right.updated_at;
+        right.dependencies = vec![readded.clone(), dependency("1", "c")];
+
+        let (merged, merge) = merge_issue(base.clone(), left.clone(), right.clone());
+        assert_eq!(merged.labels, vec!["ui", "p1", "backend"]);
+        assert!(merged.relates_to.is_empty());
+        assert_eq!(merged.dependencies, vec![readded, dependency("1", "c")]);
+        assert_eq!(merge.fields["labels"], FieldResolution::Merged);
+        assert_eq!(merge.fields["relates_to"], FieldResolution::Left);
+        assert_eq!(merge.fields["dependencies"], FieldResolution::Right);
+        assert_eq!(
+            merge.conflicts,
+            vec![EntryConflict {
+                field: "dependencies",
+                entry: "1 -> a (blocks)".to_string(),
+                kept: true,
+                side: Side::Right,
+            }]
+        );
+        assert_eq!(
+            merge.describe(["remote", "local"]),
+            "1: labels merged, relates_to from remote, \
+             dependencies 1 -> a (blocks) kept (local newer)"
+        );
+
+        // With the removal newer, it wins instead.
+        left.updated_at = Utc.timestamp_opt(now, 0).unwrap();
+        let (merged, merge) = merge_issue(base, left, right);
+        assert_eq!(merged.dependencies, vec![dependency("1", "c")]);
+        assert_eq!(merge.fields["dependencies"], FieldResolution::Merged);
+        assert!(!merge.conflicts[0].kept);
+        assert_eq!(merge.conflicts[0].side, Side::Left);
+    }
+
+    #[test]
+    fn test_merge_compaction_against_new_comment() {
+        let now = Utc::now().timestamp();
+        let comment = |id: i64, author: &str, text: &str| Comment {
+            id,
+            issue_id: "1".to_string(),
+            author: author.to_string(),
+            text: text.to_string(),
+            created_at: Utc.timestamp_opt(1000, 0).unwrap(),
+        };
+        let mut base = create_issue("1", "Title", now - 300);
+        base.description = "A long description".to_string();
+        base.comments = vec![comment(1, "alice", "Tried X"), comment(2, "bob", "Tried Y")];
+        // Left compacted the issue, dropping its comments.
+        let mut left = base.clone();
+        left.updated_at = Utc.timestamp_opt(now - 200, 0).unwrap();
+        left.description = "Summary".to_string();
+        left.compaction_level = 1;
+        left.comments = vec![];
+        // Right, whose database numbers comments its own way, added one later.
+        let mut right = base.clone();
+        right.updated_at = Utc.timestamp_opt(now - 100, 0).unwrap();
+        right.comments = vec![
+            comment(7, "alice", "Tried X"),
+            comment(8, "bob", "Tried Y"),
+            comment(9, "carol", "Fixed by Z"),
+        ];
+
+        let (merged, merge) = merge_issue(base, left, right);
+        assert_eq!(merged.description, "Summary");
+        assert_eq!(merged.comments, vec![comment(9, "carol", "Fixed by Z")]);
+        assert_eq!(merge.fields["comments"], FieldResolution::Merged);
+        assert!(merge.conflicts.is_empty());
     }
 }
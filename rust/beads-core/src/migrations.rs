@@ -0,0 +1,250 @@
+//! Versioned schema migrations for `SqliteStore`.
+//!
+//! The schema version lives in `PRAGMA user_version`. Each migration runs in
+//! its own transaction together with the version bump, so a failure leaves the
+//! database at the previous version. Migrations must stay idempotent
+//! (`IF NOT EXISTS`, and `columns` for `ALTER TABLE ... ADD COLUMN`) because
+//! databases created before versioning report 0.
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    sql: &'static str,
+    /// Columns to add as (table, column, definition), skipped when the
+    /// table already has them.
+    columns: &'static [(&'static str, &'static str, &'static str)],
+}
+
+/// Every migration, in the order they are applied. Append only.
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "base schema",
+        sql: BASE_SCHEMA,
+        columns: &[],
+    },
+    Migration {
+        version: 2,
+        description: "full-text search index",
+        sql: SEARCH_INDEX,
+        columns: &[],
+    },
+    Migration {
+        version: 3,
+        description: "lookup indexes for labels, dependencies and comments",
+        sql: LOOKUP_INDEXES,
+        columns: &[],
+    },
+    Migration {
+        version: 4,
+        description: "child ID counters",
+        sql: CHILD_COUNTERS,
+        columns: &[],
+    },
+    Migration {
+        version: 5,
+        description: "compaction fields",
+        sql: "",
+        columns: COMPACTION_COLUMNS,
+    },
+];
+
+/// Schema version this binary writes.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Migrations newer than the database, or an error if the database is newer
+/// than this binary understands.
+pub fn pending(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    let version = schema_version(conn)?;
+    if version > latest_version() {
+        anyhow::bail!(
+            "database schema version {} is newer than this bd supports ({}); upgrade bd",
+            version,
+            latest_version()
+        );
+    }
+    Ok(MIGRATIONS.iter().filter(|m| m.version > version).collect())
+}
+
+pub fn apply(conn: &mut Connection) -> Result<Vec<&'static Migration>> {
+    let pending = pending(conn)?;
+    for migration in &pending {
+        let tx = conn.transaction()?;
+        let context = || {
+            format!(
+                "migration {} ({}) failed",
+                migration.version, migration.description
+            )
+        };
+        tx.execute_batch(migration.sql).with_context(context)?;
+        for (table, column, definition) in migration.columns {
+            if !has_column(&tx, table, column)? {
+                tx.execute_batch(&format!(
+                    "ALTER TABLE {} ADD COLUMN {} {}",
+                    table, column, definition
+                ))
+                .with_context(context)?;
+            }
+        }
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+    Ok(pending)
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+const BASE_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS issues (
+        id TEXT PRIMARY KEY,
+        content_hash TEXT DEFAULT '',
+        title TEXT,
+        description TEXT,
+        design TEXT DEFAULT '',
+        acceptance_criteria TEXT DEFAULT '',
+        notes TEXT DEFAULT '',
+        status TEXT,
+        priority INTEGER,
+        issue_type TEXT,
+        assignee TEXT,
+        estimated_minutes INTEGER,
+        created_at TEXT,
+        updated_at TEXT,
+        closed_at TEXT,
+        external_ref TEXT,
+        sender TEXT DEFAULT '',
+        ephemeral BOOLEAN DEFAULT 0,
+        replies_to TEXT DEFAULT '',
+        relates_to TEXT DEFAULT '',
+        duplicate_of TEXT DEFAULT '',
+        superseded_by TEXT DEFAULT '',
+        deleted_at TEXT,
+        deleted_by TEXT DEFAULT '',
+        delete_reason TEXT DEFAULT '',
+        original_type TEXT DEFAULT ''
+    );
+
+    CREATE TABLE IF NOT EXISTS labels (
+        issue_id TEXT,
+        label TEXT,
+        PRIMARY KEY (issue_id, label)
+    );
+
+    CREATE TABLE IF NOT EXISTS dependencies (
+        issue_id TEXT,
+        depends_on_id TEXT,
+        type TEXT,
+        created_at TEXT,
+        created_by TEXT,
+        PRIMARY KEY (issue_id, depends_on_id, type)
+    );
+
+    CREATE TABLE IF NOT EXISTS comments (
+        id INTEGER PRIMARY KEY,
+        issue_id TEXT,
+        author TEXT,
+        text TEXT,
+        created_at TEXT
+    );
+
+    CREATE TABLE IF NOT EXISTS dirty_issues (
+        issue_id TEXT PRIMARY KEY
+    );
+
+    CREATE TABLE IF NOT EXISTS metadata (
+        key TEXT PRIMARY KEY,
+        value TEXT
+    );
+
+    CREATE TABLE IF NOT EXISTS config (
+        key TEXT PRIMARY KEY,
+        value TEXT
+    );
+";
+
+/// Full-text index over the text fields and comments of each issue, kept in
+/// sync by triggers so every write path updates it.
+const SEARCH_INDEX: &str = "
+    CREATE VIRTUAL TABLE IF NOT EXISTS issues_fts USING fts5(
+        id, title, description, design, acceptance_criteria, notes, comments
+    );
+
+    CREATE TRIGGER IF NOT EXISTS issues_fts_insert AFTER INSERT ON issues BEGIN
+        DELETE FROM issues_fts WHERE id = new.id;
+        INSERT INTO issues_fts SELECT id, title, description, design, acceptance_criteria, notes,
+            (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = issues.id)
+        FROM issues WHERE id = new.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS issues_fts_update AFTER UPDATE ON issues BEGIN
+        DELETE FROM issues_fts WHERE id = old.id;
+        INSERT INTO issues_fts SELECT id, title, description, design, acceptance_criteria, notes,
+            (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = issues.id)
+        FROM issues WHERE id = new.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS issues_fts_delete AFTER DELETE ON issues BEGIN
+        DELETE FROM issues_fts WHERE id = old.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS comments_fts_insert AFTER INSERT ON comments BEGIN
+        DELETE FROM issues_fts WHERE id = new.issue_id;
+        INSERT INTO issues_fts SELECT id, title, description, design, acceptance_criteria, notes,
+            (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = issues.id)
+        FROM issues WHERE id = new.issue_id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS comments_fts_delete AFTER DELETE ON comments BEGIN
+        DELETE FROM issues_fts WHERE id = old.issue_id;
+        INSERT INTO issues_fts SELECT id, title, description, design, acceptance_criteria, notes,
+            (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = issues.id)
+        FROM issues WHERE id = old.issue_id;
+    END;
+
+    DELETE FROM issues_fts;
+    INSERT INTO issues_fts SELECT id, title, description, design, acceptance_criteria, notes,
+        (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = issues.id)
+    FROM issues;
+";
+
+const LOOKUP_INDEXES: &str = "
+    CREATE INDEX IF NOT EXISTS idx_issues_status ON issues(status);
+    CREATE INDEX IF NOT EXISTS idx_labels_label ON labels(label);
+    CREATE INDEX IF NOT EXISTS idx_dependencies_depends_on ON dependencies(depends_on_id);
+    CREATE INDEX IF NOT EXISTS idx_comments_issue ON comments(issue_id);
+";
+
+/// Last child number handed out under each parent, so `next_child_id` never
+/// reuses one (same table as the Go bd).
+const CHILD_COUNTERS: &str = "
+    CREATE TABLE IF NOT EXISTS child_counters (
+        parent_id TEXT PRIMARY KEY,
+        last_child INTEGER NOT NULL DEFAULT 0
+    );
+";
+
+/// Compaction state of closed issues (`bd compact`), as in the Go bd.
+const COMPACTION_COLUMNS: &[(&str, &str, &str)] = &[
+    ("issues", "compaction_level", "INTEGER DEFAULT 0"),
+    ("issues", "compacted_at", "TEXT"),
+    ("issues", "compacted_at_commit", "TEXT"),
+    ("issues", "original_size", "INTEGER DEFAULT 0"),
+];
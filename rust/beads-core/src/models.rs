@@ -1,11 +1,23 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 // Status is just a string in Go
 pub type Status = String;
 pub type IssueType = String;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Status of a soft-deleted issue. Tombstones stay in the JSONL so deletions
+/// propagate through git instead of being resurrected by other clones.
+pub const STATUS_TOMBSTONE: &str = "tombstone";
+pub const STATUS_CLOSED: &str = "closed";
+
+/// Issue type whose `parent-child` children make up its work.
+pub const TYPE_EPIC: &str = "epic";
+/// Issue type of agent-to-agent mail (`bd mail`). Messages stay out of
+/// `bd list` and `bd ready`.
+pub const TYPE_MESSAGE: &str = "message";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Dependency {
     pub issue_id: String,
     pub depends_on_id: String,
@@ -15,7 +27,7 @@ pub struct Dependency {
     pub created_by: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Comment {
     pub id: i64,
     pub issue_id: String,
@@ -24,10 +36,12 @@ pub struct Comment {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
 pub struct Issue {
     pub id: String,
 
+    /// `compute_content_hash()` as of the last write; filled in by the store
+    /// and never exported.
     #[serde(skip)]
     pub content_hash: String,
 
@@ -69,6 +83,19 @@ pub struct Issue {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub external_ref: Option<String>,
 
+    // Compaction fields (see `compact`)
+    /// 0 for an uncompacted issue, otherwise the highest tier applied.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub compaction_level: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compacted_at: Option<DateTime<Utc>>,
+    /// Git commit whose JSONL still holds the full issue, for `bd restore`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compacted_at_commit: Option<String>,
+    /// Bytes of text the issue had before it was first compacted.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub original_size: i32,
+
     // Messaging fields
     #[serde(default)]
     pub sender: String,
@@ -105,3 +132,194 @@ pub struct Issue {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub comments: Vec<Comment>,
 }
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
+}
+
+impl Issue {
+    /// Whether every field but the labels, dependencies and comments (which
+    /// live apart from the issue row) matches `other`. Unlike the content
+    /// hash this covers timestamps, links and the compaction and tombstone
+    /// fields, so imports use it to tell an unchanged issue.
+    pub fn same_fields(&self, other: &Issue) -> bool {
+        let bare = |issue: &Issue| Issue {
+            content_hash: String::new(),
+            labels: Vec::new(),
+            dependencies: Vec::new(),
+            comments: Vec::new(),
+            ..issue.clone()
+        };
+        bare(self) == bare(other)
+    }
+
+    /// SHA-256 of the issue's substantive content, hex encoded. IDs,
+    /// timestamps, labels, dependencies and comments are excluded so the same
+    /// content hashes identically in every clone. Matches the Go bd's
+    /// `ComputeContentHash`.
+    pub fn compute_content_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        for field in [
+            self.title.as_str(),
+            &self.description,
+            &self.design,
+            &self.acceptance_criteria,
+            &self.notes,
+            &self.status,
+            &self.priority.to_string(),
+            &self.issue_type,
+            self.assignee.as_deref().unwrap_or_default(),
+        ] {
+            hasher.update(field.as_bytes());
+            hasher.update([0]);
+        }
+        if let Some(external_ref) = &self.external_ref {
+            hasher.update(external_ref.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Outcome of importing a JSONL file.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportStats {
+    /// Issues that did not exist before.
+    pub created: usize,
+    /// Existing issues whose content hash changed.
+    pub updated: usize,
+    /// Existing issues whose content hash matched and were left alone.
+    pub unchanged: usize,
+}
+
+/// Minimal view of another issue, used when listing blockers and links.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct IssueSummary {
+    pub id: String,
+    pub title: String,
+    pub status: Status,
+}
+
+impl From<&Issue> for IssueSummary {
+    fn from(issue: &Issue) -> Self {
+        IssueSummary {
+            id: issue.id.clone(),
+            title: issue.title.clone(),
+            status: issue.status.clone(),
+        }
+    }
+}
+
+/// The graph links of an issue in both directions, for `bd show`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IssueLinks {
+    /// Issues it relates to, or that relate to it.
+    pub related: Vec<IssueSummary>,
+    pub duplicate_of: Option<IssueSummary>,
+    pub superseded_by: Option<IssueSummary>,
+    /// Issues marked as duplicates of it.
+    pub duplicates: Vec<IssueSummary>,
+    /// Issues it supersedes.
+    pub supersedes: Vec<IssueSummary>,
+}
+
+/// An issue that cannot be worked on yet, with the issues blocking it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BlockedIssue {
+    #[serde(flatten)]
+    pub issue: Issue,
+    /// Active issues it has a `blocks` dependency on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blockers: Vec<IssueSummary>,
+    /// Its parent, when it is blocked only because the parent is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_via_parent: Option<IssueSummary>,
+}
+
+/// Progress of an open epic towards having all of its children closed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EpicStatus {
+    pub epic: Issue,
+    pub total_children: usize,
+    pub closed_children: usize,
+    /// Every child is closed, so the epic can be too.
+    pub eligible_for_close: bool,
+}
+
+impl EpicStatus {
+    /// Share of children closed, rounded down; 0 for an epic without children.
+    pub fn percent_complete(&self) -> usize {
+        (self.closed_children * 100)
+            .checked_div(self.total_children)
+            .unwrap_or(0)
+    }
+}
+
+/// An issue matched by `Store::search`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub issue: Issue,
+    /// Relevance score; lower is better, as with SQLite's `bm25()`.
+    pub rank: f64,
+    /// Excerpt around the best match with matched terms in `[brackets]`.
+    pub snippet: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Issue {
+        Issue {
+            id: "test-1".to_string(),
+            title: "Test Issue".to_string(),
+            description: "Description".to_string(),
+            status: "open".to_string(),
+            priority: 2,
+            issue_type: "feature".to_string(),
+            estimated_minutes: Some(60),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compute_content_hash_matches_go() {
+        // Reference values from the Go bd's Issue.ComputeContentHash
+        let issue = sample();
+        assert_eq!(
+            issue.compute_content_hash(),
+            "71c1d21dec890ca5303f491676bd73023377ac0f5cdf8df3c5bfd58f7059bba0"
+        );
+        let with_ref = Issue {
+            external_ref: Some("EXT-123".to_string()),
+            ..sample()
+        };
+        assert_eq!(
+            with_ref.compute_content_hash(),
+            "b11b8d63299a1d699f407e890411d97b8de350cbc5a1c5be130c16266d3ceb24"
+        );
+    }
+
+    #[test]
+    fn test_compute_content_hash_ignores_identity_and_timestamps() {
+        let other = Issue {
+            id: "test-2".to_string(),
+            created_at: Utc::now(),
+            labels: vec!["x".to_string()],
+            ..sample()
+        };
+        assert_eq!(
+            sample().compute_content_hash(),
+            other.compute_content_hash()
+        );
+
+        let retitled = Issue {
+            title: "Different Title".to_string(),
+            ..sample()
+        };
+        assert_ne!(
+            sample().compute_content_hash(),
+            retitled.compute_content_hash()
+        );
+    }
+}
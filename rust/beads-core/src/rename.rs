@@ -0,0 +1,205 @@
+//! Moving every issue from one ID prefix to another (`bd rename-prefix`).
+
+use crate::models::Issue;
+use anyhow::Result;
+use std::collections::HashSet;
+
+/// Longest prefix `validate_prefix` accepts, as in the Go bd.
+pub const MAX_PREFIX_LENGTH: usize = 8;
+
+/// Checks a new ID prefix and returns it without the trailing hyphen people
+/// often type (`kw-` becomes `kw`). Prefixes are at most 8 characters of
+/// lowercase letters, digits and single hyphens, starting with a letter.
+pub fn validate_prefix(prefix: &str) -> Result<String> {
+    let prefix = prefix.trim_end_matches('-');
+    if prefix.is_empty() {
+        anyhow::bail!("prefix cannot be empty");
+    }
+    if prefix.len() > MAX_PREFIX_LENGTH {
+        anyhow::bail!(
+            "prefix too long (max {} characters): {}",
+            MAX_PREFIX_LENGTH,
+            prefix
+        );
+    }
+    let mut chars = prefix.chars();
+    if !chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        || !chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        anyhow::bail!(
+            "prefix must start with a lowercase letter and contain only lowercase letters, \
+             numbers, and hyphens: {}",
+            prefix
+        );
+    }
+    if prefix.contains("--") {
+        anyhow::bail!("prefix has invalid hyphen placement: {}", prefix);
+    }
+    Ok(prefix.to_string())
+}
+
+/// `id` moved to `new_prefix`, or `None` if it does not use `old_prefix`.
+pub fn renamed_id(id: &str, old_prefix: &str, new_prefix: &str) -> Option<String> {
+    let rest = id.strip_prefix(old_prefix)?.strip_prefix('-')?;
+    Some(format!("{}-{}", new_prefix, rest))
+}
+
+/// Rewrites every reference `issue` makes to the issues in `renamed` (IDs
+/// still on `old_prefix`): its own ID, dependencies, comments, link fields,
+/// labels, and mentions in its text.
+pub fn rename_references(
+    issue: &Issue,
+    old_prefix: &str,
+    new_prefix: &str,
+    renamed: &HashSet<String>,
+) -> Issue {
+    let id = |id: &str| {
+        renamed
+            .contains(id)
+            .then(|| renamed_id(id, old_prefix, new_prefix))
+            .flatten()
+            .unwrap_or_else(|| id.to_string())
+    };
+    let text = |text: &str| rewrite_mentions(text, old_prefix, new_prefix, renamed);
+
+    let mut issue = issue.clone();
+    issue.id = id(&issue.id);
+    issue.title = text(&issue.title);
+    issue.description = text(&issue.description);
+    issue.design = text(&issue.design);
+    issue.acceptance_criteria = text(&issue.acceptance_criteria);
+    issue.notes = text(&issue.notes);
+    issue.replies_to = id(&issue.replies_to);
+    issue.relates_to = issue.relates_to.iter().map(|r| id(r)).collect();
+    issue.duplicate_of = id(&issue.duplicate_of);
+    issue.superseded_by = id(&issue.superseded_by);
+    issue.labels = issue.labels.iter().map(|l| text(l)).collect();
+    for dep in &mut issue.dependencies {
+        dep.issue_id = id(&dep.issue_id);
+        dep.depends_on_id = id(&dep.depends_on_id);
+    }
+    for comment in &mut issue.comments {
+        comment.issue_id = id(&comment.issue_id);
+        comment.text = text(&comment.text);
+    }
+    issue
+}
+
+/// What renaming a prefix does to a set of issues.
+pub(crate) struct Plan {
+    /// Moved IDs as (old, new) pairs, sorted by old ID.
+    pub moves: Vec<(String, String)>,
+    /// Every issue that changes, paired with its current ID.
+    pub changed: Vec<(String, Issue)>,
+}
+
+/// Works out renaming `old_prefix` to `new_prefix` across `issues`. Fails if
+/// a new ID is already taken.
+pub(crate) fn plan(issues: &[Issue], old_prefix: &str, new_prefix: &str) -> Result<Plan> {
+    let mut moves: Vec<(String, String)> = issues
+        .iter()
+        .filter_map(|i| Some((i.id.clone(), renamed_id(&i.id, old_prefix, new_prefix)?)))
+        .collect();
+    moves.sort();
+    let existing: HashSet<&str> = issues.iter().map(|i| i.id.as_str()).collect();
+    if let Some((id, new_id)) = moves
+        .iter()
+        .find(|(_, new_id)| existing.contains(new_id.as_str()))
+    {
+        anyhow::bail!(
+            "cannot rename {} to {}: that ID is already taken",
+            id,
+            new_id
+        );
+    }
+
+    let renamed: HashSet<String> = moves.iter().map(|(id, _)| id.clone()).collect();
+    let changed = issues
+        .iter()
+        .filter_map(|issue| {
+            let updated = rename_references(issue, old_prefix, new_prefix, &renamed);
+            let same = serde_json::to_value(&updated).ok() == serde_json::to_value(issue).ok();
+            (!same).then(|| (issue.id.clone(), updated))
+        })
+        .collect();
+    Ok(Plan { moves, changed })
+}
+
+/// Replaces mentions of the `renamed` IDs in free text. A mention is a whole
+/// word, so `bd-a3f` is rewritten in "see bd-a3f." but not in "xbd-a3f" or
+/// "bd-a3f9"; child IDs such as `bd-a3f.2` are matched in full.
+fn rewrite_mentions(
+    text: &str,
+    old_prefix: &str,
+    new_prefix: &str,
+    renamed: &HashSet<String>,
+) -> String {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    let needle = format!("{}-", old_prefix);
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = rest.find(&needle) {
+        let (before, candidate) = rest.split_at(pos);
+        out.push_str(before);
+        let at_word_start = !out.chars().next_back().is_some_and(is_word);
+
+        // The longest run of ID characters, less trailing dots and any
+        // child segments that do not name a renamed issue
+        let run_len = candidate[needle.len()..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '.')
+            .map_or(candidate.len(), |n| n + needle.len());
+        let mut id = candidate[..run_len].trim_end_matches('.');
+        while !renamed.contains(id) {
+            match id.rfind('.') {
+                Some(dot) => id = &id[..dot],
+                None => break,
+            }
+        }
+
+        if at_word_start && renamed.contains(id) {
+            out.push_str(new_prefix);
+            out.push('-');
+            out.push_str(&id[needle.len()..]);
+            rest = &candidate[id.len()..];
+        } else {
+            out.push_str(&needle);
+            rest = &candidate[needle.len()..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_prefix() {
+        assert_eq!(validate_prefix("kw-").unwrap(), "kw");
+        assert_eq!(validate_prefix("work2").unwrap(), "work2");
+        assert_eq!(validate_prefix("my-proj").unwrap(), "my-proj");
+        for bad in ["", "-", "Bd", "9bd", "bd_x", "toolongpx", "a--b"] {
+            assert!(validate_prefix(bad).is_err(), "{:?} accepted", bad);
+        }
+    }
+
+    #[test]
+    fn test_rewrite_mentions() {
+        let renamed: HashSet<String> = ["bd-a3f", "bd-a3f.2", "bd-x9"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let rewrite = |text: &str| rewrite_mentions(text, "bd", "kw", &renamed);
+
+        assert_eq!(
+            rewrite("See bd-a3f, bd-a3f.2 and (bd-x9)."),
+            "See kw-a3f, kw-a3f.2 and (kw-x9)."
+        );
+        // Unknown IDs, longer words and unknown child IDs
+        assert_eq!(rewrite("bd-zzz xbd-a3f bd-a3f9"), "bd-zzz xbd-a3f bd-a3f9");
+        assert_eq!(rewrite("bd-a3f.7 done"), "kw-a3f.7 done");
+        assert_eq!(rewrite("bd-bd-a3f"), "bd-bd-a3f");
+    }
+}
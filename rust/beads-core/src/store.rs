@@ -1,27 +1,49 @@
+use crate::filter::{IssueFilter, SortField};
 use crate::fs::FileSystem;
-use crate::models::Issue;
-#[cfg(not(target_arch = "wasm32"))]
-use crate::models::{Comment, Dependency};
+use crate::graph::{self, Blockers, DependencyGraph};
+use crate::merge;
+use crate::models::{
+    BlockedIssue, Comment, Dependency, EpicStatus, ImportStats, Issue, IssueLinks, IssueSummary,
+    SearchResult, STATUS_CLOSED, STATUS_TOMBSTONE, TYPE_EPIC,
+};
+use crate::rename;
+use crate::util;
 use anyhow::Result;
-use std::path::Path;
+use chrono::Utc;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 pub trait Store {
     fn get_config(&self, key: &str) -> Result<Option<String>>;
     fn set_config(&self, key: &str, value: &str) -> Result<()>;
     fn update_issue(&self, issue: &Issue) -> Result<()>;
+    /// Like `update_issue`, but fails without writing if the stored issue's
+    /// content hash is no longer `expected_hash`, i.e. it changed since the
+    /// caller read it.
+    fn update_issue_if_unchanged(&self, issue: &Issue, expected_hash: &str) -> Result<()>;
     fn list_config(&self) -> Result<Vec<(String, String)>>;
     fn execute_raw(&self, sql: &str) -> Result<()>;
     fn get_issue(&self, id: &str) -> Result<Option<Issue>>;
-    fn list_issues(
-        &self,
-        status: Option<&str>,
-        assignee: Option<&str>,
-        priority: Option<i32>,
-        issue_type: Option<&str>,
-        label: Option<&str>,
-        sort_by: Option<&str>,
-    ) -> Result<Vec<Issue>>;
-    fn import_from_jsonl(&mut self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<()>;
+    /// Issues matching `filter` with their labels, ordered and paged as it asks.
+    fn list_issues(&self, filter: &IssueFilter) -> Result<Vec<Issue>>;
+    /// Upserts every issue. Issues whose content hash matches the stored one
+    /// keep their row and only have labels, dependencies and comments brought
+    /// in line.
+    fn import_issues(&mut self, issues: Vec<Issue>) -> Result<ImportStats>;
+    /// Imports every issue in a JSONL file with `import_issues`. A missing
+    /// file imports nothing.
+    fn import_from_jsonl(&mut self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<ImportStats> {
+        if !fs.exists(jsonl_path) {
+            return Ok(ImportStats::default());
+        }
+        self.import_issues(parse_jsonl(&fs.read_to_string(jsonl_path)?)?)
+    }
+    fn get_metadata(&self, key: &str) -> Result<Option<String>>;
+    fn set_metadata(&self, key: &str, value: &str) -> Result<()>;
+    /// IDs of issues changed since the last export, sorted.
+    fn dirty_issue_ids(&self) -> Result<Vec<String>>;
     fn generate_unique_id(
         &self,
         prefix: &str,
@@ -29,8 +51,362 @@ pub trait Store {
         description: &str,
         creator: &str,
     ) -> Result<String>;
+    /// Allocates the next hierarchical ID under `parent_id`, e.g. `bd-a3f8.1`.
+    /// Numbers are never handed out twice, and skip past children that
+    /// already exist. Fails if the parent is missing, deleted, or already
+    /// `MAX_HIERARCHY_DEPTH` levels deep.
+    fn next_child_id(&self, parent_id: &str) -> Result<String>;
     fn create_issue(&self, issue: &Issue) -> Result<()>;
+    /// Creates all of `issues` in one transaction, so a linked set (an epic
+    /// and its children) is never left half-created.
+    fn create_issues(&self, issues: &[Issue]) -> Result<()>;
+    /// Updates all of `issues` in one transaction, like `update_issue`.
+    fn update_issues(&self, issues: &[Issue]) -> Result<()>;
     fn export_to_jsonl(&self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<()>;
+    /// Brings the JSONL up to date by rewriting only the lines of dirty
+    /// issues; every other line is kept byte-for-byte. Falls back to a full
+    /// export when the file is missing or has changed since it was last
+    /// written. The result is always identical to `export_to_jsonl`.
+    fn export_dirty_to_jsonl(&self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<()>;
+    /// Open and in-progress issues with no open blockers, sorted by priority.
+    /// Messages are never ready work.
+    fn ready_issues(&self) -> Result<Vec<Issue>>;
+    /// Active issues blocked directly or through a blocked parent, sorted by priority,
+    /// with a summary of each blocker. Messages are never listed.
+    fn blocked_issues(&self) -> Result<Vec<BlockedIssue>>;
+    /// Adds a single dependency. Fails if either issue is missing, the type is
+    /// unknown, or the edge would create a cycle (see `graph::CycleError`).
+    fn add_dependency(&self, dep: &Dependency) -> Result<()>;
+    /// Removes every dependency of `issue_id` on `depends_on_id`.
+    fn remove_dependency(&self, issue_id: &str, depends_on_id: &str) -> Result<()>;
+    /// Builds the graph of all dependencies in the store.
+    fn dependency_graph(&self) -> Result<DependencyGraph>;
+    /// Turns an issue into a tombstone and drops every dependency link to or
+    /// from it. Issues that lose a link are marked dirty alongside it.
+    fn delete_issue(&self, id: &str, actor: &str, reason: &str) -> Result<()>;
+    /// Sets a closed issue back to open and clears `closed_at`. A non-empty
+    /// `reason` is recorded as a comment by `actor`.
+    fn reopen_issue(&self, id: &str, actor: &str, reason: &str) -> Result<()>;
+    /// Restores a tombstone that has not expired yet, bringing back its
+    /// original type. The issue comes back open, without its old dependencies.
+    fn undelete_issue(&self, id: &str) -> Result<()>;
+    /// Appends a comment to an issue and returns it with its assigned ID.
+    fn add_comment(&self, issue_id: &str, author: &str, text: &str) -> Result<Comment>;
+    /// Comments on an issue, oldest first.
+    fn list_comments(&self, issue_id: &str) -> Result<Vec<Comment>>;
+    fn delete_comment(&self, comment_id: i64) -> Result<()>;
+    /// Issues whose ID, text fields or comments contain every word of `query`,
+    /// best match first, restricted by `filter`.
+    fn search(&self, query: &str, filter: &IssueFilter) -> Result<Vec<SearchResult>>;
+    /// Moves every `old_prefix-…` issue to `new_prefix` in one transaction,
+    /// rewriting all references to them (see `rename::rename_references`),
+    /// and makes `new_prefix` the configured `issue_id_prefix`. Returns the
+    /// (old, new) ID pairs, sorted; `dry_run` only computes them. Issues that
+    /// changed are marked dirty, but the old IDs stay in the JSONL until a
+    /// full export.
+    fn rename_prefix(
+        &self,
+        old_prefix: &str,
+        new_prefix: &str,
+        dry_run: bool,
+    ) -> Result<Vec<(String, String)>>;
+    /// Stores `issue` in its compacted form (see `compact::compact_issue`)
+    /// and drops its comments, in one transaction.
+    fn apply_compaction(&self, issue: &Issue) -> Result<()>;
+    /// Every epic that is not closed, with how many of its `parent-child`
+    /// children are, ordered like `bd ready`. Deleted children do not count.
+    fn epic_status(&self) -> Result<Vec<EpicStatus>> {
+        let issues = self.list_issues(&IssueFilter::default())?;
+        let statuses: HashMap<&str, &str> = issues
+            .iter()
+            .map(|i| (i.id.as_str(), i.status.as_str()))
+            .collect();
+        let graph = self.dependency_graph()?;
+
+        let mut epics: Vec<Issue> = issues
+            .iter()
+            .filter(|i| i.issue_type == TYPE_EPIC && i.status != STATUS_CLOSED)
+            .cloned()
+            .collect();
+        graph::sort_by_priority(&mut epics);
+        Ok(epics
+            .into_iter()
+            .map(|epic| {
+                let children: Vec<&str> = graph
+                    .children_of(&epic.id)
+                    .filter_map(|id| statuses.get(id).copied())
+                    .collect();
+                let total_children = children.len();
+                let closed_children = children.iter().filter(|&&s| s == STATUS_CLOSED).count();
+                EpicStatus {
+                    epic,
+                    total_children,
+                    closed_children,
+                    eligible_for_close: total_children > 0 && closed_children == total_children,
+                }
+            })
+            .collect())
+    }
+    /// Links `a` and `b` to each other through `relates_to`. Issues that
+    /// are already related are left alone.
+    fn relate_issues(&self, a: &str, b: &str) -> Result<()> {
+        if a == b {
+            anyhow::bail!("cannot relate an issue to itself");
+        }
+        let now = Utc::now();
+        let mut changed = Vec::new();
+        for (id, other) in [(a, b), (b, a)] {
+            let mut issue = self
+                .get_issue(id)?
+                .ok_or_else(|| anyhow::anyhow!("issue {} not found", id))?;
+            if !issue.relates_to.iter().any(|r| r == other) {
+                issue.relates_to.push(other.to_string());
+                issue.updated_at = now;
+                changed.push(issue);
+            }
+        }
+        self.update_issues(&changed)
+    }
+    /// Closes `id` as a duplicate of `canonical`, recorded in `duplicate_of`.
+    fn mark_duplicate(&self, id: &str, canonical: &str) -> Result<()> {
+        if id == canonical {
+            anyhow::bail!("cannot mark an issue as a duplicate of itself");
+        }
+        close_with_link(self, id, canonical, "canonical", |issue, target| {
+            issue.duplicate_of = target
+        })
+    }
+    /// Closes `id` as replaced by `replacement`, recorded in `superseded_by`.
+    fn mark_superseded(&self, id: &str, replacement: &str) -> Result<()> {
+        if id == replacement {
+            anyhow::bail!("cannot mark an issue as superseded by itself");
+        }
+        close_with_link(self, id, replacement, "replacement", |issue, target| {
+            issue.superseded_by = target
+        })
+    }
+    /// The `relates_to`, `duplicate_of` and `superseded_by` links of `id`
+    /// together with those other issues make to it, sorted by ID. Links to
+    /// deleted or missing issues are left out.
+    fn issue_links(&self, id: &str) -> Result<IssueLinks> {
+        let issue = self
+            .get_issue(id)?
+            .ok_or_else(|| anyhow::anyhow!("issue {} not found", id))?;
+        let issues = self.list_issues(&IssueFilter {
+            sort: SortField::Id,
+            ..Default::default()
+        })?;
+        let summary = |target: &str| {
+            issues
+                .iter()
+                .find(|i| i.id == target)
+                .map(IssueSummary::from)
+        };
+
+        let related = issues
+            .iter()
+            .filter(|i| {
+                i.id != id
+                    && (issue.relates_to.contains(&i.id) || i.relates_to.iter().any(|r| r == id))
+            })
+            .map(IssueSummary::from)
+            .collect();
+        Ok(IssueLinks {
+            related,
+            duplicate_of: summary(&issue.duplicate_of),
+            superseded_by: summary(&issue.superseded_by),
+            duplicates: issues
+                .iter()
+                .filter(|i| i.duplicate_of == id)
+                .map(IssueSummary::from)
+                .collect(),
+            supersedes: issues
+                .iter()
+                .filter(|i| i.superseded_by == id)
+                .map(IssueSummary::from)
+                .collect(),
+        })
+    }
+}
+
+/// Closes `id`, if it is not already, and points it at `target` with `link`.
+/// `role` names the target in the error for a missing one.
+fn close_with_link<S: Store + ?Sized>(
+    store: &S,
+    id: &str,
+    target: &str,
+    role: &str,
+    link: impl FnOnce(&mut Issue, String),
+) -> Result<()> {
+    let mut issue = store
+        .get_issue(id)?
+        .ok_or_else(|| anyhow::anyhow!("issue {} not found", id))?;
+    if store.get_issue(target)?.is_none() {
+        anyhow::bail!("{} issue not found: {}", role, target);
+    }
+    let now = Utc::now();
+    link(&mut issue, target.to_string());
+    if issue.status != STATUS_CLOSED {
+        issue.status = STATUS_CLOSED.to_string();
+        issue.closed_at = Some(now);
+    }
+    issue.updated_at = now;
+    store.update_issue(&issue)
+}
+
+/// Deepest nesting of hierarchical child IDs (`bd-a3f8.1.2.3`), as in the Go bd.
+pub const MAX_HIERARCHY_DEPTH: usize = 3;
+
+/// Fails unless an issue with status `parent_status` (`None` if missing) can
+/// get another level of children under `parent_id`.
+pub(crate) fn check_child_parent(parent_id: &str, parent_status: Option<&str>) -> Result<()> {
+    match parent_status {
+        None => anyhow::bail!("parent issue {} not found", parent_id),
+        Some(STATUS_TOMBSTONE) => anyhow::bail!("parent issue {} is deleted", parent_id),
+        Some(_) => {}
+    }
+    if parent_id.matches('.').count() >= MAX_HIERARCHY_DEPTH {
+        anyhow::bail!(
+            "maximum hierarchy depth ({}) exceeded for parent {}",
+            MAX_HIERARCHY_DEPTH,
+            parent_id
+        );
+    }
+    Ok(())
+}
+
+/// The `n` of a direct child ID `parent_id.n`.
+pub(crate) fn child_number(parent_id: &str, id: &str) -> Option<u32> {
+    id.strip_prefix(parent_id)?.strip_prefix('.')?.parse().ok()
+}
+
+/// Fails unless `stored` (the current content hash of `id`) is `expected`.
+pub(crate) fn check_content_hash(id: &str, stored: Option<&str>, expected: &str) -> Result<()> {
+    match stored {
+        None => anyhow::bail!("issue {} not found", id),
+        Some(stored) if stored != expected => anyhow::bail!(
+            "issue {} was modified since it was read (content hash {} != {})",
+            id,
+            stored,
+            expected
+        ),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Length new hash IDs start at, given how many top-level issues already use
+/// the prefix and the adaptive ID settings in the store's config.
+pub(crate) fn adaptive_id_length<S: Store + ?Sized>(
+    store: &S,
+    top_level_issues: usize,
+) -> Result<usize> {
+    let config = util::AdaptiveIdConfig::from_config(|key| store.get_config(key))?;
+    Ok(util::adaptive_id_length(top_level_issues, &config))
+}
+
+/// Whether `id` is a `prefix-hash` ID rather than a hierarchical child ID.
+pub(crate) fn is_top_level_id(prefix: &str, id: &str) -> bool {
+    id.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .is_some_and(|hash| !hash.contains('.'))
+}
+
+/// Type an undeleted issue gets back, or an error if `issue` is not a tombstone
+/// or has outlived the tombstone TTL.
+pub(crate) fn restorable_type(issue: &Issue) -> Result<String> {
+    if issue.status != STATUS_TOMBSTONE {
+        anyhow::bail!("issue {} is not deleted", issue.id);
+    }
+    if merge::is_expired_tombstone(issue, merge::DEFAULT_TOMBSTONE_TTL_DAYS) {
+        anyhow::bail!(
+            "tombstone for {} has expired and cannot be restored",
+            issue.id
+        );
+    }
+    Ok(if issue.original_type.is_empty() {
+        issue.issue_type.clone()
+    } else {
+        issue.original_type.clone()
+    })
+}
+
+/// Issues in a JSONL export, skipping blank lines.
+pub(crate) fn parse_jsonl(contents: &str) -> Result<Vec<Issue>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| anyhow::anyhow!("invalid issue on line {}: {}", n + 1, e))
+        })
+        .collect()
+}
+
+/// Metadata key holding `jsonl_hash` of the file as last exported or imported.
+pub(crate) const JSONL_HASH_KEY: &str = "jsonl_content_hash";
+
+/// SHA-256 of a JSONL file's contents, hex encoded.
+pub(crate) fn jsonl_hash(contents: &[u8]) -> String {
+    hex::encode(Sha256::digest(contents))
+}
+
+/// Hidden sibling that exports are written to before being renamed into place.
+pub(crate) fn jsonl_temp_path(jsonl_path: &Path) -> PathBuf {
+    let dir = jsonl_path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = jsonl_path.file_name().unwrap_or_default();
+    dir.join(format!(".{}.tmp", file_name.to_string_lossy()))
+}
+
+/// Replaces the lines of the `changed` issues in an export sorted by ID,
+/// dropping those mapped to `None` and slotting new ones into place. Other
+/// lines are copied unchanged. Returns `None` if `existing` is not sorted by
+/// ID, since patching it could not reproduce a full export.
+pub(crate) fn patch_jsonl(
+    existing: &str,
+    changed: &BTreeMap<String, Option<Issue>>,
+) -> Result<Option<String>> {
+    #[derive(Deserialize)]
+    struct Line {
+        id: String,
+    }
+
+    fn push(issue: &Option<Issue>, out: &mut String) -> Result<()> {
+        if let Some(issue) = issue {
+            out.push_str(&serde_json::to_string(issue)?);
+            out.push('\n');
+        }
+        Ok(())
+    }
+
+    let mut out = String::with_capacity(existing.len());
+
+    let mut pending = changed.iter().peekable();
+    let mut previous: Option<String> = None;
+    for line in existing.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let id = serde_json::from_str::<Line>(line)?.id;
+        if previous.as_ref().is_some_and(|p| *p >= id) {
+            return Ok(None);
+        }
+        while let Some((_, issue)) = pending.next_if(|(c, _)| c.as_str() < id.as_str()) {
+            push(issue, &mut out)?;
+        }
+        match pending.next_if(|(c, _)| **c == id) {
+            Some((_, issue)) => push(issue, &mut out)?,
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        previous = Some(id);
+    }
+    for (_, issue) in pending {
+        push(issue, &mut out)?;
+    }
+    Ok(Some(out))
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -39,163 +415,79 @@ pub use sqlite_impl::SqliteStore;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod sqlite_impl {
     use super::*;
-    use crate::util;
-    use chrono::{DateTime, NaiveDateTime, Utc};
-    use rusqlite::{params, Connection};
+    use crate::migrations::{self, Migration};
+    use crate::models::TYPE_MESSAGE;
+    use chrono::{DateTime, NaiveDateTime};
+    use rusqlite::{params, Connection, OptionalExtension};
     use sha2::{Digest, Sha256};
-    use std::collections::HashMap;
-    use std::io::{BufRead, BufWriter, Write};
+    use std::io::{BufWriter, Write};
 
     pub struct SqliteStore {
         conn: Connection,
     }
 
     impl SqliteStore {
+        /// Opens the database and applies any pending schema migrations.
         pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-            let conn = Connection::open(&path)?;
-
-            conn.execute_batch(
-                "
-                CREATE TABLE IF NOT EXISTS issues (
-                    id TEXT PRIMARY KEY,
-                    content_hash TEXT DEFAULT '',
-                    title TEXT,
-                    description TEXT,
-                    design TEXT DEFAULT '',
-                    acceptance_criteria TEXT DEFAULT '',
-                    notes TEXT DEFAULT '',
-                    status TEXT,
-                    priority INTEGER,
-                    issue_type TEXT,
-                    assignee TEXT,
-                    estimated_minutes INTEGER,
-                    created_at TEXT,
-                    updated_at TEXT,
-                    closed_at TEXT,
-                    external_ref TEXT,
-                    sender TEXT DEFAULT '',
-                    ephemeral BOOLEAN DEFAULT 0,
-                    replies_to TEXT DEFAULT '',
-                    relates_to TEXT DEFAULT '',
-                    duplicate_of TEXT DEFAULT '',
-                    superseded_by TEXT DEFAULT '',
-                    deleted_at TEXT,
-                    deleted_by TEXT DEFAULT '',
-                    delete_reason TEXT DEFAULT '',
-                    original_type TEXT DEFAULT ''
-                );
-
-                CREATE TABLE IF NOT EXISTS labels (
-                    issue_id TEXT,
-                    label TEXT,
-                    PRIMARY KEY (issue_id, label)
-                );
-
-                CREATE TABLE IF NOT EXISTS dependencies (
-                    issue_id TEXT,
-                    depends_on_id TEXT,
-                    type TEXT,
-                    created_at TEXT,
-                    created_by TEXT,
-                    PRIMARY KEY (issue_id, depends_on_id, type)
-                );
-
-                CREATE TABLE IF NOT EXISTS comments (
-                    id INTEGER PRIMARY KEY,
-                    issue_id TEXT,
-                    author TEXT,
-                    text TEXT,
-                    created_at TEXT
-                );
+            let mut store = Self::open_unmigrated(path)?;
+            store.migrate()?;
+            Ok(store)
+        }
 
-                CREATE TABLE IF NOT EXISTS dirty_issues (
-                    issue_id TEXT PRIMARY KEY
-                );
+        /// Opens the database as-is, for inspecting its schema version.
+        pub fn open_unmigrated<P: AsRef<Path>>(path: P) -> Result<Self> {
+            Ok(SqliteStore {
+                conn: Connection::open(&path)?,
+            })
+        }
 
-                CREATE TABLE IF NOT EXISTS metadata (
-                    key TEXT PRIMARY KEY,
-                    value TEXT
-                );
+        pub fn schema_version(&self) -> Result<u32> {
+            migrations::schema_version(&self.conn)
+        }
 
-                CREATE TABLE IF NOT EXISTS config (
-                    key TEXT PRIMARY KEY,
-                    value TEXT
-                );
-                ",
-            )?;
+        /// Migrations that `migrate` would apply. Fails if the database was
+        /// written by a newer binary.
+        pub fn pending_migrations(&self) -> Result<Vec<&'static Migration>> {
+            migrations::pending(&self.conn)
+        }
 
-            Ok(SqliteStore { conn })
+        /// Applies pending migrations in order and returns them. Issues
+        /// written without a content hash get one.
+        pub fn migrate(&mut self) -> Result<Vec<&'static Migration>> {
+            let applied = migrations::apply(&mut self.conn)?;
+            let tx = self.conn.transaction()?;
+            let missing: Vec<String> = tx
+                .prepare("SELECT id FROM issues WHERE content_hash IS NULL OR content_hash = ''")?
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            for id in missing {
+                refresh_content_hash(&tx, &id)?;
+            }
+            tx.commit()?;
+            Ok(applied)
         }
 
-        fn export_all_issues(&self) -> Result<Vec<Issue>> {
+        /// Issues as exported to JSONL, ordered by ID: all of them, or only
+        /// those listed in `dirty_issues`.
+        fn export_issues(&self, dirty_only: bool) -> Result<Vec<Issue>> {
             // Fetch all related data first (bulk)
-            let labels_map = self.get_all_labels()?;
-            let deps_map = self.get_all_dependencies()?;
-            let comments_map = self.get_all_comments()?;
-
-            let mut stmt = self.conn.prepare(
-                "SELECT
-                    id, content_hash, title, description, design, acceptance_criteria, notes,
-                    status, priority, issue_type, assignee, estimated_minutes,
-                    created_at, updated_at, closed_at, external_ref,
-                    sender, ephemeral, replies_to, relates_to, duplicate_of, superseded_by,
-                    deleted_at, deleted_by, delete_reason, original_type
-                FROM issues
-                ORDER BY id",
-            )?;
+            let labels_map = self.get_all_labels(dirty_only)?;
+            let deps_map = self.get_all_dependencies(dirty_only)?;
+            let comments_map = self.get_all_comments(dirty_only)?;
+
+            let sql = format!(
+                "SELECT {} FROM issues{} ORDER BY id",
+                ISSUE_COLUMNS,
+                dirty_scope("id", dirty_only)
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
 
             let issue_iter = stmt.query_map([], |row| {
-                let id: String = row.get(0)?;
-                let created_at_s: String = row.get(12)?;
-                let updated_at_s: String = row.get(13)?;
-                let closed_at_s: Option<String> = row.get(14)?;
-                let deleted_at_s: Option<String> = row.get(22)?;
-
-                let created_at = parse_timestamp(&created_at_s).unwrap_or_else(|| Utc::now());
-                let updated_at = parse_timestamp(&updated_at_s).unwrap_or_else(|| Utc::now());
-                let closed_at = closed_at_s.and_then(|s| parse_timestamp(&s));
-                let deleted_at = deleted_at_s.and_then(|s| parse_timestamp(&s));
-
-                let relates_to_s: String = row.get(19).unwrap_or_default();
-                let relates_to = if relates_to_s.is_empty() {
-                    Vec::new()
-                } else {
-                    serde_json::from_str(&relates_to_s).unwrap_or_default()
-                };
-
-                Ok(Issue {
-                    id: id.clone(),
-                    content_hash: row.get(1).unwrap_or_default(),
-                    title: row.get(2).unwrap_or_default(),
-                    description: row.get(3).unwrap_or_default(),
-                    design: row.get(4).unwrap_or_default(),
-                    acceptance_criteria: row.get(5).unwrap_or_default(),
-                    notes: row.get(6).unwrap_or_default(),
-                    status: row.get(7).unwrap_or_default(),
-                    priority: row.get(8).unwrap_or_default(),
-                    issue_type: row.get(9).unwrap_or_default(),
-                    assignee: row.get(10)?,
-                    estimated_minutes: row.get(11)?,
-                    created_at,
-                    updated_at,
-                    closed_at,
-                    external_ref: row.get(15)?,
-                    sender: row.get(16).unwrap_or_default(),
-                    ephemeral: row.get(17).unwrap_or(false),
-                    replies_to: row.get(18).unwrap_or_default(),
-                    relates_to,
-                    duplicate_of: row.get(20).unwrap_or_default(),
-                    superseded_by: row.get(21).unwrap_or_default(),
-
-                    deleted_at,
-                    deleted_by: row.get(23).unwrap_or_default(),
-                    delete_reason: row.get(24).unwrap_or_default(),
-                    original_type: row.get(25).unwrap_or_default(),
-
-                    labels: labels_map.get(&id).cloned().unwrap_or_default(),
-                    dependencies: deps_map.get(&id).cloned().unwrap_or_default(),
-                    comments: comments_map.get(&id).cloned().unwrap_or_default(),
-                })
+                let mut issue = issue_from_row(row)?;
+                issue.labels = labels_map.get(&issue.id).cloned().unwrap_or_default();
+                issue.dependencies = deps_map.get(&issue.id).cloned().unwrap_or_default();
+                issue.comments = comments_map.get(&issue.id).cloned().unwrap_or_default();
+                Ok(issue)
             })?;
 
             let mut issues = Vec::new();
@@ -205,8 +497,11 @@ pub mod sqlite_impl {
             Ok(issues)
         }
 
-        fn get_all_labels(&self) -> Result<HashMap<String, Vec<String>>> {
-            let mut stmt = self.conn.prepare("SELECT issue_id, label FROM labels")?;
+        fn get_all_labels(&self, dirty_only: bool) -> Result<HashMap<String, Vec<String>>> {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT issue_id, label FROM labels{} ORDER BY issue_id, label",
+                dirty_scope("issue_id", dirty_only)
+            ))?;
             let rows = stmt.query_map([], |row| {
                 Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
             })?;
@@ -219,13 +514,18 @@ pub mod sqlite_impl {
             Ok(map)
         }
 
-        fn get_all_dependencies(&self) -> Result<HashMap<String, Vec<Dependency>>> {
-            let mut stmt = self.conn.prepare(
-                "SELECT issue_id, depends_on_id, type, created_at, created_by FROM dependencies",
-            )?;
+        fn get_all_dependencies(
+            &self,
+            dirty_only: bool,
+        ) -> Result<HashMap<String, Vec<Dependency>>> {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT issue_id, depends_on_id, type, created_at, created_by FROM dependencies{}
+                 ORDER BY issue_id, created_at, depends_on_id, type",
+                dirty_scope("issue_id", dirty_only)
+            ))?;
             let rows = stmt.query_map([], |row| {
                 let created_at_s: String = row.get(3)?;
-                let created_at = parse_timestamp(&created_at_s).unwrap_or_else(|| Utc::now());
+                let created_at = parse_timestamp(&created_at_s).unwrap_or_else(Utc::now);
 
                 Ok(Dependency {
                     issue_id: row.get(0)?,
@@ -244,22 +544,13 @@ pub mod sqlite_impl {
             Ok(map)
         }
 
-        fn get_all_comments(&self) -> Result<HashMap<String, Vec<Comment>>> {
-            let mut stmt = self
-                .conn
-                .prepare("SELECT id, issue_id, author, text, created_at FROM comments")?;
-            let rows = stmt.query_map([], |row| {
-                let created_at_s: String = row.get(4)?;
-                let created_at = parse_timestamp(&created_at_s).unwrap_or_else(|| Utc::now());
-
-                Ok(Comment {
-                    id: row.get(0)?,
-                    issue_id: row.get(1)?,
-                    author: row.get(2)?,
-                    text: row.get(3)?,
-                    created_at,
-                })
-            })?;
+        fn get_all_comments(&self, dirty_only: bool) -> Result<HashMap<String, Vec<Comment>>> {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT id, issue_id, author, text, created_at FROM comments{}
+                 ORDER BY issue_id, created_at, id",
+                dirty_scope("issue_id", dirty_only)
+            ))?;
+            let rows = stmt.query_map([], comment_from_row)?;
 
             let mut map: HashMap<String, Vec<Comment>> = HashMap::new();
             for row in rows {
@@ -270,6 +561,71 @@ pub mod sqlite_impl {
             }
             Ok(map)
         }
+
+        fn query_issues(&self, where_clause: &str) -> Result<Vec<Issue>> {
+            let sql = format!(
+                "SELECT {} FROM issues WHERE {} ORDER BY priority ASC, created_at ASC, id ASC",
+                ISSUE_COLUMNS, where_clause
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let rows = stmt.query_map([], issue_from_row)?;
+
+            let mut issues = Vec::new();
+            for issue in rows {
+                issues.push(issue?);
+            }
+            Ok(issues)
+        }
+
+        fn issue_summaries(&self, ids: &[&str]) -> Result<HashMap<String, IssueSummary>> {
+            let mut map = HashMap::new();
+            if ids.is_empty() {
+                return Ok(map);
+            }
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            let sql = format!(
+                "SELECT id, title, status FROM issues WHERE id IN ({})",
+                placeholders
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(ids.iter()), |row| {
+                Ok(IssueSummary {
+                    id: row.get(0)?,
+                    title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                    status: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                })
+            })?;
+            for row in rows {
+                let summary = row?;
+                map.insert(summary.id.clone(), summary);
+            }
+            Ok(map)
+        }
+
+        fn issue_exists(&self, id: &str) -> Result<bool> {
+            let count: i64 = self.conn.query_row(
+                "SELECT COUNT(*) FROM issues WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )?;
+            Ok(count > 0)
+        }
+
+        fn blocked_map(&self) -> Result<std::collections::BTreeMap<String, Blockers>> {
+            let graph = self.dependency_graph()?;
+
+            let mut stmt = self.conn.prepare("SELECT id, status FROM issues")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })?;
+            let mut statuses = HashMap::new();
+            for row in rows {
+                let (id, status) = row?;
+                statuses.insert(id, status.unwrap_or_default());
+            }
+
+            Ok(graph.blocked(&statuses))
+        }
     }
 
     impl Store for SqliteStore {
@@ -293,44 +649,25 @@ pub mod sqlite_impl {
             Ok(())
         }
 
-        fn update_issue(&self, issue: &Issue) -> Result<()> {
-            // Serialize nested fields
-            let relates_to_json = serde_json::to_string(&issue.relates_to).unwrap_or_default();
+        fn update_issue_if_unchanged(&self, issue: &Issue, expected_hash: &str) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            let stored: Option<String> = tx
+                .query_row(
+                    "SELECT content_hash FROM issues WHERE id = ?1",
+                    [&issue.id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            check_content_hash(&issue.id, stored.as_deref(), expected_hash)?;
+            self.update_issue(issue)?;
+            tx.commit()?;
+            Ok(())
+        }
 
-            // Update main issue record
+        fn update_issue(&self, issue: &Issue) -> Result<()> {
             self.conn.execute(
-                "UPDATE issues SET
-                    title = ?2, description = ?3, status = ?4, priority = ?5, issue_type = ?6,
-                    created_at = ?7, updated_at = ?8, closed_at = ?9, external_ref = ?10,
-                    sender = ?11, ephemeral = ?12, replies_to = ?13, relates_to = ?14,
-                    duplicate_of = ?15, superseded_by = ?16,
-                    deleted_at = ?17, deleted_by = ?18, delete_reason = ?19, original_type = ?20,
-                    assignee = ?21, estimated_minutes = ?22
-                WHERE id = ?1",
-                params![
-                    &issue.id,
-                    &issue.title,
-                    &issue.description,
-                    &issue.status,
-                    &issue.priority,
-                    &issue.issue_type,
-                    issue.created_at.to_rfc3339(),
-                    issue.updated_at.to_rfc3339(),
-                    issue.closed_at.map(|t| t.to_rfc3339()),
-                    &issue.external_ref,
-                    &issue.sender,
-                    issue.ephemeral,
-                    &issue.replies_to,
-                    relates_to_json,
-                    &issue.duplicate_of,
-                    &issue.superseded_by,
-                    issue.deleted_at.map(|t| t.to_rfc3339()),
-                    &issue.deleted_by,
-                    &issue.delete_reason,
-                    &issue.original_type,
-                    &issue.assignee,
-                    &issue.estimated_minutes,
-                ],
+                &update_issue_sql(),
+                rusqlite::params_from_iter(issue_values(issue)),
             )?;
 
             // Replace labels
@@ -363,6 +700,33 @@ pub mod sqlite_impl {
             Ok(())
         }
 
+        fn get_metadata(&self, key: &str) -> Result<Option<String>> {
+            Ok(self
+                .conn
+                .query_row("SELECT value FROM metadata WHERE key = ?1", [key], |row| {
+                    row.get(0)
+                })
+                .optional()?)
+        }
+
+        fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )?;
+            Ok(())
+        }
+
+        fn dirty_issue_ids(&self) -> Result<Vec<String>> {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT issue_id FROM dirty_issues ORDER BY issue_id")?;
+            let ids = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(ids)
+        }
+
         fn list_config(&self) -> Result<Vec<(String, String)>> {
             let mut stmt = self
                 .conn
@@ -389,27 +753,20 @@ pub mod sqlite_impl {
                 id.to_string()
             };
 
-            let mut stmt = self.conn.prepare(
-                "SELECT
-                    id, content_hash, title, description, design, acceptance_criteria, notes,
-                    status, priority, issue_type, assignee, estimated_minutes,
-                    created_at, updated_at, closed_at, external_ref,
-                    sender, ephemeral, replies_to, relates_to, duplicate_of, superseded_by,
-                    deleted_at, deleted_by, delete_reason, original_type
-                FROM issues
-                WHERE id LIKE ?1
-                LIMIT 1",
-            )?;
+            let sql = format!(
+                "SELECT {} FROM issues WHERE id LIKE ?1 LIMIT 1",
+                ISSUE_COLUMNS
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
 
             let mut rows = stmt.query([&query_id])?;
 
-            let row = if let Some(row) = rows.next()? {
-                row
+            let mut issue = if let Some(row) = rows.next()? {
+                issue_from_row(row)?
             } else {
                 return Ok(None);
             };
-
-            let id: String = row.get(0)?;
+            let id = issue.id.clone();
 
             // Fetch children
             let mut labels = Vec::new();
@@ -425,7 +782,7 @@ pub mod sqlite_impl {
             let mut deps_stmt = self.conn.prepare("SELECT depends_on_id, type, created_at, created_by FROM dependencies WHERE issue_id = ?1")?;
             let deps_rows = deps_stmt.query_map([&id], |r| {
                 let created_at_s: String = r.get(2)?;
-                let created_at = parse_timestamp(&created_at_s).unwrap_or_else(|| Utc::now());
+                let created_at = parse_timestamp(&created_at_s).unwrap_or_else(Utc::now);
                 Ok(Dependency {
                     issue_id: id.clone(),
                     depends_on_id: r.get(0)?,
@@ -438,236 +795,64 @@ pub mod sqlite_impl {
                 deps.push(d?);
             }
 
-            let mut comments = Vec::new();
-            let mut comments_stmt = self.conn.prepare("SELECT id, author, text, created_at FROM comments WHERE issue_id = ?1 ORDER BY created_at")?;
-            let comments_rows = comments_stmt.query_map([&id], |r| {
-                let created_at_s: String = r.get(3)?;
-                let created_at = parse_timestamp(&created_at_s).unwrap_or_else(|| Utc::now());
-                Ok(Comment {
-                    id: r.get(0)?,
-                    issue_id: id.clone(),
-                    author: r.get(1)?,
-                    text: r.get(2)?,
-                    created_at,
-                })
-            })?;
-            for c in comments_rows {
-                comments.push(c?);
-            }
-
-            let created_at_s: String = row.get(12)?;
-            let updated_at_s: String = row.get(13)?;
-            let closed_at_s: Option<String> = row.get(14)?;
-            let deleted_at_s: Option<String> = row.get(22)?;
-
-            let created_at = parse_timestamp(&created_at_s).unwrap_or_else(|| Utc::now());
-            let updated_at = parse_timestamp(&updated_at_s).unwrap_or_else(|| Utc::now());
-            let closed_at = closed_at_s.and_then(|s| parse_timestamp(&s));
-            let deleted_at = deleted_at_s.and_then(|s| parse_timestamp(&s));
-
-            let relates_to_s: String = row.get(19).unwrap_or_default();
-            let relates_to = if relates_to_s.is_empty() {
-                Vec::new()
-            } else {
-                serde_json::from_str(&relates_to_s).unwrap_or_default()
-            };
-
-            Ok(Some(Issue {
-                id,
-                content_hash: row.get(1).unwrap_or_default(),
-                title: row.get(2).unwrap_or_default(),
-                description: row.get(3).unwrap_or_default(),
-                design: row.get(4).unwrap_or_default(),
-                acceptance_criteria: row.get(5).unwrap_or_default(),
-                notes: row.get(6).unwrap_or_default(),
-                status: row.get(7).unwrap_or_default(),
-                priority: row.get(8).unwrap_or_default(),
-                issue_type: row.get(9).unwrap_or_default(),
-                assignee: row.get(10)?,
-                estimated_minutes: row.get(11)?,
-                created_at,
-                updated_at,
-                closed_at,
-                external_ref: row.get(15)?,
-                sender: row.get(16).unwrap_or_default(),
-                ephemeral: row.get(17).unwrap_or(false),
-                replies_to: row.get(18).unwrap_or_default(),
-                relates_to,
-                duplicate_of: row.get(20).unwrap_or_default(),
-                superseded_by: row.get(21).unwrap_or_default(),
-                deleted_at,
-                deleted_by: row.get(23).unwrap_or_default(),
-                delete_reason: row.get(24).unwrap_or_default(),
-                original_type: row.get(25).unwrap_or_default(),
-                labels,
-                dependencies: deps,
-                comments,
-            }))
-        }
-
-        fn list_issues(
-            &self,
-            status: Option<&str>,
-            assignee: Option<&str>,
-            priority: Option<i32>,
-            issue_type: Option<&str>,
-            label: Option<&str>,
-            sort_by: Option<&str>,
-        ) -> Result<Vec<Issue>> {
-            let mut conditions = Vec::new();
-            let mut args: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-            if let Some(s) = status {
-                conditions.push("issues.status = ?");
-                args.push(Box::new(s.to_string()));
-            }
-
-            if let Some(a) = assignee {
-                if a == "unassigned" {
-                    conditions.push("(issues.assignee IS NULL OR issues.assignee = '')");
-                } else {
-                    conditions.push("issues.assignee = ?");
-                    args.push(Box::new(a.to_string()));
-                }
-            }
-
-            if let Some(p) = priority {
-                conditions.push("issues.priority = ?");
-                args.push(Box::new(p));
-            }
-
-            if let Some(t) = issue_type {
-                conditions.push("issues.issue_type = ?");
-                args.push(Box::new(t.to_string()));
-            }
-
-            let mut sql = "SELECT issues.id, issues.title, issues.description, issues.status, issues.priority, issues.issue_type, issues.created_at, issues.updated_at, issues.assignee FROM issues".to_string();
+            let comments = self.list_comments(&id)?;
 
-            if let Some(l) = label {
-                sql.push_str(" INNER JOIN labels ON issues.id = labels.issue_id");
-                conditions.push("labels.label = ?");
-                args.push(Box::new(l.to_string()));
-            }
+            issue.labels = labels;
+            issue.dependencies = deps;
+            issue.comments = comments;
+            Ok(Some(issue))
+        }
 
+        fn list_issues(&self, filter: &IssueFilter) -> Result<Vec<Issue>> {
+            let (conditions, mut args) = filter_conditions(filter);
+            let mut sql = format!("SELECT {} FROM issues", qualified_issue_columns());
             if !conditions.is_empty() {
                 sql.push_str(" WHERE ");
                 sql.push_str(&conditions.join(" AND "));
             }
-
-            let order_clause = match sort_by {
-                Some("updated") => "ORDER BY issues.updated_at DESC",
-                Some("created") => "ORDER BY issues.created_at DESC",
-                Some("priority") => "ORDER BY issues.priority ASC, issues.created_at DESC",
-                _ => "ORDER BY issues.created_at DESC",
-            };
-            sql.push_str(" ");
-            sql.push_str(order_clause);
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&order_clause(filter));
+            push_limit_offset(&mut sql, &mut args, filter);
 
             let mut stmt = self.conn.prepare(&sql)?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(args.iter()), issue_from_row)?;
 
-            let issue_iter = stmt.query_map(rusqlite::params_from_iter(args.iter()), |row| {
-                let created_at_s: String = row.get(6)?;
-                let updated_at_s: String = row.get(7)?;
-
-                let created_at = parse_timestamp(&created_at_s).unwrap_or_else(|| Utc::now());
-                let updated_at = parse_timestamp(&updated_at_s).unwrap_or_else(|| Utc::now());
-
-                Ok(Issue {
-                    id: row.get(0)?,
-                    content_hash: String::new(),
-                    title: row.get(1)?,
-                    description: row.get(2)?,
-                    design: String::new(),
-                    acceptance_criteria: String::new(),
-                    notes: String::new(),
-                    status: row.get(3)?,
-                    priority: row.get(4)?,
-                    issue_type: row.get(5)?,
-                    assignee: None,
-                    estimated_minutes: None,
-                    created_at,
-                    updated_at,
-                    closed_at: None,
-                    external_ref: None,
-                    sender: String::new(),
-                    ephemeral: false,
-                    replies_to: String::new(),
-                    relates_to: Vec::new(),
-                    duplicate_of: String::new(),
-                    superseded_by: String::new(),
-
-                    deleted_at: None,
-                    deleted_by: String::new(),
-                    delete_reason: String::new(),
-                    original_type: String::new(),
-
-                    labels: Vec::new(),
-                    dependencies: Vec::new(),
-                    comments: Vec::new(),
-                })
-            })?;
-
+            let labels = self.get_all_labels(false)?;
             let mut issues = Vec::new();
-            for issue in issue_iter {
-                issues.push(issue?);
+            for issue in rows {
+                let mut issue = issue?;
+                issue.labels = labels.get(&issue.id).cloned().unwrap_or_default();
+                issues.push(issue);
             }
             Ok(issues)
         }
 
-        fn import_from_jsonl(&mut self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<()> {
-            if !fs.exists(jsonl_path) {
-                return Ok(());
-            }
-
-            let reader = fs.open_read(jsonl_path)?;
-
+        fn import_issues(&mut self, issues: Vec<Issue>) -> Result<ImportStats> {
+            let mut stats = ImportStats::default();
             let tx = self.conn.transaction()?;
 
-            for line in reader.lines() {
-                let line = line?;
-                if line.trim().is_empty() {
-                    continue;
-                }
-                let issue: Issue = serde_json::from_str(&line)?;
-
-                let relates_to_json = serde_json::to_string(&issue.relates_to).unwrap_or_default();
-
-                tx.execute(
-                    "INSERT OR REPLACE INTO issues (
-                        id, title, description, status, priority, issue_type,
-                        created_at, updated_at, closed_at, external_ref,
-                        sender, ephemeral, replies_to, relates_to, duplicate_of, superseded_by,
-                        deleted_at, deleted_by, delete_reason, original_type
+            for issue in issues {
+                let stored: Option<Issue> = tx
+                    .query_row(
+                        &format!("SELECT {} FROM issues WHERE id = ?1", ISSUE_COLUMNS),
+                        [&issue.id],
+                        issue_from_row,
                     )
-                    VALUES (
-                        ?1, ?2, ?3, ?4, ?5, ?6,
-                        ?7, ?8, ?9, ?10,
-                        ?11, ?12, ?13, ?14, ?15, ?16,
-                        ?17, ?18, ?19, ?20
-                    )",
-                    params![
-                        &issue.id,
-                        &issue.title,
-                        &issue.description,
-                        &issue.status,
-                        &issue.priority,
-                        &issue.issue_type,
-                        issue.created_at.to_rfc3339(),
-                        issue.updated_at.to_rfc3339(),
-                        issue.closed_at.map(|t| t.to_rfc3339()),
-                        &issue.external_ref,
-                        &issue.sender,
-                        issue.ephemeral,
-                        &issue.replies_to,
-                        relates_to_json,
-                        &issue.duplicate_of,
-                        &issue.superseded_by,
-                        issue.deleted_at.map(|t| t.to_rfc3339()),
-                        &issue.deleted_by,
-                        &issue.delete_reason,
-                        &issue.original_type,
-                    ],
-                )?;
+                    .optional()?;
+                match stored {
+                    Some(stored) if stored.same_fields(&issue) => stats.unchanged += 1,
+                    stored => {
+                        if stored.is_some() {
+                            stats.updated += 1;
+                        } else {
+                            stats.created += 1;
+                        }
+                        tx.execute(
+                            &insert_issue_sql(true),
+                            rusqlite::params_from_iter(issue_values(&issue)),
+                        )?;
+                    }
+                }
 
                 tx.execute("DELETE FROM labels WHERE issue_id = ?1", params![&issue.id])?;
                 for label in &issue.labels {
@@ -696,6 +881,21 @@ pub mod sqlite_impl {
                     .collect();
                 drop(stmt);
 
+                // Comments are told apart by author and text, as IDs differ
+                // between databases; ones the issue no longer has are dropped.
+                let incoming: std::collections::HashSet<(&str, &str)> = issue
+                    .comments
+                    .iter()
+                    .map(|c| (c.author.as_str(), c.text.as_str()))
+                    .collect();
+                for (author, text) in &existing_comments {
+                    if !incoming.contains(&(author.as_str(), text.as_str())) {
+                        tx.execute(
+                            "DELETE FROM comments WHERE issue_id = ?1 AND author = ?2 AND text = ?3",
+                            params![&issue.id, author, text],
+                        )?;
+                    }
+                }
                 for comment in &issue.comments {
                     if !existing_comments.contains(&(comment.author.clone(), comment.text.clone()))
                     {
@@ -708,7 +908,7 @@ pub mod sqlite_impl {
             }
 
             tx.commit()?;
-            Ok(())
+            Ok(stats)
         }
 
         fn generate_unique_id(
@@ -718,75 +918,65 @@ pub mod sqlite_impl {
             description: &str,
             creator: &str,
         ) -> Result<String> {
-            let created_at = Utc::now();
-            let base_length = 6;
-            let max_length = 8;
-
-            for length in base_length..=max_length {
-                for nonce in 0..10 {
-                    let candidate = util::generate_hash_id(
-                        prefix,
-                        title,
-                        description,
-                        creator,
-                        created_at,
-                        length,
-                        nonce,
-                    );
+            let top_level: i64 = self.conn.query_row(
+                "SELECT COUNT(*) FROM issues
+                 WHERE id LIKE ?1 || '-%' AND instr(substr(id, length(?1) + 2), '.') = 0",
+                params![prefix],
+                |row| row.get(0),
+            )?;
+            let base_length = adaptive_id_length(self, top_level as usize)?;
+            util::generate_unique_hash_id(
+                prefix,
+                title,
+                description,
+                creator,
+                Utc::now(),
+                base_length,
+                |candidate| {
                     let count: i64 = self.conn.query_row(
                         "SELECT COUNT(*) FROM issues WHERE id = ?1",
-                        params![&candidate],
+                        params![candidate],
                         |row| row.get(0),
                     )?;
+                    Ok(count > 0)
+                },
+            )
+        }
 
-                    if count == 0 {
-                        return Ok(candidate);
-                    }
-                }
+        fn next_child_id(&self, parent_id: &str) -> Result<String> {
+            let tx = self.conn.unchecked_transaction()?;
+            let status: Option<String> = tx
+                .query_row(
+                    "SELECT status FROM issues WHERE id = ?1",
+                    params![parent_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            check_child_parent(parent_id, status.as_deref())?;
+
+            let mut stmt = tx
+                .prepare("SELECT id FROM issues WHERE substr(id, 1, length(?1) + 1) = ?1 || '.'")?;
+            let mut highest = 0;
+            for id in stmt.query_map(params![parent_id], |row| row.get::<_, String>(0))? {
+                highest = highest.max(child_number(parent_id, &id?).unwrap_or(0));
             }
-            Err(anyhow::anyhow!(
-                "Failed to generate unique ID after retries"
-            ))
+            drop(stmt);
+
+            let next: u32 = tx.query_row(
+                "INSERT INTO child_counters (parent_id, last_child) VALUES (?1, ?2)
+                 ON CONFLICT(parent_id) DO UPDATE SET last_child = max(last_child + 1, ?2)
+                 RETURNING last_child",
+                params![parent_id, highest + 1],
+                |row| row.get(0),
+            )?;
+            tx.commit()?;
+            Ok(format!("{}.{}", parent_id, next))
         }
 
         fn create_issue(&self, issue: &Issue) -> Result<()> {
-            let relates_to_json = serde_json::to_string(&issue.relates_to).unwrap_or_default();
-
             self.conn.execute(
-                "INSERT INTO issues (
-                    id, title, description, status, priority, issue_type,
-                    created_at, updated_at, closed_at, external_ref,
-                    sender, ephemeral, replies_to, relates_to, duplicate_of, superseded_by,
-                    deleted_at, deleted_by, delete_reason, original_type
-                )
-                VALUES (
-                    ?1, ?2, ?3, ?4, ?5, ?6,
-                    ?7, ?8, ?9, ?10,
-                    ?11, ?12, ?13, ?14, ?15, ?16,
-                    ?17, ?18, ?19, ?20
-                )",
-                params![
-                    &issue.id,
-                    &issue.title,
-                    &issue.description,
-                    &issue.status,
-                    &issue.priority,
-                    &issue.issue_type,
-                    issue.created_at.to_rfc3339(),
-                    issue.updated_at.to_rfc3339(),
-                    issue.closed_at.map(|t| t.to_rfc3339()),
-                    &issue.external_ref,
-                    &issue.sender,
-                    issue.ephemeral,
-                    &issue.replies_to,
-                    relates_to_json,
-                    &issue.duplicate_of,
-                    &issue.superseded_by,
-                    issue.deleted_at.map(|t| t.to_rfc3339()),
-                    &issue.deleted_by,
-                    &issue.delete_reason,
-                    &issue.original_type,
-                ],
+                &insert_issue_sql(false),
+                rusqlite::params_from_iter(issue_values(issue)),
             )?;
 
             for label in &issue.labels {
@@ -817,12 +1007,29 @@ pub mod sqlite_impl {
             Ok(())
         }
 
-        fn export_to_jsonl(&self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<()> {
-            let issues = self.export_all_issues()?;
+        fn create_issues(&self, issues: &[Issue]) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            for issue in issues {
+                self.create_issue(issue)
+                    .map_err(|e| e.context(format!("failed to create {}", issue.id)))?;
+            }
+            tx.commit()?;
+            Ok(())
+        }
 
-            let dir = jsonl_path.parent().unwrap_or_else(|| Path::new("."));
-            let file_name = jsonl_path.file_name().unwrap_or_default();
-            let temp_path = dir.join(format!(".{}.tmp", file_name.to_string_lossy()));
+        fn update_issues(&self, issues: &[Issue]) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            for issue in issues {
+                self.update_issue(issue)
+                    .map_err(|e| e.context(format!("failed to update {}", issue.id)))?;
+            }
+            tx.commit()?;
+            Ok(())
+        }
+
+        fn export_to_jsonl(&self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<()> {
+            let issues = self.export_issues(false)?;
+            let temp_path = jsonl_temp_path(jsonl_path);
 
             {
                 let file = fs.open_write(&temp_path)?;
@@ -844,13 +1051,776 @@ pub mod sqlite_impl {
 
             self.conn.execute(
                 "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
-                params!["jsonl_content_hash", &hash],
+                params![JSONL_HASH_KEY, &hash],
             )?;
 
             self.conn.execute("DELETE FROM dirty_issues", [])?;
 
             Ok(())
         }
+
+        fn export_dirty_to_jsonl(&self, jsonl_path: &Path, fs: &dyn FileSystem) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            let dirty = self.dirty_issue_ids()?;
+            if dirty.is_empty() && fs.exists(jsonl_path) {
+                return Ok(());
+            }
+
+            let last_hash = self.get_metadata(JSONL_HASH_KEY)?;
+            let existing = if fs.exists(jsonl_path) {
+                Some(fs.read_to_string(jsonl_path)?)
+            } else {
+                None
+            };
+            let patched = match existing {
+                Some(existing) if last_hash == Some(jsonl_hash(existing.as_bytes())) => {
+                    // Issues that are dirty but gone from the database drop out
+                    let mut changed: BTreeMap<String, Option<Issue>> =
+                        dirty.into_iter().map(|id| (id, None)).collect();
+                    for issue in self.export_issues(true)? {
+                        changed.insert(issue.id.clone(), Some(issue));
+                    }
+                    patch_jsonl(&existing, &changed)?
+                }
+                _ => None,
+            };
+            let Some(contents) = patched else {
+                self.export_to_jsonl(jsonl_path, fs)?;
+                tx.commit()?;
+                return Ok(());
+            };
+
+            let temp_path = jsonl_temp_path(jsonl_path);
+            fs.write(&temp_path, contents.as_bytes())?;
+            fs.rename(&temp_path, jsonl_path)?;
+
+            self.set_metadata(JSONL_HASH_KEY, &jsonl_hash(contents.as_bytes()))?;
+            tx.execute("DELETE FROM dirty_issues", [])?;
+            tx.commit()?;
+            Ok(())
+        }
+
+        fn ready_issues(&self) -> Result<Vec<Issue>> {
+            let blocked = self.blocked_map()?;
+            let mut issues = self.query_issues(&format!(
+                "status IN ('open', 'in_progress') AND issue_type != '{}'",
+                TYPE_MESSAGE
+            ))?;
+            issues.retain(|i| !blocked.contains_key(&i.id));
+            Ok(issues)
+        }
+
+        fn blocked_issues(&self) -> Result<Vec<BlockedIssue>> {
+            let mut blocked = self.blocked_map()?;
+            let mut issues = self.query_issues(&format!(
+                "status IN ('open', 'in_progress', 'blocked') AND issue_type != '{}'",
+                TYPE_MESSAGE
+            ))?;
+            issues.retain(|i| blocked.contains_key(&i.id) || i.status == "blocked");
+
+            let blocker_ids: Vec<&str> = issues
+                .iter()
+                .filter_map(|i| blocked.get(&i.id))
+                .flat_map(Blockers::ids)
+                .map(String::as_str)
+                .collect();
+            let summaries = self.issue_summaries(&blocker_ids)?;
+
+            Ok(issues
+                .into_iter()
+                .map(|issue| {
+                    let summary = |id: &String| summaries.get(id).cloned();
+                    let (blockers, blocked_via_parent) = match blocked.remove(&issue.id) {
+                        Some(Blockers::Direct(ids)) => {
+                            (ids.iter().filter_map(summary).collect(), None)
+                        }
+                        Some(Blockers::Parent(id)) => (Vec::new(), summary(&id)),
+                        None => (Vec::new(), None),
+                    };
+                    BlockedIssue {
+                        issue,
+                        blockers,
+                        blocked_via_parent,
+                    }
+                })
+                .collect())
+        }
+
+        fn add_dependency(&self, dep: &Dependency) -> Result<()> {
+            if !self.issue_exists(&dep.issue_id)? {
+                anyhow::bail!("issue {} not found", dep.issue_id);
+            }
+            if !self.issue_exists(&dep.depends_on_id)? {
+                anyhow::bail!("dependency target {} not found", dep.depends_on_id);
+            }
+            graph::validate_new_dependency(&self.dependency_graph()?, dep)?;
+
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute(
+                "INSERT INTO dependencies (issue_id, depends_on_id, type, created_at, created_by) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![&dep.issue_id, &dep.depends_on_id, &dep.type_, dep.created_at.to_rfc3339(), &dep.created_by],
+            )?;
+            for id in [&dep.issue_id, &dep.depends_on_id] {
+                tx.execute(
+                    "INSERT OR IGNORE INTO dirty_issues (issue_id) VALUES (?1)",
+                    params![id],
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        }
+
+        fn remove_dependency(&self, issue_id: &str, depends_on_id: &str) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            let removed = tx.execute(
+                "DELETE FROM dependencies WHERE issue_id = ?1 AND depends_on_id = ?2",
+                params![issue_id, depends_on_id],
+            )?;
+            if removed == 0 {
+                anyhow::bail!(
+                    "dependency from {} to {} does not exist",
+                    issue_id,
+                    depends_on_id
+                );
+            }
+            for id in [issue_id, depends_on_id] {
+                tx.execute(
+                    "INSERT OR IGNORE INTO dirty_issues (issue_id) VALUES (?1)",
+                    params![id],
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        }
+
+        fn dependency_graph(&self) -> Result<DependencyGraph> {
+            let deps: Vec<Dependency> = self
+                .get_all_dependencies(false)?
+                .into_values()
+                .flatten()
+                .collect();
+            Ok(DependencyGraph::new(&deps))
+        }
+
+        fn delete_issue(&self, id: &str, actor: &str, reason: &str) -> Result<()> {
+            let status: Option<String> = self
+                .conn
+                .query_row("SELECT status FROM issues WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })
+                .optional()?;
+            match status.as_deref() {
+                None => anyhow::bail!("issue {} not found", id),
+                Some(STATUS_TOMBSTONE) => anyhow::bail!("issue {} is already deleted", id),
+                Some(_) => {}
+            }
+
+            let tx = self.conn.unchecked_transaction()?;
+            let now = Utc::now().to_rfc3339();
+            tx.execute(
+                "UPDATE issues SET
+                    status = ?2, closed_at = NULL, deleted_at = ?3, deleted_by = ?4,
+                    delete_reason = ?5, original_type = issue_type, updated_at = ?3
+                WHERE id = ?1",
+                params![id, STATUS_TOMBSTONE, now, actor, reason],
+            )?;
+            refresh_content_hash(&tx, id)?;
+            tx.execute(
+                "INSERT OR IGNORE INTO dirty_issues (issue_id)
+                 SELECT issue_id FROM dependencies WHERE depends_on_id = ?1
+                 UNION SELECT depends_on_id FROM dependencies WHERE issue_id = ?1
+                 UNION SELECT ?1",
+                params![id],
+            )?;
+            tx.execute(
+                "DELETE FROM dependencies WHERE issue_id = ?1 OR depends_on_id = ?1",
+                params![id],
+            )?;
+            tx.commit()?;
+            Ok(())
+        }
+
+        fn reopen_issue(&self, id: &str, actor: &str, reason: &str) -> Result<()> {
+            let status: Option<String> = self
+                .conn
+                .query_row("SELECT status FROM issues WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })
+                .optional()?;
+            match status.as_deref() {
+                None => anyhow::bail!("issue {} not found", id),
+                Some("closed") => {}
+                Some(other) => anyhow::bail!("issue {} is not closed (status: {})", id, other),
+            }
+
+            let tx = self.conn.unchecked_transaction()?;
+            let now = Utc::now().to_rfc3339();
+            tx.execute(
+                "UPDATE issues SET status = 'open', closed_at = NULL, updated_at = ?2 WHERE id = ?1",
+                params![id, now],
+            )?;
+            refresh_content_hash(&tx, id)?;
+            if !reason.is_empty() {
+                tx.execute(
+                    "INSERT INTO comments (issue_id, author, text, created_at) VALUES (?1, ?2, ?3, ?4)",
+                    params![id, actor, reason, now],
+                )?;
+            }
+            tx.execute(
+                "INSERT OR IGNORE INTO dirty_issues (issue_id) VALUES (?1)",
+                params![id],
+            )?;
+            tx.commit()?;
+            Ok(())
+        }
+
+        fn undelete_issue(&self, id: &str) -> Result<()> {
+            let sql = format!("SELECT {} FROM issues WHERE id = ?1", ISSUE_COLUMNS);
+            let issue = match self.conn.query_row(&sql, [id], issue_from_row).optional()? {
+                Some(issue) => issue,
+                None => anyhow::bail!("issue {} not found", id),
+            };
+            let issue_type = restorable_type(&issue)?;
+
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute(
+                "UPDATE issues SET
+                    status = 'open', issue_type = ?2, deleted_at = NULL, deleted_by = '',
+                    delete_reason = '', original_type = '', updated_at = ?3
+                WHERE id = ?1",
+                params![id, issue_type, Utc::now().to_rfc3339()],
+            )?;
+            refresh_content_hash(&tx, id)?;
+            tx.execute(
+                "INSERT OR IGNORE INTO dirty_issues (issue_id) VALUES (?1)",
+                params![id],
+            )?;
+            tx.commit()?;
+            Ok(())
+        }
+
+        fn add_comment(&self, issue_id: &str, author: &str, text: &str) -> Result<Comment> {
+            if !self.issue_exists(issue_id)? {
+                anyhow::bail!("issue {} not found", issue_id);
+            }
+            let created_at = Utc::now();
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute(
+                "INSERT INTO comments (issue_id, author, text, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![issue_id, author, text, created_at.to_rfc3339()],
+            )?;
+            let id = tx.last_insert_rowid();
+            tx.execute(
+                "INSERT OR IGNORE INTO dirty_issues (issue_id) VALUES (?1)",
+                params![issue_id],
+            )?;
+            tx.commit()?;
+            Ok(Comment {
+                id,
+                issue_id: issue_id.to_string(),
+                author: author.to_string(),
+                text: text.to_string(),
+                created_at,
+            })
+        }
+
+        fn list_comments(&self, issue_id: &str) -> Result<Vec<Comment>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, issue_id, author, text, created_at FROM comments
+                 WHERE issue_id = ?1 ORDER BY created_at, id",
+            )?;
+            let rows = stmt.query_map([issue_id], comment_from_row)?;
+            let mut comments = Vec::new();
+            for comment in rows {
+                comments.push(comment?);
+            }
+            Ok(comments)
+        }
+
+        fn apply_compaction(&self, issue: &Issue) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            self.update_issue(issue)?;
+            tx.execute("DELETE FROM comments WHERE issue_id = ?1", [&issue.id])?;
+            tx.commit()?;
+            Ok(())
+        }
+
+        fn delete_comment(&self, comment_id: i64) -> Result<()> {
+            let issue_id: Option<String> = self
+                .conn
+                .query_row(
+                    "SELECT issue_id FROM comments WHERE id = ?1",
+                    [comment_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let issue_id = match issue_id {
+                Some(issue_id) => issue_id,
+                None => anyhow::bail!("comment {} not found", comment_id),
+            };
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute("DELETE FROM comments WHERE id = ?1", [comment_id])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO dirty_issues (issue_id) VALUES (?1)",
+                params![issue_id],
+            )?;
+            tx.commit()?;
+            Ok(())
+        }
+
+        fn rename_prefix(
+            &self,
+            old_prefix: &str,
+            new_prefix: &str,
+            dry_run: bool,
+        ) -> Result<Vec<(String, String)>> {
+            let new_prefix = rename::validate_prefix(new_prefix)?;
+            if new_prefix == old_prefix {
+                anyhow::bail!(
+                    "new prefix is the same as the current prefix: {}",
+                    old_prefix
+                );
+            }
+            let tx = self.conn.unchecked_transaction()?;
+            let rename::Plan { moves, changed } =
+                rename::plan(&self.export_issues(false)?, old_prefix, &new_prefix)?;
+            if dry_run {
+                return Ok(moves);
+            }
+
+            for (old_id, issue) in &changed {
+                for table in ["labels", "dependencies", "comments"] {
+                    tx.execute(
+                        &format!("DELETE FROM {} WHERE issue_id = ?1", table),
+                        params![old_id],
+                    )?;
+                }
+                tx.execute("DELETE FROM issues WHERE id = ?1", params![old_id])?;
+                tx.execute(
+                    "DELETE FROM dirty_issues WHERE issue_id = ?1",
+                    params![old_id],
+                )?;
+
+                tx.execute(
+                    &insert_issue_sql(false),
+                    rusqlite::params_from_iter(issue_values(issue)),
+                )?;
+                for label in &issue.labels {
+                    tx.execute(
+                        "INSERT INTO labels (issue_id, label) VALUES (?1, ?2)",
+                        params![&issue.id, label],
+                    )?;
+                }
+                for dep in &issue.dependencies {
+                    tx.execute(
+                        "INSERT INTO dependencies (issue_id, depends_on_id, type, created_at, created_by) VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![&dep.issue_id, &dep.depends_on_id, &dep.type_, dep.created_at.to_rfc3339(), &dep.created_by],
+                    )?;
+                }
+                for comment in &issue.comments {
+                    tx.execute(
+                        "INSERT INTO comments (id, issue_id, author, text, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![comment.id, &issue.id, &comment.author, &comment.text, comment.created_at.to_rfc3339()],
+                    )?;
+                }
+                tx.execute(
+                    "INSERT OR IGNORE INTO dirty_issues (issue_id) VALUES (?1)",
+                    params![&issue.id],
+                )?;
+                if old_id != &issue.id {
+                    tx.execute(
+                        "UPDATE child_counters SET parent_id = ?2 WHERE parent_id = ?1",
+                        params![old_id, &issue.id],
+                    )?;
+                }
+            }
+            tx.execute(
+                "INSERT OR REPLACE INTO config (key, value) VALUES ('issue_id_prefix', ?1)",
+                params![&new_prefix],
+            )?;
+            tx.commit()?;
+            Ok(moves)
+        }
+
+        fn search(&self, query: &str, filter: &IssueFilter) -> Result<Vec<SearchResult>> {
+            let fts_query = fts_query(query);
+            if fts_query.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let (conditions, mut args) = filter_conditions(filter);
+            args.insert(0, Box::new(fts_query));
+            let mut sql = format!(
+                "SELECT {}, bm25(issues_fts), snippet(issues_fts, -1, '[', ']', '…', 12)
+                 FROM issues_fts JOIN issues ON issues.id = issues_fts.id
+                 WHERE issues_fts MATCH ?",
+                qualified_issue_columns()
+            );
+            for condition in &conditions {
+                sql.push_str(" AND ");
+                sql.push_str(condition);
+            }
+            sql.push_str(" ORDER BY bm25(issues_fts), issues.priority, issues.created_at DESC");
+            push_limit_offset(&mut sql, &mut args, filter);
+
+            let mut stmt = self.conn.prepare(&sql)?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(args.iter()), |row| {
+                Ok(SearchResult {
+                    issue: issue_from_row(row)?,
+                    rank: row.get(30)?,
+                    snippet: row.get(31)?,
+                })
+            })?;
+
+            let labels = self.get_all_labels(false)?;
+            let mut results = Vec::new();
+            for row in rows {
+                let mut result = row?;
+                result.issue.labels = labels.get(&result.issue.id).cloned().unwrap_or_default();
+                results.push(result);
+            }
+            Ok(results)
+        }
+    }
+
+    /// `ISSUE_COLUMNS` prefixed with the table name, for queries that join.
+    fn qualified_issue_columns() -> String {
+        ISSUE_COLUMNS
+            .split(',')
+            .map(|c| format!("issues.{}", c.trim()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Compiles the matching part of `filter` into `WHERE` conditions over
+    /// `issues`, with one positional parameter per `?`.
+    fn filter_conditions(filter: &IssueFilter) -> (Vec<String>, Vec<Box<dyn rusqlite::ToSql>>) {
+        let mut conditions = Vec::new();
+        let mut args: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        let placeholders = |n: usize| vec!["?"; n].join(", ");
+
+        if filter.statuses.is_empty() {
+            conditions.push(format!("issues.status != '{}'", STATUS_TOMBSTONE));
+        } else {
+            conditions.push(format!(
+                "issues.status IN ({})",
+                placeholders(filter.statuses.len())
+            ));
+            for status in &filter.statuses {
+                args.push(Box::new(status.clone()));
+            }
+        }
+        if !filter.exclude_statuses.is_empty() {
+            conditions.push(format!(
+                "issues.status NOT IN ({})",
+                placeholders(filter.exclude_statuses.len())
+            ));
+            for status in &filter.exclude_statuses {
+                args.push(Box::new(status.clone()));
+            }
+        }
+
+        match filter.assignee.as_deref() {
+            Some("unassigned") => {
+                conditions.push("(issues.assignee IS NULL OR issues.assignee = '')".to_string())
+            }
+            Some(assignee) => {
+                conditions.push("issues.assignee = ?".to_string());
+                args.push(Box::new(assignee.to_string()));
+            }
+            None => {}
+        }
+        if let Some(issue_type) = &filter.issue_type {
+            conditions.push("issues.issue_type = ?".to_string());
+            args.push(Box::new(issue_type.clone()));
+        }
+        if !filter.exclude_types.is_empty() {
+            conditions.push(format!(
+                "issues.issue_type NOT IN ({})",
+                placeholders(filter.exclude_types.len())
+            ));
+            for issue_type in &filter.exclude_types {
+                args.push(Box::new(issue_type.clone()));
+            }
+        }
+
+        for label in &filter.labels {
+            conditions
+                .push("issues.id IN (SELECT issue_id FROM labels WHERE label = ?)".to_string());
+            args.push(Box::new(label.clone()));
+        }
+        if !filter.labels_any.is_empty() {
+            conditions.push(format!(
+                "issues.id IN (SELECT issue_id FROM labels WHERE label IN ({}))",
+                placeholders(filter.labels_any.len())
+            ));
+            for label in &filter.labels_any {
+                args.push(Box::new(label.clone()));
+            }
+        }
+
+        if let Some(min) = filter.priority_min {
+            conditions.push("issues.priority >= ?".to_string());
+            args.push(Box::new(min));
+        }
+        if let Some(max) = filter.priority_max {
+            conditions.push("issues.priority <= ?".to_string());
+            args.push(Box::new(max));
+        }
+
+        let date_bounds = [
+            ("created_at", ">=", filter.created_after),
+            ("created_at", "<=", filter.created_before),
+            ("updated_at", ">=", filter.updated_after),
+            ("updated_at", "<=", filter.updated_before),
+            ("closed_at", ">=", filter.closed_after),
+            ("closed_at", "<=", filter.closed_before),
+        ];
+        for (column, op, bound) in date_bounds {
+            if let Some(bound) = bound {
+                conditions.push(format!("julianday(issues.{}) {} julianday(?)", column, op));
+                args.push(Box::new(bound.to_rfc3339()));
+            }
+        }
+
+        if let Some(prefix) = &filter.id_prefix {
+            conditions.push("substr(issues.id, 1, length(?)) = ?".to_string());
+            args.push(Box::new(prefix.clone()));
+            args.push(Box::new(prefix.clone()));
+        }
+        if let Some(text) = &filter.text {
+            conditions.push(
+                "(instr(lower(issues.title), lower(?)) > 0 OR instr(lower(issues.description), lower(?)) > 0)"
+                    .to_string(),
+            );
+            args.push(Box::new(text.clone()));
+            args.push(Box::new(text.clone()));
+        }
+
+        (conditions, args)
+    }
+
+    /// `ORDER BY` terms matching `IssueFilter::sort_and_page`.
+    fn order_clause(filter: &IssueFilter) -> String {
+        let terms: &[(&str, bool)] = match filter.sort {
+            SortField::Created => &[("julianday(issues.created_at)", false)],
+            SortField::Updated => &[("julianday(issues.updated_at)", false)],
+            SortField::Closed => &[("julianday(issues.closed_at)", false)],
+            SortField::Priority => &[
+                ("issues.priority", true),
+                ("julianday(issues.created_at)", false),
+            ],
+            SortField::Id => &[],
+            SortField::Title => &[("issues.title", true)],
+        };
+        terms
+            .iter()
+            .chain(&[("issues.id", true)])
+            .map(|(term, ascending)| {
+                let direction = if *ascending != filter.reverse {
+                    "ASC"
+                } else {
+                    "DESC"
+                };
+                format!("{} {}", term, direction)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn push_limit_offset(
+        sql: &mut String,
+        args: &mut Vec<Box<dyn rusqlite::ToSql>>,
+        filter: &IssueFilter,
+    ) {
+        if filter.limit.is_some() || filter.offset > 0 {
+            // SQLite treats a negative limit as "no limit"
+            sql.push_str(" LIMIT ? OFFSET ?");
+            args.push(Box::new(filter.limit.map_or(-1, |l| l as i64)));
+            args.push(Box::new(filter.offset as i64));
+        }
+    }
+
+    /// Turns free text into an FTS5 query: every word must match, as a prefix,
+    /// and punctuation such as the dash in issue IDs is taken literally.
+    fn fts_query(query: &str) -> String {
+        query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    const ISSUE_COLUMNS: &str = "
+        id, content_hash, title, description, design, acceptance_criteria, notes,
+        status, priority, issue_type, assignee, estimated_minutes,
+        created_at, updated_at, closed_at, external_ref,
+        compaction_level, compacted_at, compacted_at_commit, original_size,
+        sender, ephemeral, replies_to, relates_to, duplicate_of, superseded_by,
+        deleted_at, deleted_by, delete_reason, original_type";
+
+    fn issue_column_names() -> impl Iterator<Item = &'static str> {
+        ISSUE_COLUMNS.split(',').map(str::trim)
+    }
+
+    /// `INSERT` of every `ISSUE_COLUMNS` value; with `upsert`, an existing
+    /// issue with the same ID is overwritten in place.
+    fn insert_issue_sql(upsert: bool) -> String {
+        let columns: Vec<&str> = issue_column_names().collect();
+        let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+        let mut sql = format!(
+            "INSERT INTO issues ({}) VALUES ({})",
+            columns.join(", "),
+            placeholders.join(", ")
+        );
+        if upsert {
+            let assignments: Vec<String> = columns[1..]
+                .iter()
+                .map(|c| format!("{} = excluded.{}", c, c))
+                .collect();
+            sql.push_str(" ON CONFLICT(id) DO UPDATE SET ");
+            sql.push_str(&assignments.join(", "));
+        }
+        sql
+    }
+
+    /// `UPDATE` of every column but `id`, bound with `issue_values`.
+    fn update_issue_sql() -> String {
+        let assignments: Vec<String> = issue_column_names()
+            .enumerate()
+            .skip(1)
+            .map(|(i, c)| format!("{} = ?{}", c, i + 1))
+            .collect();
+        format!("UPDATE issues SET {} WHERE id = ?1", assignments.join(", "))
+    }
+
+    /// `WHERE` clause limiting a query to dirty issues, keyed on `column`.
+    fn dirty_scope(column: &str, dirty_only: bool) -> String {
+        if dirty_only {
+            format!(" WHERE {} IN (SELECT issue_id FROM dirty_issues)", column)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Recomputes the stored content hash of `id` after a partial update.
+    fn refresh_content_hash(conn: &Connection, id: &str) -> Result<()> {
+        let sql = format!("SELECT {} FROM issues WHERE id = ?1", ISSUE_COLUMNS);
+        let issue = conn.query_row(&sql, [id], issue_from_row)?;
+        conn.execute(
+            "UPDATE issues SET content_hash = ?2 WHERE id = ?1",
+            params![id, issue.compute_content_hash()],
+        )?;
+        Ok(())
+    }
+
+    /// Values for `ISSUE_COLUMNS`, in the same order. The content hash is
+    /// always recomputed rather than trusted from `issue`.
+    fn issue_values(issue: &Issue) -> Vec<Box<dyn rusqlite::ToSql>> {
+        vec![
+            Box::new(issue.id.clone()),
+            Box::new(issue.compute_content_hash()),
+            Box::new(issue.title.clone()),
+            Box::new(issue.description.clone()),
+            Box::new(issue.design.clone()),
+            Box::new(issue.acceptance_criteria.clone()),
+            Box::new(issue.notes.clone()),
+            Box::new(issue.status.clone()),
+            Box::new(issue.priority),
+            Box::new(issue.issue_type.clone()),
+            Box::new(issue.assignee.clone()),
+            Box::new(issue.estimated_minutes),
+            Box::new(issue.created_at.to_rfc3339()),
+            Box::new(issue.updated_at.to_rfc3339()),
+            Box::new(issue.closed_at.map(|t| t.to_rfc3339())),
+            Box::new(issue.external_ref.clone()),
+            Box::new(issue.compaction_level),
+            Box::new(issue.compacted_at.map(|t| t.to_rfc3339())),
+            Box::new(issue.compacted_at_commit.clone()),
+            Box::new(issue.original_size),
+            Box::new(issue.sender.clone()),
+            Box::new(issue.ephemeral),
+            Box::new(issue.replies_to.clone()),
+            Box::new(serde_json::to_string(&issue.relates_to).unwrap_or_default()),
+            Box::new(issue.duplicate_of.clone()),
+            Box::new(issue.superseded_by.clone()),
+            Box::new(issue.deleted_at.map(|t| t.to_rfc3339())),
+            Box::new(issue.deleted_by.clone()),
+            Box::new(issue.delete_reason.clone()),
+            Box::new(issue.original_type.clone()),
+        ]
+    }
+
+    /// Maps a row selected with `ISSUE_COLUMNS` to an issue without labels,
+    /// dependencies or comments.
+    fn issue_from_row(row: &rusqlite::Row) -> rusqlite::Result<Issue> {
+        let created_at_s: String = row.get(12)?;
+        let updated_at_s: String = row.get(13)?;
+        let closed_at_s: Option<String> = row.get(14)?;
+        let compacted_at_s: Option<String> = row.get(17)?;
+        let deleted_at_s: Option<String> = row.get(26)?;
+
+        let created_at = parse_timestamp(&created_at_s).unwrap_or_else(Utc::now);
+        let updated_at = parse_timestamp(&updated_at_s).unwrap_or_else(Utc::now);
+        let closed_at = closed_at_s.and_then(|s| parse_timestamp(&s));
+        let compacted_at = compacted_at_s.and_then(|s| parse_timestamp(&s));
+        let deleted_at = deleted_at_s.and_then(|s| parse_timestamp(&s));
+
+        let relates_to_s: String = row.get(23).unwrap_or_default();
+        let relates_to = if relates_to_s.is_empty() {
+            Vec::new()
+        } else {
+            serde_json::from_str(&relates_to_s).unwrap_or_default()
+        };
+
+        Ok(Issue {
+            id: row.get(0)?,
+            content_hash: row.get(1).unwrap_or_default(),
+            title: row.get(2).unwrap_or_default(),
+            description: row.get(3).unwrap_or_default(),
+            design: row.get(4).unwrap_or_default(),
+            acceptance_criteria: row.get(5).unwrap_or_default(),
+            notes: row.get(6).unwrap_or_default(),
+            status: row.get(7).unwrap_or_default(),
+            priority: row.get(8).unwrap_or_default(),
+            issue_type: row.get(9).unwrap_or_default(),
+            assignee: row.get(10)?,
+            estimated_minutes: row.get(11)?,
+            created_at,
+            updated_at,
+            closed_at,
+            external_ref: row.get(15)?,
+            compaction_level: row.get(16).unwrap_or_default(),
+            compacted_at,
+            compacted_at_commit: row.get(18)?,
+            original_size: row.get(19).unwrap_or_default(),
+            sender: row.get(20).unwrap_or_default(),
+            ephemeral: row.get(21).unwrap_or(false),
+            replies_to: row.get(22).unwrap_or_default(),
+            relates_to,
+            duplicate_of: row.get(24).unwrap_or_default(),
+            superseded_by: row.get(25).unwrap_or_default(),
+            deleted_at,
+            deleted_by: row.get(27).unwrap_or_default(),
+            delete_reason: row.get(28).unwrap_or_default(),
+            original_type: row.get(29).unwrap_or_default(),
+            labels: Vec::new(),
+            dependencies: Vec::new(),
+            comments: Vec::new(),
+        })
+    }
+
+    /// Maps a `id, issue_id, author, text, created_at` row to a comment.
+    fn comment_from_row(row: &rusqlite::Row) -> rusqlite::Result<Comment> {
+        let created_at_s: String = row.get(4)?;
+        Ok(Comment {
+            id: row.get(0)?,
+            issue_id: row.get(1)?,
+            author: row.get(2)?,
+            text: row.get(3)?,
+            created_at: parse_timestamp(&created_at_s).unwrap_or_else(Utc::now),
+        })
     }
 
     fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
@@ -1,9 +1,65 @@
 use crate::fs::FileSystem;
-use crate::merge::merge3way;
+use crate::merge::{merge3way, MergeReport, Side};
 use crate::{GitOps, Store};
 use anyhow::{bail, Context, Result};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+/// Environment variable naming the branch `bd sync` commits issues to.
+pub const SYNC_BRANCH_ENV: &str = "BEADS_SYNC_BRANCH";
+/// Config key naming the branch `bd sync` commits issues to.
+pub const SYNC_BRANCH_KEY: &str = "sync.branch";
+/// The same setting in `.beads/config.yaml`.
+const CONFIG_YAML_SYNC_BRANCH: &str = "sync-branch";
+
+/// The branch to sync issues through instead of the current one:
+/// `$BEADS_SYNC_BRANCH`, the `sync.branch` config key, or `sync-branch` in
+/// `config.yaml` in `beads_dir`. `None` syncs on the current branch.
+pub fn sync_branch(
+    store: &impl Store,
+    fs: &impl FileSystem,
+    beads_dir: &Path,
+) -> Result<Option<String>> {
+    if let Some(env) = std::env::var(SYNC_BRANCH_ENV)
+        .ok()
+        .filter(|e| !e.is_empty())
+    {
+        return Ok(Some(env));
+    }
+    if let Some(branch) = store.get_config(SYNC_BRANCH_KEY)?.filter(|b| !b.is_empty()) {
+        return Ok(Some(branch));
+    }
+    let config_path = beads_dir.join("config.yaml");
+    if !fs.exists(&config_path) {
+        return Ok(None);
+    }
+    let config: serde_yaml::Value = serde_yaml::from_str(&fs.read_to_string(&config_path)?)
+        .with_context(|| format!("Invalid {:?}", config_path))?;
+    Ok(config
+        .get(CONFIG_YAML_SYNC_BRANCH)
+        .and_then(serde_yaml::Value::as_str)
+        .filter(|b| !b.is_empty())
+        .map(str::to_string))
+}
+
+/// The worktree the sync branch is checked out in:
+/// `beads-worktrees/<branch>/` in the repository's common git directory.
+pub fn worktree_path(git_dir: &Path, branch: &str) -> PathBuf {
+    git_dir.join("beads-worktrees").join(branch)
+}
+
+/// The JSONL as of the last sync through `branch`, the base its next sync
+/// merges the branch's issues and this database's against. It sits beside
+/// the worktree as `<branch>.base.jsonl`.
+pub fn base_snapshot_path(git_dir: &Path, branch: &str) -> PathBuf {
+    git_dir
+        .join("beads-worktrees")
+        .join(format!("{}.base.jsonl", branch))
+}
+
+/// Exports, commits, pulls and pushes the JSONL, then imports it. Returns
+/// what merging decided: about the pull if it conflicted on the JSONL, and,
+/// through a sync branch, about the branch's changes merged into this
+/// database's.
 pub fn run_sync(
     store: &mut impl Store,
     git: &impl GitOps,
@@ -12,7 +68,7 @@ pub fn run_sync(
     fs: &impl FileSystem,
     squash: bool,
     dry_run: bool,
-) -> Result<()> {
+) -> Result<Option<MergeReport>> {
     if dry_run {
         // 1. Export to temp file
         let temp_dir = std::env::temp_dir();
@@ -23,7 +79,7 @@ pub fn run_sync(
 
         tracing::info!("Dry run: Exported pending changes to {:?}", temp_jsonl);
         tracing::info!("Dry run: Git operations and import skipped.");
-        return Ok(());
+        return Ok(None);
     }
 
     // 1. Export
@@ -33,9 +89,91 @@ pub fn run_sync(
 
     if squash {
         tracing::info!("Squash mode: Changes exported to JSONL. Git operations skipped.");
-        return Ok(());
+        return Ok(None);
     }
 
+    let beads_dir = jsonl_path.parent().unwrap_or(Path::new("."));
+    let report = match sync_branch(store, fs, beads_dir)? {
+        Some(branch) => {
+            // Commit in the branch's worktree, leaving the current branch
+            // and working tree alone.
+            let rel_path = PathBuf::from(repo_path(jsonl_path, git_root)?);
+            let sparse_dir = rel_path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .context("A sync branch needs the JSONL in a directory such as .beads")?;
+            let git_dir = git.common_dir()?;
+            let worktree_root = worktree_path(&git_dir, &branch);
+            let worktree = git
+                .worktree(&worktree_root, &branch, sparse_dir)
+                .with_context(|| format!("Could not set up worktree for branch {}", branch))?;
+            let worktree_jsonl = worktree_root.join(&rel_path);
+            let base_jsonl = base_snapshot_path(&git_dir, &branch);
+            let mut adopted = None;
+            if fs.exists(&worktree_jsonl) {
+                // Merge in what the branch has (pushed from another clone,
+                // say) against what this clone last synced, so the copy
+                // below neither drops the branch's changes nor clobbers
+                // local edits made since.
+                if !fs.exists(&base_jsonl) {
+                    fs.write(&base_jsonl, b"")?;
+                }
+                let mut merged = merge3way(
+                    jsonl_path.to_str().unwrap(),
+                    base_jsonl.to_str().unwrap(),
+                    worktree_jsonl.to_str().unwrap(),
+                    jsonl_path.to_str().unwrap(),
+                    false,
+                    fs,
+                )
+                .context("Merge with sync branch failed")?;
+                // Local changes the branch didn't touch are no news.
+                merged.issues.retain(|issue| issue.involves(Side::Left));
+                adopted = Some(merged).filter(|merged| !merged.issues.is_empty());
+                store
+                    .import_from_jsonl(jsonl_path, fs)
+                    .context("Import from sync branch failed")?;
+            }
+            fs.write(&worktree_jsonl, fs.read_to_string(jsonl_path)?.as_bytes())?;
+            let report = commit_pull_push(&worktree, &worktree_root, &worktree_jsonl, fs)?;
+            // Bring the branch's issues, now including remote changes, back,
+            // and remember them as the base of the next sync's merge.
+            let synced = fs.read_to_string(&worktree_jsonl)?;
+            fs.write(jsonl_path, synced.as_bytes())?;
+            fs.write(&base_jsonl, synced.as_bytes())?;
+            match (adopted, report) {
+                (Some(mut adopted), Some(report)) => {
+                    adopted.issues.extend(report.issues);
+                    Some(adopted)
+                }
+                (adopted, report) => report.or(adopted),
+            }
+        }
+        None => commit_pull_push(git, git_root, jsonl_path, fs)?,
+    };
+
+    // 6. Import changes from JSONL back to DB
+    let stats = store
+        .import_from_jsonl(jsonl_path, fs)
+        .context("Import failed")?;
+    tracing::info!(
+        "Imported {} created, {} updated, {} unchanged",
+        stats.created,
+        stats.updated,
+        stats.unchanged
+    );
+
+    Ok(report)
+}
+
+/// Commits `jsonl_path`, rebases onto the remote resolving conflicts in it
+/// with `merge3way`, and pushes. Returns the merge's report if there was one.
+fn commit_pull_push(
+    git: &impl GitOps,
+    git_root: &Path,
+    jsonl_path: &Path,
+    fs: &impl FileSystem,
+) -> Result<Option<MergeReport>> {
     // 2. Git Add
     git.add(jsonl_path).context("Git add failed")?;
 
@@ -46,30 +184,14 @@ pub fn run_sync(
         .context("Git commit failed")?;
 
     // 4. Pull Rebase
+    let mut report = None;
     if git.has_remote()? {
         if let Err(e) = git.pull_rebase() {
             // Check for conflict
             let status = git.status()?;
 
             // Calculate relative path for git show
-            let rel_path = match jsonl_path.strip_prefix(git_root) {
-                // Git expects forward slashes even on Windows
-                Ok(p) => p.to_string_lossy().replace('\\', "/"),
-                Err(_) => {
-                    // If jsonl_path is not under git_root, we can't easily resolve the git path.
-                    // Assuming jsonl_path is absolute or relative to CWD, and git_root is also.
-                    // If they are separate trees, we have a problem.
-                    // Let's try to canonicalize both first?
-                    // For now, if strip_prefix fails, we assume the user provided path is what git knows
-                    // if they are running from git root.
-                    // But safer is to bail or try just the filename if it's in root.
-                    bail!(
-                        "Could not determine relative path of {:?} to git root {:?}",
-                        jsonl_path,
-                        git_root
-                    );
-                }
-            };
+            let rel_path = repo_path(jsonl_path, git_root)?;
 
             // Check if issues.jsonl is in conflict (UU = both modified)
             if status.contains("UU") && status.contains(&rel_path) {
@@ -91,14 +213,14 @@ pub fn run_sync(
 
                 // Run merge
                 // Output directly to jsonl_path (overwriting conflict markers)
-                merge3way(
+                report = Some(merge3way(
                     jsonl_path.to_str().unwrap(),
                     base_path.to_str().unwrap(),
                     left_path.to_str().unwrap(),
                     right_path.to_str().unwrap(),
                     false,
                     fs,
-                )?;
+                )?);
 
                 // Add and continue
                 git.add(jsonl_path)?;
@@ -117,10 +239,24 @@ pub fn run_sync(
         git.push().context("Git push failed")?;
     }
 
-    // 6. Import changes from JSONL back to DB
-    store
-        .import_from_jsonl(jsonl_path, fs)
-        .context("Import failed")?;
+    Ok(report)
+}
 
-    Ok(())
+/// `jsonl_path` relative to `git_root`, as git names it.
+fn repo_path(jsonl_path: &Path, git_root: &Path) -> Result<String> {
+    match jsonl_path.strip_prefix(git_root) {
+        // Git expects forward slashes even on Windows
+        Ok(p) => Ok(p.to_string_lossy().replace('\\', "/")),
+        Err(_) => {
+            // If jsonl_path is not under git_root, we can't easily resolve the git path.
+            // Assuming jsonl_path is absolute or relative to CWD, and git_root is also.
+            // If they are separate trees, we have a problem.
+            // But safer is to bail or try just the filename if it's in root.
+            bail!(
+                "Could not determine relative path of {:?} to git root {:?}",
+                jsonl_path,
+                git_root
+            );
+        }
+    }
 }
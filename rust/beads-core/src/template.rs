@@ -0,0 +1,345 @@
+//! Issue templates (`bd template`, `bd create --from-template`).
+//!
+//! Templates are YAML files, or markdown files with YAML frontmatter, in
+//! `.beads/templates/`. Custom templates override the built-in `bug`,
+//! `epic` and `feature` templates of the same name.
+
+use crate::fs::FileSystem;
+use crate::graph::DEP_PARENT_CHILD;
+use crate::models::{Dependency, Issue};
+use crate::store::MAX_HIERARCHY_DEPTH;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Placeholder in title patterns for the title given on the command line.
+pub const TITLE_PLACEHOLDER: &str = "{title}";
+
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("bug", include_str!("../templates/bug.yaml")),
+    ("epic", include_str!("../templates/epic.yaml")),
+    ("feature", include_str!("../templates/feature.yaml")),
+];
+
+/// Extensions tried, in order, when loading a custom template by name.
+const EXTENSIONS: &[&str] = &["yaml", "yml", "md"];
+
+/// Markdown headings that start the design and acceptance criteria sections
+/// of a markdown template; everything before them is the description.
+const DESIGN_HEADING: &str = "# Design";
+const ACCEPTANCE_HEADING: &str = "# Acceptance Criteria";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Template {
+    /// File name without the extension; set when loading.
+    #[serde(default)]
+    pub name: String,
+    /// Title pattern; `{title}` is replaced with the given title.
+    #[serde(default = "default_title", skip_serializing_if = "is_default_title")]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(rename = "type", default = "default_type")]
+    pub issue_type: String,
+    #[serde(default = "default_priority")]
+    pub priority: i32,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub design: String,
+    #[serde(default)]
+    pub acceptance_criteria: String,
+    /// Issues created as `parent-child` children of the templated issue.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ChildTemplate>,
+    /// Whether this is one of bd's own templates.
+    #[serde(skip)]
+    pub builtin: bool,
+}
+
+/// A child issue created along with its template's issue.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ChildTemplate {
+    /// Title pattern; `{title}` is replaced with the parent's given title.
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(rename = "type", default = "default_child_type")]
+    pub issue_type: String,
+    /// Defaults to the parent's priority.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+}
+
+fn default_title() -> String {
+    TITLE_PLACEHOLDER.to_string()
+}
+
+fn is_default_title(title: &String) -> bool {
+    title == TITLE_PLACEHOLDER
+}
+
+fn default_type() -> String {
+    "task".to_string()
+}
+
+fn default_child_type() -> String {
+    "task".to_string()
+}
+
+fn default_priority() -> i32 {
+    2
+}
+
+impl Template {
+    /// Skeleton written by `bd template create`, as in the Go bd.
+    pub fn skeleton(name: &str) -> Self {
+        Template {
+            name: name.to_string(),
+            title: default_title(),
+            description: "[Describe the issue]\n\n## Additional Context\n\n[Add relevant details]"
+                .to_string(),
+            issue_type: default_type(),
+            priority: default_priority(),
+            labels: Vec::new(),
+            design: "[Design notes]".to_string(),
+            acceptance_criteria: "- [ ] Acceptance criterion 1\n- [ ] Acceptance criterion 2"
+                .to_string(),
+            children: Vec::new(),
+            builtin: false,
+        }
+    }
+
+    /// Parses a YAML template, or a markdown one if `markdown` is set.
+    pub fn parse(name: &str, contents: &str, markdown: bool) -> Result<Self> {
+        let mut template: Template = if markdown {
+            parse_markdown(contents)?
+        } else {
+            serde_yaml::from_str(contents)?
+        };
+        template.name = name.to_string();
+        Ok(template)
+    }
+
+    /// The template as the YAML `bd template create` writes.
+    pub fn to_yaml(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// The issue this template makes for `title`, with ID `id`, followed by
+    /// its children as `id.1`, `id.2`, ... linked to it by `parent-child`
+    /// dependencies. `id` must be new, so that none of those IDs are taken.
+    pub fn instantiate(
+        &self,
+        id: &str,
+        title: &str,
+        actor: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Issue>> {
+        if !self.children.is_empty() && id.matches('.').count() >= MAX_HIERARCHY_DEPTH {
+            anyhow::bail!(
+                "maximum hierarchy depth ({}) exceeded for the children of {}",
+                MAX_HIERARCHY_DEPTH,
+                id
+            );
+        }
+        let new_issue = |id: String, title: String, issue_type: &str, priority: i32| Issue {
+            id,
+            title,
+            status: "open".to_string(),
+            issue_type: issue_type.to_string(),
+            priority,
+            created_at: now,
+            updated_at: now,
+            ..Default::default()
+        };
+
+        let mut issue = new_issue(
+            id.to_string(),
+            expand_title(&self.title, title),
+            &self.issue_type,
+            self.priority,
+        );
+        issue.description = self.description.clone();
+        issue.design = self.design.clone();
+        issue.acceptance_criteria = self.acceptance_criteria.clone();
+        issue.labels = self.labels.clone();
+
+        let mut issues = vec![issue];
+        for (n, child) in self.children.iter().enumerate() {
+            let child_id = format!("{}.{}", id, n + 1);
+            let mut issue = new_issue(
+                child_id.clone(),
+                expand_title(&child.title, title),
+                &child.issue_type,
+                child.priority.unwrap_or(self.priority),
+            );
+            issue.description = child.description.clone();
+            issue.labels = child.labels.clone();
+            issue.dependencies.push(Dependency {
+                issue_id: child_id,
+                depends_on_id: id.to_string(),
+                type_: DEP_PARENT_CHILD.to_string(),
+                created_at: now,
+                created_by: actor.to_string(),
+            });
+            issues.push(issue);
+        }
+        Ok(issues)
+    }
+}
+
+/// Fails unless `name` can name a template file: no path separators or `..`.
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") || name.starts_with('.')
+    {
+        anyhow::bail!("invalid template name '{}'", name);
+    }
+    Ok(())
+}
+
+/// The built-in template `name`, if there is one.
+pub fn builtin(name: &str) -> Option<Template> {
+    let (_, contents) = BUILTIN_TEMPLATES.iter().find(|(n, _)| *n == name)?;
+    let mut template = Template::parse(name, contents, false).expect("built-in templates parse");
+    template.builtin = true;
+    Some(template)
+}
+
+/// Template `name` from `dir`, or the built-in one.
+pub fn load(dir: &Path, name: &str, fs: &dyn FileSystem) -> Result<Template> {
+    validate_name(name)?;
+    for ext in EXTENSIONS {
+        let path = dir.join(format!("{}.{}", name, ext));
+        if fs.exists(&path) {
+            let contents = fs.read_to_string(&path)?;
+            return Template::parse(name, &contents, *ext == "md")
+                .with_context(|| format!("invalid template {}", path.display()));
+        }
+    }
+    builtin(name).with_context(|| format!("template '{}' not found", name))
+}
+
+/// Every template, built-in first and then those in `dir`, each sorted by
+/// name. A custom template replaces the built-in one of the same name;
+/// files that do not parse are skipped with a warning.
+pub fn list(dir: &Path, fs: &dyn FileSystem) -> Result<Vec<Template>> {
+    let mut custom = Vec::new();
+    if fs.exists(dir) {
+        for file in fs.list_dir(dir)? {
+            let Some((name, ext)) = file.rsplit_once('.') else {
+                continue;
+            };
+            if !EXTENSIONS.contains(&ext) || custom.iter().any(|t: &Template| t.name == name) {
+                continue;
+            }
+            match load(dir, name, fs) {
+                Ok(template) => custom.push(template),
+                Err(e) => tracing::warn!("skipping template {}: {:#}", file, e),
+            }
+        }
+    }
+
+    let mut templates: Vec<Template> = BUILTIN_TEMPLATES
+        .iter()
+        .filter(|(name, _)| !custom.iter().any(|t| t.name == *name))
+        .filter_map(|(name, _)| builtin(name))
+        .collect();
+    templates.extend(custom);
+    Ok(templates)
+}
+
+fn expand_title(pattern: &str, title: &str) -> String {
+    pattern.replace(TITLE_PLACEHOLDER, title)
+}
+
+/// A markdown template: YAML frontmatter for everything but the text, then
+/// the description, with optional `# Design` and `# Acceptance Criteria`
+/// sections.
+fn parse_markdown(contents: &str) -> Result<Template> {
+    let rest = contents
+        .strip_prefix("---")
+        .context("markdown template must start with --- frontmatter")?;
+    let (frontmatter, body) = rest
+        .split_once("\n---")
+        .context("markdown template frontmatter is not closed with ---")?;
+    let mut template: Template = if frontmatter.trim().is_empty() {
+        serde_yaml::from_str("{}")?
+    } else {
+        serde_yaml::from_str(frontmatter)?
+    };
+
+    // Description, design and acceptance criteria, in that order
+    let mut sections = [String::new(), String::new(), String::new()];
+    let mut current = 0;
+    for line in body.lines().skip(1) {
+        match line.trim_end() {
+            DESIGN_HEADING => current = 1,
+            ACCEPTANCE_HEADING => current = 2,
+            _ => {
+                sections[current].push_str(line);
+                sections[current].push('\n');
+            }
+        }
+    }
+    let [description, design, acceptance_criteria] = sections.map(|s| s.trim().to_string());
+    template.description = description;
+    template.design = design;
+    template.acceptance_criteria = acceptance_criteria;
+    Ok(template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_templates() {
+        for (name, _) in BUILTIN_TEMPLATES {
+            let template = builtin(name).unwrap();
+            assert_eq!(template.name, *name);
+            assert!(template.builtin);
+            assert!(!template.description.is_empty());
+        }
+        assert_eq!(builtin("bug").unwrap().priority, 1);
+        assert_eq!(builtin("epic").unwrap().children.len(), 3);
+        assert!(builtin("chore").is_none());
+    }
+
+    #[test]
+    fn test_markdown_template() -> Result<()> {
+        let template = Template::parse(
+            "spike",
+            "---\ntype: chore\nlabels: [research]\ntitle: \"Spike: {title}\"\n---\n\
+             ## Question\n\n[What do we need to learn?]\n\n\
+             # Design\nTime-box to two days.\n\n\
+             # Acceptance Criteria\n- [ ] Findings written up\n",
+            true,
+        )?;
+        assert_eq!(template.name, "spike");
+        assert_eq!(template.issue_type, "chore");
+        assert_eq!(template.priority, 2);
+        assert_eq!(template.labels, ["research"]);
+        assert_eq!(
+            template.description,
+            "## Question\n\n[What do we need to learn?]"
+        );
+        assert_eq!(template.design, "Time-box to two days.");
+        assert_eq!(template.acceptance_criteria, "- [ ] Findings written up");
+        assert_eq!(expand_title(&template.title, "Caching"), "Spike: Caching");
+
+        assert!(Template::parse("bad", "no frontmatter", true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("security-audit").is_ok());
+        for bad in ["", "../bug", "a/b", "a\\b", ".hidden"] {
+            assert!(validate_name(bad).is_err(), "{:?} accepted", bad);
+        }
+    }
+}
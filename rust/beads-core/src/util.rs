@@ -1,3 +1,4 @@
+use anyhow::Result;
 use chrono::{DateTime, Utc};
 use sha2::{Digest, Sha256};
 
@@ -38,7 +39,7 @@ fn encode_base36(data: &[u8], length: usize) -> String {
     } else {
         0
     };
-    padded_chars.extend(std::iter::repeat('0').take(needed));
+    padded_chars.extend(std::iter::repeat_n('0', needed));
     padded_chars.extend(chars);
 
     // Truncate to exact length if needed (keep least significant digits / suffix)
@@ -98,6 +99,123 @@ pub fn generate_hash_id(
     format!("{}-{}", prefix, short_hash)
 }
 
+/// Longest hash the generator produces, whatever `max_hash_length` says.
+pub const MAX_HASH_ID_LENGTH: usize = 8;
+
+/// Config keys tuning `adaptive_id_length`.
+pub const CONFIG_MAX_COLLISION_PROB: &str = "max_collision_prob";
+pub const CONFIG_MIN_HASH_LENGTH: &str = "min_hash_length";
+pub const CONFIG_MAX_HASH_LENGTH: &str = "max_hash_length";
+
+/// How short hash IDs may be for a given number of issues.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveIdConfig {
+    /// Highest acceptable chance that two issues share an ID.
+    pub max_collision_probability: f64,
+    pub min_length: usize,
+    pub max_length: usize,
+}
+
+impl Default for AdaptiveIdConfig {
+    /// The Go bd's defaults: 3 characters up to ~160 issues, 4 up to ~980,
+    /// 5 up to ~5.9K and so on.
+    fn default() -> Self {
+        AdaptiveIdConfig {
+            max_collision_probability: 0.25,
+            min_length: 3,
+            max_length: 8,
+        }
+    }
+}
+
+impl AdaptiveIdConfig {
+    /// Reads the config keys through `get`, keeping defaults for values that
+    /// are unset or do not parse.
+    pub fn from_config(get: impl Fn(&str) -> Result<Option<String>>) -> Result<Self> {
+        let mut config = AdaptiveIdConfig::default();
+        if let Some(p) = parse_config_value(&get, CONFIG_MAX_COLLISION_PROB)? {
+            config.max_collision_probability = p;
+        }
+        if let Some(n) = parse_config_value(&get, CONFIG_MIN_HASH_LENGTH)? {
+            config.min_length = n;
+        }
+        if let Some(n) = parse_config_value(&get, CONFIG_MAX_HASH_LENGTH)? {
+            config.max_length = n;
+        }
+        Ok(config)
+    }
+}
+
+/// Reads `key` through `get` and parses it, warning about and ignoring a
+/// value that does not parse.
+pub(crate) fn parse_config_value<T: std::str::FromStr>(
+    get: &impl Fn(&str) -> Result<Option<String>>,
+    key: &str,
+) -> Result<Option<T>> {
+    let Some(value) = get(key)? else {
+        return Ok(None);
+    };
+    let parsed = value.trim().parse().ok();
+    if parsed.is_none() {
+        tracing::warn!("ignoring invalid {} '{}'", key, value);
+    }
+    Ok(parsed)
+}
+
+/// Birthday-paradox approximation of the chance that `num_issues` random
+/// base36 IDs of `id_length` characters contain a duplicate: 1 - e^(-n²/2N).
+pub fn collision_probability(num_issues: usize, id_length: usize) -> f64 {
+    let total = 36f64.powi(id_length as i32);
+    let n = num_issues as f64;
+    1.0 - (-(n * n) / (2.0 * total)).exp()
+}
+
+/// Shortest length within the configured bounds whose collision probability
+/// for `num_issues` stays under the threshold, or the maximum if none does.
+pub fn adaptive_id_length(num_issues: usize, config: &AdaptiveIdConfig) -> usize {
+    (config.min_length..=config.max_length)
+        .find(|&length| {
+            collision_probability(num_issues, length) <= config.max_collision_probability
+        })
+        .unwrap_or(config.max_length)
+}
+
+/// Finds a free ID for a new issue, starting at `base_length` and trying 10
+/// nonces at each length up to `MAX_HASH_ID_LENGTH`. `exists` reports
+/// whether a candidate is already taken.
+pub fn generate_unique_hash_id(
+    prefix: &str,
+    title: &str,
+    description: &str,
+    creator: &str,
+    created_at: DateTime<Utc>,
+    base_length: usize,
+    mut exists: impl FnMut(&str) -> Result<bool>,
+) -> Result<String> {
+    let base_length = base_length.min(MAX_HASH_ID_LENGTH);
+    for length in base_length..=MAX_HASH_ID_LENGTH {
+        for nonce in 0..10 {
+            let candidate = generate_hash_id(
+                prefix,
+                title,
+                description,
+                creator,
+                created_at,
+                length,
+                nonce,
+            );
+            if !exists(&candidate)? {
+                return Ok(candidate);
+            }
+        }
+    }
+    anyhow::bail!(
+        "failed to generate a unique ID after trying lengths {}-{} with 10 nonces each",
+        base_length,
+        MAX_HASH_ID_LENGTH
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,4 +257,44 @@ mod tests {
         let id4 = generate_hash_id("bd", "Title", "Desc", "User", date, 6, 1);
         assert_ne!(id1, id4);
     }
+
+    #[test]
+    fn test_collision_probability() {
+        // Reference values from the Go bd's adaptive length tests
+        for (issues, length, expected) in [
+            (50, 4, 0.0007),
+            (500, 4, 0.0717),
+            (1000, 5, 0.0082),
+            (1000, 6, 0.0002),
+        ] {
+            let got = collision_probability(issues, length);
+            assert!(
+                (got - expected).abs() <= expected * 0.2,
+                "P({}, {}) = {}, want ~{}",
+                issues,
+                length,
+                got,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_adaptive_id_length() {
+        let config = AdaptiveIdConfig::default();
+        assert_eq!(adaptive_id_length(0, &config), 3);
+        assert_eq!(adaptive_id_length(50, &config), 3);
+        assert_eq!(adaptive_id_length(500, &config), 4);
+        assert_eq!(adaptive_id_length(5_000, &config), 5);
+        assert_eq!(adaptive_id_length(30_000, &config), 6);
+        assert_eq!(adaptive_id_length(100_000_000, &config), 8);
+
+        let strict = AdaptiveIdConfig {
+            max_collision_probability: 0.01,
+            min_length: 4,
+            ..config
+        };
+        assert_eq!(adaptive_id_length(10, &strict), 4);
+        assert_eq!(adaptive_id_length(500, &strict), 5);
+    }
 }
@@ -2,7 +2,7 @@ use crate::fs::FileSystem;
 use crate::git::GitOps;
 use anyhow::{bail, Result};
 use std::io::{BufRead, Cursor, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use wasm_bindgen::prelude::*;
 
 // JS bindings for beads-core
@@ -26,6 +26,7 @@ extern "C" {
     fn git_show(revision: &str) -> String;
     fn git_rebase_continue() -> String;
     fn git_has_remote() -> bool;
+    fn git_head_commit() -> String;
 }
 
 /// FileSystem implementation that delegates to JavaScript.
@@ -202,4 +203,20 @@ impl GitOps for WasmGit {
     fn has_remote(&self) -> Result<bool> {
         Ok(git_has_remote())
     }
+
+    fn head_commit(&self) -> Result<Option<String>> {
+        let res = git_head_commit();
+        if res.starts_with("error:") {
+            bail!("{}", res);
+        }
+        Ok((!res.is_empty()).then_some(res))
+    }
+
+    fn common_dir(&self) -> Result<PathBuf> {
+        bail!("sync branches are not supported in the browser")
+    }
+
+    fn worktree(&self, _path: &Path, _branch: &str, _sparse_dir: &Path) -> Result<Box<dyn GitOps>> {
+        bail!("sync branches are not supported in the browser")
+    }
 }
@@ -35,6 +35,10 @@ fn test_export_to_jsonl() -> anyhow::Result<()> {
         updated_at: now,
         closed_at: None,
         external_ref: None,
+        compaction_level: 0,
+        compacted_at: None,
+        compacted_at_commit: None,
+        original_size: 0,
         sender: "".to_string(),
         ephemeral: false,
         replies_to: "".to_string(),
@@ -1,43 +1,438 @@
-use beads_core::git::{GitOps, StdGit};
+use beads_core::git::{configured, GitOps, StdGit, CONFIG_GIT_BACKEND};
+use beads_core::merge::{FieldResolution, MergeReport};
+use beads_core::sync::{base_snapshot_path, run_sync, worktree_path, SYNC_BRANCH_KEY};
+use beads_core::{GixGit, Issue, SqliteStore, StdFileSystem, Store};
+use chrono::Utc;
 use std::fs;
+use std::path::Path;
+use std::process::Command;
 use tempfile::tempdir;
 
+type Open = fn(&Path) -> Box<dyn GitOps>;
+
+const BACKENDS: [(&str, Open); 2] = [
+    ("cli", |root| Box::new(StdGit::new(root))),
+    ("gix", |root| Box::new(GixGit::new(root))),
+];
+
+/// Runs the git binary in `dir` to set up a fixture.
+fn git(dir: &Path, args: &[&str]) {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn set_identity(dir: &Path) {
+    git(dir, &["config", "user.name", "Test"]);
+    git(dir, &["config", "user.email", "test@example.com"]);
+}
+
 #[test]
 fn test_git_operations() {
-    let dir = tempdir().unwrap();
-    let root = dir.path();
-    let git = StdGit::new(root);
-
-    // Init
-    git.init().expect("git init failed");
-
-    // Create file
-    let file_path = root.join("test.txt");
-    fs::write(&file_path, "hello world").unwrap();
-
-    // Add
-    git.add(&file_path).expect("git add failed");
-
-    // Commit
-    git.commit("initial commit").expect("git commit failed");
-
-    // Status
-    let status = git.status().expect("git status failed");
-    assert!(status.is_empty(), "status should be clean");
-
-    // Verify commit exists by using show
-    // We don't know the hash, but we can look at HEAD
-    // But `git show` in git.rs takes a revision.
-    // Let's modify the file and see status change
-    fs::write(&file_path, "hello world 2").unwrap();
-    let status_dirty = git.status().expect("git status failed");
-    assert!(status_dirty.contains("test.txt"));
-
-    // Add and commit again
-    git.add(&file_path).expect("git add failed");
-    git.commit("second commit").expect("git commit failed");
-
-    // Show content of HEAD
-    let content = git.show("HEAD:test.txt").expect("git show failed");
-    assert_eq!(content, "hello world 2");
+    for (backend, open) in BACKENDS {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let git = open(root);
+
+        // Init
+        git.init().expect("git init failed");
+        set_identity(root);
+        assert_eq!(git.head_commit().unwrap(), None, "{}", backend);
+        assert!(!git.has_remote().unwrap(), "{}", backend);
+
+        // Create file
+        let file_path = root.join("test.txt");
+        fs::write(&file_path, "hello world").unwrap();
+        assert!(git.status().unwrap().contains("?? test.txt"), "{}", backend);
+
+        // Add
+        git.add(&file_path).expect("git add failed");
+
+        // Commit
+        git.commit("initial commit").expect("git commit failed");
+        let first = git.head_commit().unwrap();
+        assert!(first.is_some(), "{}", backend);
+
+        // Status
+        let status = git.status().expect("git status failed");
+        assert!(status.is_empty(), "{}: status should be clean", backend);
+
+        // Committing a clean tree is a no-op
+        git.commit("nothing").expect("git commit failed");
+        assert_eq!(git.head_commit().unwrap(), first, "{}", backend);
+
+        // Let's modify the file and see status change
+        fs::write(&file_path, "hello world 2").unwrap();
+        let status_dirty = git.status().expect("git status failed");
+        assert!(status_dirty.contains(" M test.txt"), "{}", backend);
+
+        // Add and commit again
+        git.add(&file_path).expect("git add failed");
+        git.commit("second commit").expect("git commit failed");
+
+        // Show content of HEAD
+        let content = git.show("HEAD:test.txt").expect("git show failed");
+        assert_eq!(content, "hello world 2", "{}", backend);
+        let content = git.show("HEAD~1:test.txt").expect("git show failed");
+        assert_eq!(content, "hello world", "{}", backend);
+    }
+}
+
+#[test]
+fn test_git_remote_conflict() {
+    for (backend, open) in BACKENDS {
+        let dir = tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        git(dir.path(), &["init", "--bare", "remote.git"]);
+        git(dir.path(), &["clone", "remote.git", "a"]);
+        set_identity(&a);
+        fs::write(a.join("issues.jsonl"), "base\n").unwrap();
+        git(&a, &["add", "issues.jsonl"]);
+        git(&a, &["commit", "-m", "base"]);
+        git(&a, &["push", "-u", "origin", "HEAD"]);
+        git(dir.path(), &["clone", "remote.git", "b"]);
+        set_identity(&b);
+        let (git_a, git_b) = (open(&a), open(&b));
+        assert!(git_b.has_remote().unwrap(), "{}", backend);
+
+        // Both sides change the same file; A pushes first.
+        fs::write(a.join("issues.jsonl"), "from a\n").unwrap();
+        git_a.add(&a.join("issues.jsonl")).unwrap();
+        git_a.commit("a").unwrap();
+        git_a.push().expect("push from a failed");
+
+        fs::write(b.join("notes.txt"), "notes\n").unwrap();
+        git_b.add(Path::new("notes.txt")).unwrap();
+        git_b.commit("notes").unwrap();
+        fs::write(b.join("issues.jsonl"), "from b\n").unwrap();
+        git_b.add(&b.join("issues.jsonl")).unwrap();
+        git_b.commit("b").unwrap();
+        assert!(git_b.push().is_err(), "{}: push must be rejected", backend);
+
+        // B's rebase stops with the three versions in the index.
+        assert!(
+            git_b.pull_rebase().is_err(),
+            "{}: expected conflict",
+            backend
+        );
+        let status = git_b.status().unwrap();
+        assert!(
+            status.contains("UU issues.jsonl"),
+            "{}: {}",
+            backend,
+            status
+        );
+        assert_eq!(
+            git_b.show(":1:issues.jsonl").unwrap(),
+            "base\n",
+            "{}",
+            backend
+        );
+        assert_eq!(
+            git_b.show(":2:issues.jsonl").unwrap(),
+            "from a\n",
+            "{}",
+            backend
+        );
+        assert_eq!(
+            git_b.show(":3:issues.jsonl").unwrap(),
+            "from b\n",
+            "{}",
+            backend
+        );
+        assert_eq!(fs::read_to_string(b.join("notes.txt")).unwrap(), "notes\n");
+
+        fs::write(b.join("issues.jsonl"), "from a\nfrom b\n").unwrap();
+        git_b.add(&b.join("issues.jsonl")).unwrap();
+        git_b.rebase_continue().expect("rebase --continue failed");
+        assert_eq!(git_b.status().unwrap(), "", "{}", backend);
+        assert_eq!(
+            git_b.show("HEAD~1:notes.txt").unwrap(),
+            "notes\n",
+            "{}",
+            backend
+        );
+        git_b.push().expect("push from b failed");
+
+        // A is behind: a local commit rebases cleanly on top of B's work.
+        fs::write(a.join("other.txt"), "other\n").unwrap();
+        git_a.add(&a.join("other.txt")).unwrap();
+        git_a.commit("other").unwrap();
+        assert!(git_a.push().is_err(), "{}: push must be rejected", backend);
+        git_a.pull_rebase().expect("pull from a failed");
+        assert_eq!(
+            fs::read_to_string(a.join("issues.jsonl")).unwrap(),
+            "from a\nfrom b\n",
+            "{}",
+            backend
+        );
+        assert!(a.join("notes.txt").exists(), "{}", backend);
+        git_a.push().expect("second push from a failed");
+
+        git_b.pull_rebase().expect("fast-forward of b failed");
+        assert_eq!(
+            git_a.head_commit().unwrap(),
+            git_b.head_commit().unwrap(),
+            "{}",
+            backend
+        );
+        assert_eq!(fs::read_to_string(b.join("other.txt")).unwrap(), "other\n");
+        assert_eq!(git_b.status().unwrap(), "", "{}", backend);
+        git(&remote, &["fsck", "--strict"]);
+    }
+}
+
+#[test]
+fn test_git_remote_merges_lines() {
+    for (backend, open) in BACKENDS {
+        let dir = tempdir().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        git(dir.path(), &["init", "--bare", "remote.git"]);
+        git(dir.path(), &["clone", "remote.git", "a"]);
+        set_identity(&a);
+        fs::write(
+            a.join("README.md"),
+            "# Title\n\nIntro.\n\n## Usage\n\nRun it.\n",
+        )
+        .unwrap();
+        git(&a, &["add", "README.md"]);
+        git(&a, &["commit", "-m", "base"]);
+        git(&a, &["push", "-u", "origin", "HEAD"]);
+        git(dir.path(), &["clone", "remote.git", "b"]);
+        set_identity(&b);
+        let (git_a, git_b) = (open(&a), open(&b));
+
+        // Both sides edit different lines of the same file.
+        fs::write(
+            a.join("README.md"),
+            "# New title\n\nIntro.\n\n## Usage\n\nRun it.\n",
+        )
+        .unwrap();
+        git_a.add(&a.join("README.md")).unwrap();
+        git_a.commit("a").unwrap();
+        git_a.push().expect("push from a failed");
+        fs::write(
+            b.join("README.md"),
+            "# Title\n\nIntro.\n\n## Usage\n\nRun it twice.\n",
+        )
+        .unwrap();
+        git_b.add(&b.join("README.md")).unwrap();
+        git_b.commit("b").unwrap();
+
+        git_b.pull_rebase().expect("pull from b failed");
+        assert_eq!(
+            fs::read_to_string(b.join("README.md")).unwrap(),
+            "# New title\n\nIntro.\n\n## Usage\n\nRun it twice.\n",
+            "{}",
+            backend
+        );
+        assert_eq!(git_b.status().unwrap(), "", "{}", backend);
+        git_b.push().expect("push from b failed");
+    }
+}
+
+fn sync(root: &Path, store: &mut SqliteStore, open: Open) -> anyhow::Result<Option<MergeReport>> {
+    let jsonl_path = root.join(".beads").join("issues.jsonl");
+    run_sync(
+        store,
+        &open(root),
+        root,
+        &jsonl_path,
+        &StdFileSystem,
+        false,
+        false,
+    )
+}
+
+fn new_issue(id: &str, title: &str) -> Issue {
+    Issue {
+        id: id.to_string(),
+        title: title.to_string(),
+        status: "open".to_string(),
+        priority: 2,
+        issue_type: "task".to_string(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_sync_branch() -> anyhow::Result<()> {
+    for (backend, open) in BACKENDS {
+        let dir = tempdir()?;
+        let remote = dir.path().join("remote.git");
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        git(dir.path(), &["init", "--bare", "remote.git"]);
+        git(dir.path(), &["clone", "remote.git", "a"]);
+        set_identity(&a);
+        fs::create_dir_all(a.join("src"))?;
+        fs::write(a.join("src").join("main.rs"), "fn main() {}\n")?;
+        fs::create_dir_all(a.join(".beads"))?;
+        fs::write(a.join(".beads").join(".gitignore"), "*.db*\n")?;
+        git(&a, &["add", "."]);
+        git(&a, &["commit", "-m", "init"]);
+        git(&a, &["push", "-u", "origin", "HEAD"]);
+        git(dir.path(), &["clone", "remote.git", "b"]);
+        set_identity(&b);
+
+        // A's first sync creates the branch and pushes it, leaving A's
+        // own branch and working tree alone.
+        let mut store_a = SqliteStore::open(a.join(".beads").join("beads.db"))?;
+        store_a.set_config(SYNC_BRANCH_KEY, "beads-sync")?;
+        store_a.create_issue(&new_issue("bd-a", "From A"))?;
+        let head_a = open(&a).head_commit()?;
+        sync(&a, &mut store_a, open)?;
+        assert_eq!(open(&a).head_commit()?, head_a, "{}", backend);
+        let status = open(&a).status()?;
+        assert_eq!(status, "?? .beads/issues.jsonl\n", "{}", backend);
+        let worktree = worktree_path(&a.join(".git"), "beads-sync");
+        assert!(worktree.join(".beads").join("issues.jsonl").exists());
+        assert!(!worktree.join("src").exists(), "{}: not sparse", backend);
+        git(&remote, &["rev-parse", "--verify", "refs/heads/beads-sync"]);
+
+        // B starts from the branch on the remote and picks up A's issue.
+        let mut store_b = SqliteStore::open(b.join(".beads").join("beads.db"))?;
+        store_b.set_config(SYNC_BRANCH_KEY, "beads-sync")?;
+        store_b.create_issue(&new_issue("bd-b", "From B"))?;
+        sync(&b, &mut store_b, open)?;
+        assert!(store_b.get_issue("bd-a")?.is_some(), "{}", backend);
+
+        // Both edit bd-a before syncing: the rebase in B's worktree
+        // conflicts and is merged.
+        let mut issue = store_a.get_issue("bd-a")?.unwrap();
+        issue.title = "Renamed by A".to_string();
+        issue.updated_at = Utc::now();
+        store_a.update_issue(&issue)?;
+        sync(&a, &mut store_a, open)?;
+        let mut issue = store_b.get_issue("bd-a")?.unwrap();
+        issue.description = "Described by B".to_string();
+        issue.updated_at = Utc::now();
+        store_b.update_issue(&issue)?;
+        let report = sync(&b, &mut store_b, open)?.expect("no merge");
+        let fields = &report.get("bd-a").unwrap().fields;
+        assert_eq!(fields["title"], FieldResolution::Left, "{}", backend);
+        assert_eq!(fields["description"], FieldResolution::Right, "{}", backend);
+        let merged = store_b.get_issue("bd-a")?.unwrap();
+        assert_eq!(merged.title, "Renamed by A", "{}", backend);
+        assert_eq!(merged.description, "Described by B", "{}", backend);
+
+        sync(&a, &mut store_a, open)?;
+        assert!(store_a.get_issue("bd-b")?.is_some(), "{}", backend);
+        assert_eq!(
+            store_a.get_issue("bd-a")?.unwrap().description,
+            "Described by B",
+            "{}",
+            backend
+        );
+        assert_eq!(open(&a).head_commit()?, head_a, "{}", backend);
+
+        // Links live outside the content hash and still reach B.
+        store_a.relate_issues("bd-a", "bd-b")?;
+        sync(&a, &mut store_a, open)?;
+        sync(&b, &mut store_b, open)?;
+        for (id, other) in [("bd-a", "bd-b"), ("bd-b", "bd-a")] {
+            let related = store_b.get_issue(id)?.unwrap().relates_to;
+            assert_eq!(related, vec![other], "{}", backend);
+        }
+
+        // B's worktree gets ahead of its database (pulled by hand, say)
+        // while B edits another field: the sync merges rather than keeping
+        // whichever copy is newer.
+        let mut issue = store_a.get_issue("bd-b")?.unwrap();
+        issue.title = "Renamed by A".to_string();
+        issue.updated_at = Utc::now();
+        store_a.update_issue(&issue)?;
+        sync(&a, &mut store_a, open)?;
+        let worktree_b = worktree_path(&b.join(".git"), "beads-sync");
+        git(&worktree_b, &["fetch", "origin", "beads-sync"]);
+        git(&worktree_b, &["merge", "--ff-only", "FETCH_HEAD"]);
+        let mut issue = store_b.get_issue("bd-b")?.unwrap();
+        issue.description = "Described by B".to_string();
+        issue.updated_at = Utc::now();
+        store_b.update_issue(&issue)?;
+        let report = sync(&b, &mut store_b, open)?.expect("no merge");
+        assert_eq!(report.issues.len(), 1, "{}", backend);
+        let fields = &report.get("bd-b").unwrap().fields;
+        assert_eq!(fields["title"], FieldResolution::Left, "{}", backend);
+        assert_eq!(fields["description"], FieldResolution::Right, "{}", backend);
+        let merged = store_b.get_issue("bd-b")?.unwrap();
+        assert_eq!(merged.title, "Renamed by A", "{}", backend);
+        assert_eq!(merged.description, "Described by B", "{}", backend);
+        git(&remote, &["fsck", "--strict"]);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_common_dir() -> anyhow::Result<()> {
+    for (backend, open) in BACKENDS {
+        let dir = tempdir()?;
+        let repo = dir.path().join("repo");
+        git(dir.path(), &["init", "repo"]);
+        set_identity(&repo);
+        git(&repo, &["commit", "--allow-empty", "-m", "init"]);
+        git(&repo, &["worktree", "add", "../linked"]);
+        let git_dir = fs::canonicalize(repo.join(".git"))?;
+        for root in [&repo, &dir.path().join("linked")] {
+            assert_eq!(
+                fs::canonicalize(open(root).common_dir()?)?,
+                git_dir,
+                "{}",
+                backend
+            );
+        }
+    }
+
+    // Branch names with dots or slashes keep snapshots apart.
+    let git_dir = Path::new(".git");
+    let snapshots: std::collections::HashSet<_> =
+        ["beads.sync", "beads.main", "release/v1.2", "release/v1.3"]
+            .into_iter()
+            .map(|branch| base_snapshot_path(git_dir, branch))
+            .collect();
+    assert_eq!(snapshots.len(), 4);
+    assert_eq!(
+        base_snapshot_path(git_dir, "release/v1.2"),
+        git_dir
+            .join("beads-worktrees")
+            .join("release")
+            .join("v1.2.base.jsonl")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_gix_backend_rejects_network_remotes() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    git(dir.path(), &["init", "repo"]);
+    let repo = dir.path().join("repo");
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    store.set_config(CONFIG_GIT_BACKEND, "gix")?;
+    assert!(configured(&store, &repo).is_ok());
+
+    git(
+        &repo,
+        &["remote", "add", "origin", "https://example.com/repo.git"],
+    );
+    let err = configured(&store, &repo)
+        .map(|_| ())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("https://example.com/repo.git"), "{}", err);
+    assert!(err.contains("experimental and local-only"), "{}", err);
+    assert!(err.contains("set git_backend to cli"), "{}", err);
+
+    store.set_config(CONFIG_GIT_BACKEND, "cli")?;
+    assert!(configured(&store, &repo).is_ok());
+    Ok(())
 }
@@ -1,4 +1,6 @@
-use beads_core::{Issue, MemoryStore, StdFileSystem, Store};
+use beads_core::filter::SortField;
+use beads_core::{Dependency, Issue, IssueFilter, MemoryStore, StdFileSystem, Store};
+use chrono::{TimeZone, Utc};
 use tempfile::tempdir;
 
 #[test]
@@ -27,7 +29,7 @@ fn test_memory_store_crud() {
     assert_eq!(fetched_updated.status, "closed");
 
     // List Issues
-    let issues = store.list_issues(None, None, None, None, None, None).unwrap();
+    let issues = store.list_issues(&IssueFilter::default()).unwrap();
     assert_eq!(issues.len(), 1);
     assert_eq!(issues[0].id, "issue1");
 }
@@ -66,8 +68,587 @@ fn test_memory_store_export_import() {
 
     // Import into new store
     let mut store2 = MemoryStore::new();
-    store2.import_from_jsonl(&jsonl_path, &fs).unwrap();
+    let stats = store2.import_from_jsonl(&jsonl_path, &fs).unwrap();
+    assert_eq!(stats.created, 1);
 
     let fetched = store2.get_issue("issue1").unwrap().unwrap();
     assert_eq!(fetched.title, "Export Test");
+
+    // A second import finds nothing new
+    let stats = store2.import_from_jsonl(&jsonl_path, &fs).unwrap();
+    assert_eq!((stats.created, stats.updated, stats.unchanged), (0, 0, 1));
+
+    // So does a change outside the content hash, which is then applied
+    let mut linked = fetched.clone();
+    linked.relates_to = vec!["issue2".to_string()];
+    linked.closed_at = Some(Utc::now());
+    let other = temp_dir.path().join("linked.jsonl");
+    std::fs::write(&other, serde_json::to_string(&linked).unwrap()).unwrap();
+    let stats = store2.import_from_jsonl(&other, &fs).unwrap();
+    assert_eq!((stats.created, stats.updated, stats.unchanged), (0, 1, 0));
+    let fetched = store2.get_issue("issue1").unwrap().unwrap();
+    assert_eq!(fetched.relates_to, vec!["issue2"]);
+    assert_eq!(fetched.closed_at, linked.closed_at);
+
+    // Incremental exports patch the file to match a full rebuild
+    store2.export_to_jsonl(&jsonl_path, &fs).unwrap();
+    store2
+        .create_issue(&Issue {
+            id: "issue0".to_string(),
+            title: "Earlier".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+    store2.export_dirty_to_jsonl(&jsonl_path, &fs).unwrap();
+    let full_path = temp_dir.path().join("full.jsonl");
+    store2.export_to_jsonl(&full_path, &fs).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&jsonl_path).unwrap(),
+        std::fs::read_to_string(&full_path).unwrap()
+    );
+}
+
+#[test]
+fn test_memory_store_content_hashes() -> anyhow::Result<()> {
+    let store = MemoryStore::new();
+    let mut issue = Issue {
+        id: "bd-a".to_string(),
+        title: "First".to_string(),
+        status: "open".to_string(),
+        ..Default::default()
+    };
+    store.create_issue(&issue)?;
+    let read = store.get_issue("bd-a")?.unwrap();
+    assert_eq!(read.content_hash, issue.compute_content_hash());
+
+    issue.title = "Second".to_string();
+    store.update_issue_if_unchanged(&issue, &read.content_hash)?;
+    issue.title = "Third".to_string();
+    assert!(store
+        .update_issue_if_unchanged(&issue, &read.content_hash)
+        .is_err());
+    assert_eq!(store.get_issue("bd-a")?.unwrap().title, "Second");
+
+    store.delete_issue("bd-a", "alice", "gone")?;
+    let deleted = store.get_issue("bd-a")?.unwrap();
+    assert_eq!(deleted.content_hash, deleted.compute_content_hash());
+    Ok(())
+}
+
+fn dep(issue_id: &str, depends_on_id: &str, type_: &str) -> Dependency {
+    Dependency {
+        issue_id: issue_id.to_string(),
+        depends_on_id: depends_on_id.to_string(),
+        type_: type_.to_string(),
+        created_at: Utc::now(),
+        created_by: "test".to_string(),
+    }
+}
+
+#[test]
+fn test_memory_store_ready_and_blocked() {
+    let store = MemoryStore::new();
+    let issue = |id: &str, status: &str, priority: i32, deps: Vec<Dependency>| Issue {
+        id: id.to_string(),
+        title: id.to_string(),
+        status: status.to_string(),
+        priority,
+        dependencies: deps,
+        ..Default::default()
+    };
+
+    store
+        .create_issue(&issue("blocker", "open", 2, vec![]))
+        .unwrap();
+    store
+        .create_issue(&issue("done", "closed", 0, vec![]))
+        .unwrap();
+    store
+        .create_issue(&issue(
+            "blocked",
+            "open",
+            0,
+            vec![dep("blocked", "blocker", "blocks")],
+        ))
+        .unwrap();
+    store
+        .create_issue(&issue(
+            "child",
+            "open",
+            0,
+            vec![dep("child", "blocked", "parent-child")],
+        ))
+        .unwrap();
+    store
+        .create_issue(&issue(
+            "free",
+            "open",
+            1,
+            vec![dep("free", "done", "blocks")],
+        ))
+        .unwrap();
+
+    let ready: Vec<String> = store
+        .ready_issues()
+        .unwrap()
+        .into_iter()
+        .map(|i| i.id)
+        .collect();
+    assert_eq!(ready, vec!["free", "blocker"]);
+
+    let blocked = store.blocked_issues().unwrap();
+    assert_eq!(blocked.len(), 2);
+    let entry = blocked.iter().find(|b| b.issue.id == "blocked").unwrap();
+    assert_eq!(entry.blockers.len(), 1);
+    assert_eq!(entry.blockers[0].id, "blocker");
+    assert_eq!(entry.blockers[0].status, "open");
+    assert!(entry.blocked_via_parent.is_none());
+
+    // The child has no blockers of its own: it waits on its parent.
+    let entry = blocked.iter().find(|b| b.issue.id == "child").unwrap();
+    assert!(entry.blockers.is_empty());
+    assert_eq!(entry.blocked_via_parent.as_ref().unwrap().id, "blocked");
+}
+
+#[test]
+fn test_memory_store_dependency_cycle_rejected() {
+    let store = MemoryStore::new();
+    for id in ["a", "b"] {
+        store
+            .create_issue(&Issue {
+                id: id.to_string(),
+                status: "open".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    store.add_dependency(&dep("a", "b", "blocks")).unwrap();
+    let err = store.add_dependency(&dep("b", "a", "related")).unwrap_err();
+    assert!(err.to_string().contains("b → a → b"));
+
+    store.remove_dependency("a", "b").unwrap();
+    store.add_dependency(&dep("b", "a", "related")).unwrap();
+    assert_eq!(store.get_issue("b").unwrap().unwrap().dependencies.len(), 1);
+}
+
+#[test]
+fn test_memory_store_delete_issue() {
+    let store = MemoryStore::new();
+    for id in ["a", "b"] {
+        store
+            .create_issue(&Issue {
+                id: id.to_string(),
+                status: "open".to_string(),
+                issue_type: "bug".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+    store.add_dependency(&dep("a", "b", "blocks")).unwrap();
+
+    store.delete_issue("b", "bob", "obsolete").unwrap();
+
+    let b = store.get_issue("b").unwrap().unwrap();
+    assert_eq!(b.status, "tombstone");
+    assert_eq!(b.original_type, "bug");
+    assert_eq!(b.delete_reason, "obsolete");
+    assert!(store
+        .get_issue("a")
+        .unwrap()
+        .unwrap()
+        .dependencies
+        .is_empty());
+    assert_eq!(store.list_issues(&IssueFilter::default()).unwrap().len(), 1);
+    assert!(store.delete_issue("b", "bob", "").is_err());
+}
+
+#[test]
+fn test_memory_store_reopen_and_undelete() {
+    let store = MemoryStore::new();
+    store
+        .create_issue(&Issue {
+            id: "a".to_string(),
+            status: "closed".to_string(),
+            issue_type: "task".to_string(),
+            closed_at: Some(Utc::now()),
+            ..Default::default()
+        })
+        .unwrap();
+
+    store.reopen_issue("a", "bob", "not done").unwrap();
+    let a = store.get_issue("a").unwrap().unwrap();
+    assert_eq!(a.status, "open");
+    assert!(a.closed_at.is_none());
+    assert_eq!(a.comments[0].text, "not done");
+    assert!(store.reopen_issue("a", "bob", "").is_err());
+
+    store.delete_issue("a", "bob", "").unwrap();
+    store.undelete_issue("a").unwrap();
+    let a = store.get_issue("a").unwrap().unwrap();
+    assert_eq!(a.status, "open");
+    assert_eq!(a.issue_type, "task");
+    assert!(store.undelete_issue("a").is_err());
+}
+
+#[test]
+fn test_memory_store_comments() {
+    let store = MemoryStore::new();
+    store
+        .create_issue(&Issue {
+            id: "a".to_string(),
+            status: "open".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let first = store.add_comment("a", "alice", "first").unwrap();
+    store.add_comment("a", "bob", "second").unwrap();
+    assert!(store.add_comment("missing", "alice", "nope").is_err());
+    assert_eq!(store.list_comments("a").unwrap().len(), 2);
+
+    store.delete_comment(first.id).unwrap();
+    let remaining = store.list_comments("a").unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].text, "second");
+    assert!(store.delete_comment(first.id).is_err());
+}
+
+#[test]
+fn test_memory_store_search() {
+    let store = MemoryStore::new();
+    for (id, title, notes) in [
+        ("a", "Login fails", "authentication authentication"),
+        ("b", "Slow page", "see auth"),
+        ("c", "Other", ""),
+    ] {
+        store
+            .create_issue(&Issue {
+                id: id.to_string(),
+                title: title.to_string(),
+                notes: notes.to_string(),
+                status: "open".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    let results = store.search("AUTH", &IssueFilter::default()).unwrap();
+    let ids: Vec<&str> = results.iter().map(|r| r.issue.id.as_str()).collect();
+    assert_eq!(ids, vec!["a", "b"]);
+    assert_eq!(results[1].snippet, "see [auth]");
+
+    let limited = IssueFilter {
+        limit: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(store.search("auth", &limited).unwrap().len(), 1);
+    assert!(store
+        .search("auth missing", &IssueFilter::default())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_memory_store_list_issues_filter() -> anyhow::Result<()> {
+    let store = MemoryStore::new();
+    let day = |d: u32| Utc.with_ymd_and_hms(2025, 1, d, 12, 0, 0).unwrap();
+    let issue = |id: &str, status: &str, priority: i32, created: u32, labels: &[&str]| Issue {
+        id: id.to_string(),
+        title: format!("Issue {}", id),
+        description: if id.ends_with('1') {
+            "Needle in here".to_string()
+        } else {
+            String::new()
+        },
+        status: status.to_string(),
+        priority,
+        issue_type: "task".to_string(),
+        assignee: (priority == 0).then(|| "alice".to_string()),
+        created_at: day(created),
+        updated_at: day(created + 1),
+        closed_at: (status == "closed").then(|| day(created + 2)),
+        labels: labels.iter().map(|l| l.to_string()).collect(),
+        ..Default::default()
+    };
+    for i in [
+        issue("bd-a1", "open", 0, 1, &["backend", "api"]),
+        issue("bd-a2", "in_progress", 1, 2, &["backend"]),
+        issue("bd-b1", "closed", 2, 3, &["frontend"]),
+        issue("bd-b2", "blocked", 3, 4, &[]),
+        issue("bd-c1", "tombstone", 1, 5, &[]),
+    ] {
+        store.create_issue(&i)?;
+    }
+
+    let ids = |filter: IssueFilter| -> anyhow::Result<Vec<String>> {
+        Ok(store
+            .list_issues(&filter)?
+            .into_iter()
+            .map(|i| i.id)
+            .collect())
+    };
+
+    // Newest first, tombstones hidden by default
+    assert_eq!(
+        ids(IssueFilter::default())?,
+        vec!["bd-b2", "bd-b1", "bd-a2", "bd-a1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            statuses: vec!["tombstone".to_string()],
+            ..Default::default()
+        })?,
+        vec!["bd-c1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            statuses: vec!["open".to_string(), "blocked".to_string()],
+            ..Default::default()
+        })?,
+        vec!["bd-b2", "bd-a1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            exclude_statuses: vec!["closed".to_string()],
+            sort: SortField::Priority,
+            ..Default::default()
+        })?,
+        vec!["bd-a1", "bd-a2", "bd-b2"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            labels: vec!["backend".to_string(), "api".to_string()],
+            ..Default::default()
+        })?,
+        vec!["bd-a1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            labels_any: vec!["api".to_string(), "frontend".to_string()],
+            ..Default::default()
+        })?,
+        vec!["bd-b1", "bd-a1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            priority_min: Some(1),
+            priority_max: Some(2),
+            ..Default::default()
+        })?,
+        vec!["bd-b1", "bd-a2"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            created_after: Some(day(2)),
+            created_before: Some(day(3)),
+            ..Default::default()
+        })?,
+        vec!["bd-b1", "bd-a2"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            updated_after: Some(day(4)),
+            ..Default::default()
+        })?,
+        vec!["bd-b2", "bd-b1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            closed_before: Some(day(10)),
+            ..Default::default()
+        })?,
+        vec!["bd-b1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            id_prefix: Some("bd-b".to_string()),
+            ..Default::default()
+        })?,
+        vec!["bd-b2", "bd-b1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            text: Some("NEEDLE".to_string()),
+            ..Default::default()
+        })?,
+        vec!["bd-b1", "bd-a1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            assignee: Some("unassigned".to_string()),
+            sort: SortField::Id,
+            reverse: true,
+            ..Default::default()
+        })?,
+        vec!["bd-b2", "bd-b1", "bd-a2"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            sort: SortField::Created,
+            reverse: true,
+            offset: 1,
+            limit: Some(2),
+            ..Default::default()
+        })?,
+        vec!["bd-a2", "bd-b1"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_store_adaptive_id_length() -> anyhow::Result<()> {
+    let store = MemoryStore::new();
+    let id = store.generate_unique_id("bd", "First", "", "alice")?;
+    assert_eq!(id.len(), "bd-".len() + 3);
+
+    store.set_config("min_hash_length", "5")?;
+    let id = store.generate_unique_id("bd", "First", "", "alice")?;
+    assert_eq!(id.len(), "bd-".len() + 5);
+    Ok(())
+}
+
+#[test]
+fn test_memory_store_child_ids() -> anyhow::Result<()> {
+    let store = MemoryStore::new();
+    store.create_issue(&Issue {
+        id: "bd-epic".to_string(),
+        issue_type: "epic".to_string(),
+        status: "open".to_string(),
+        ..Default::default()
+    })?;
+    store.create_issue(&Issue {
+        id: "bd-epic.2".to_string(),
+        status: "closed".to_string(),
+        dependencies: vec![Dependency {
+            issue_id: "bd-epic.2".to_string(),
+            depends_on_id: "bd-epic".to_string(),
+            type_: "parent-child".to_string(),
+            created_at: Utc::now(),
+            created_by: "test".to_string(),
+        }],
+        ..Default::default()
+    })?;
+    assert_eq!(store.next_child_id("bd-epic")?, "bd-epic.3");
+    assert_eq!(store.next_child_id("bd-epic")?, "bd-epic.4");
+    assert!(store.next_child_id("bd-missing").is_err());
+
+    let status = store.epic_status()?;
+    assert_eq!(status.len(), 1);
+    assert!(status[0].eligible_for_close);
+    Ok(())
+}
+
+#[test]
+fn test_memory_store_rename_prefix() -> anyhow::Result<()> {
+    let store = MemoryStore::new();
+    store.create_issue(&Issue {
+        id: "bd-a".to_string(),
+        ..Default::default()
+    })?;
+    store.create_issue(&Issue {
+        id: "bd-b".to_string(),
+        description: "After bd-a".to_string(),
+        dependencies: vec![Dependency {
+            issue_id: "bd-b".to_string(),
+            depends_on_id: "bd-a".to_string(),
+            type_: "blocks".to_string(),
+            created_at: Utc::now(),
+            created_by: "test".to_string(),
+        }],
+        ..Default::default()
+    })?;
+
+    assert_eq!(store.rename_prefix("bd", "kw", true)?.len(), 2);
+    assert!(store.get_issue("bd-a")?.is_some());
+
+    assert_eq!(store.rename_prefix("bd", "kw", false)?.len(), 2);
+    assert!(store.get_issue("bd-a")?.is_none());
+    let b = store.get_issue("kw-b")?.unwrap();
+    assert_eq!(b.description, "After kw-a");
+    assert_eq!(b.dependencies[0].depends_on_id, "kw-a");
+    assert_eq!(store.get_config("issue_id_prefix")?.as_deref(), Some("kw"));
+    Ok(())
+}
+
+#[test]
+fn test_memory_store_compaction() -> anyhow::Result<()> {
+    use beads_core::compact::{self, BuiltinSummarizer, CompactionConfig};
+
+    let store = MemoryStore::new();
+    let now = Utc::now();
+    store.create_issue(&Issue {
+        id: "bd-old".to_string(),
+        title: "Old".to_string(),
+        description: format!("Summary line.\n\n{}", "Detail. ".repeat(50)),
+        status: "closed".to_string(),
+        closed_at: Some(now - chrono::Duration::days(31)),
+        ..Default::default()
+    })?;
+    store.add_comment("bd-old", "bob", "Done.")?;
+
+    let config = CompactionConfig::default();
+    let candidates = compact::candidates(&store, 1, &config, now)?;
+    assert_eq!(candidates.len(), 1);
+    compact::compact_issue(&store, &candidates[0].id, 1, &BuiltinSummarizer, None, now)?;
+
+    let old = store.get_issue("bd-old")?.unwrap();
+    assert_eq!(old.description, "Summary line.\n\nResolution: Done.");
+    assert!(old.comments.is_empty());
+    assert_eq!(old.compaction_level, 1);
+    assert_eq!(old.compacted_at, Some(now));
+    Ok(())
+}
+
+#[test]
+fn test_memory_store_excludes_messages() -> anyhow::Result<()> {
+    let store = MemoryStore::new();
+    let now = Utc::now();
+    store.create_issue(&Issue {
+        id: "bd-task".to_string(),
+        status: "open".to_string(),
+        ..Default::default()
+    })?;
+    store.create_issue(&beads_core::mail::new_message(
+        "bd-m1", "bob", "alice", "Hi", "", 2, now,
+    ))?;
+    store.create_issue(&Issue {
+        status: "blocked".to_string(),
+        ..beads_core::mail::new_message("bd-m2", "dave", "carol", "Stuck", "", 2, now)
+    })?;
+
+    let ready: Vec<String> = store.ready_issues()?.into_iter().map(|i| i.id).collect();
+    assert_eq!(ready, ["bd-task"]);
+    assert!(store.blocked_issues()?.is_empty());
+    let filter = IssueFilter {
+        exclude_types: vec![beads_core::TYPE_MESSAGE.to_string()],
+        ..Default::default()
+    };
+    assert_eq!(store.list_issues(&filter)?.len(), 1);
+    assert_eq!(beads_core::mail::inbox(&store, "bob")?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_memory_store_issue_links() -> anyhow::Result<()> {
+    let store = MemoryStore::new();
+    for id in ["bd-a", "bd-b"] {
+        store.create_issue(&Issue {
+            id: id.to_string(),
+            status: "open".to_string(),
+            ..Default::default()
+        })?;
+    }
+    store.relate_issues("bd-a", "bd-b")?;
+    store.mark_duplicate("bd-b", "bd-a")?;
+
+    let b = store.get_issue("bd-b")?.unwrap();
+    assert_eq!(b.relates_to, ["bd-a"]);
+    assert_eq!(b.status, "closed");
+    let links = store.issue_links("bd-a")?;
+    assert_eq!(links.related[0].id, "bd-b");
+    assert_eq!(links.duplicates[0].id, "bd-b");
+    assert_eq!(store.dirty_issue_ids()?, ["bd-a", "bd-b"]);
+    Ok(())
 }
@@ -0,0 +1,189 @@
+use beads_core::{
+    Comment, Dependency, FileSystem, Issue, MemoryStore, SqliteStore, StdFileSystem, Store,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The issue database this repository tracks itself with, written by the Go bd.
+fn repository_jsonl() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../.beads/issues.jsonl")
+}
+
+/// Parses a JSONL export into issues keyed by ID, normalised so that equal
+/// content compares equal: timestamps as instants, label and dependency order
+/// ignored, and comment IDs (assigned by each database) dropped.
+fn normalized(path: &Path) -> anyhow::Result<BTreeMap<String, Value>> {
+    let mut issues = BTreeMap::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut value: Value = serde_json::from_str(line)?;
+        normalize(&mut value);
+        let id = value["id"].as_str().unwrap_or_default().to_string();
+        issues.insert(id, value);
+    }
+    Ok(issues)
+}
+
+fn normalize(value: &mut Value) {
+    let Value::Object(map) = value else { return };
+    for (key, field) in map.iter_mut() {
+        match field {
+            Value::String(s) if key.ends_with("_at") => {
+                if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+                    *s = t.with_timezone(&Utc).to_rfc3339();
+                }
+            }
+            Value::Array(items) => {
+                for item in items.iter_mut() {
+                    normalize(item);
+                    if let Value::Object(obj) = item {
+                        obj.remove("id");
+                    }
+                }
+                items.sort_by_key(|item| item.to_string());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn assert_same_issues(expected: &Path, actual: &Path) -> anyhow::Result<()> {
+    let expected = normalized(expected)?;
+    let actual = normalized(actual)?;
+    assert_eq!(expected.len(), actual.len(), "issue count differs");
+    for (id, issue) in &expected {
+        assert_eq!(Some(issue), actual.get(id), "issue {} changed", id);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_repository_jsonl_round_trip() -> anyhow::Result<()> {
+    let source = repository_jsonl();
+    let dir = tempfile::tempdir()?;
+    let fs = StdFileSystem;
+
+    let mut store = SqliteStore::open(dir.path().join("beads.db"))?;
+    store.import_from_jsonl(&source, &fs)?;
+    let first = dir.path().join("first.jsonl");
+    store.export_to_jsonl(&first, &fs)?;
+    assert_same_issues(&source, &first)?;
+
+    // Re-importing our own export into a fresh database changes nothing
+    let mut again = SqliteStore::open(dir.path().join("again.db"))?;
+    again.import_from_jsonl(&first, &fs)?;
+    let second = dir.path().join("second.jsonl");
+    again.export_to_jsonl(&second, &fs)?;
+    assert_same_issues(&first, &second)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_store_repository_jsonl_round_trip() -> anyhow::Result<()> {
+    let source = repository_jsonl();
+    let dir = tempfile::tempdir()?;
+    let fs = StdFileSystem;
+
+    let mut store = MemoryStore::new();
+    store.import_from_jsonl(&source, &fs)?;
+    let exported = dir.path().join("issues.jsonl");
+    store.export_to_jsonl(&exported, &fs)?;
+    assert_same_issues(&source, &exported)
+}
+
+fn fully_populated_issue() -> Issue {
+    let at = |h: u32| Utc.with_ymd_and_hms(2025, 3, 1, h, 0, 0).unwrap();
+    Issue {
+        id: "bd-full".to_string(),
+        content_hash: String::new(),
+        title: "Every field".to_string(),
+        description: "Description".to_string(),
+        design: "Design notes".to_string(),
+        acceptance_criteria: "- [ ] it works".to_string(),
+        notes: "Working notes".to_string(),
+        status: "closed".to_string(),
+        priority: 1,
+        issue_type: "feature".to_string(),
+        assignee: Some("alice".to_string()),
+        estimated_minutes: Some(90),
+        created_at: at(1),
+        updated_at: at(2),
+        closed_at: Some(at(3)),
+        external_ref: Some("gh-42".to_string()),
+        compaction_level: 1,
+        compacted_at: Some(at(5)),
+        compacted_at_commit: Some("0123abc".to_string()),
+        original_size: 512,
+        sender: "bob".to_string(),
+        ephemeral: true,
+        replies_to: "bd-msg".to_string(),
+        relates_to: vec!["bd-rel".to_string()],
+        duplicate_of: "bd-dup".to_string(),
+        superseded_by: "bd-new".to_string(),
+        deleted_at: Some(at(4)),
+        deleted_by: "carol".to_string(),
+        delete_reason: "cleanup".to_string(),
+        original_type: "task".to_string(),
+        labels: vec!["a".to_string(), "b".to_string()],
+        dependencies: vec![Dependency {
+            issue_id: "bd-full".to_string(),
+            depends_on_id: "bd-other".to_string(),
+            type_: "blocks".to_string(),
+            created_at: at(1),
+            created_by: "alice".to_string(),
+        }],
+        comments: vec![Comment {
+            id: 0,
+            issue_id: "bd-full".to_string(),
+            author: "dave".to_string(),
+            text: "LGTM".to_string(),
+            created_at: at(2),
+        }],
+    }
+}
+
+#[test]
+fn test_every_field_survives_create_update_and_import() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let fs = StdFileSystem;
+    let issue = fully_populated_issue();
+
+    let expected = dir.path().join("expected.jsonl");
+    fs.write(
+        &expected,
+        format!("{}\n", serde_json::to_string(&issue)?).as_bytes(),
+    )?;
+
+    // create_issue
+    let store = SqliteStore::open(dir.path().join("created.db"))?;
+    store.create_issue(&issue)?;
+    let created = dir.path().join("created.jsonl");
+    store.export_to_jsonl(&created, &fs)?;
+    assert_same_issues(&expected, &created)?;
+
+    // update_issue, starting from a bare issue with the same ID
+    let store = SqliteStore::open(dir.path().join("updated.db"))?;
+    store.create_issue(&Issue {
+        id: issue.id.clone(),
+        comments: issue.comments.clone(),
+        ..Default::default()
+    })?;
+    store.update_issue(&issue)?;
+    let updated = dir.path().join("updated.jsonl");
+    store.export_to_jsonl(&updated, &fs)?;
+    assert_same_issues(&expected, &updated)?;
+
+    // import_from_jsonl
+    let mut store = SqliteStore::open(dir.path().join("imported.db"))?;
+    store.import_from_jsonl(&expected, &fs)?;
+    let imported = dir.path().join("imported.jsonl");
+    store.export_to_jsonl(&imported, &fs)?;
+    assert_same_issues(&expected, &imported)?;
+
+    Ok(())
+}
@@ -1,5 +1,9 @@
-use beads_core::{Comment, Dependency, Issue, SqliteStore, StdFileSystem, Store};
-use chrono::Utc;
+use beads_core::autoimport::auto_import;
+use beads_core::compact::{self, BuiltinSummarizer, CompactionConfig};
+use beads_core::filter::SortField;
+use beads_core::graph::CycleError;
+use beads_core::{Comment, Dependency, Issue, IssueFilter, SqliteStore, StdFileSystem, Store};
+use chrono::{TimeZone, Utc};
 use std::fs;
 
 #[test]
@@ -35,6 +39,10 @@ fn test_export_to_jsonl() -> anyhow::Result<()> {
         updated_at: now,
         closed_at: None,
         external_ref: None,
+        compaction_level: 0,
+        compacted_at: None,
+        compacted_at_commit: None,
+        original_size: 0,
         sender: "".to_string(),
         ephemeral: false,
         replies_to: "".to_string(),
@@ -87,3 +95,1173 @@ fn test_export_to_jsonl() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_ready_and_blocked_issues() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let now = Utc::now();
+
+    let issue = |id: &str, status: &str, priority: i32, blocked_by: Option<&str>| Issue {
+        id: id.to_string(),
+        title: format!("Issue {}", id),
+        status: status.to_string(),
+        priority,
+        issue_type: "task".to_string(),
+        created_at: now,
+        updated_at: now,
+        dependencies: blocked_by
+            .map(|b| {
+                vec![Dependency {
+                    issue_id: id.to_string(),
+                    depends_on_id: b.to_string(),
+                    type_: "blocks".to_string(),
+                    created_at: now,
+                    created_by: "test".to_string(),
+                }]
+            })
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    store.create_issue(&issue("bd-a", "open", 3, None))?;
+    store.create_issue(&issue("bd-b", "open", 1, Some("bd-a")))?;
+    store.create_issue(&issue("bd-c", "closed", 1, None))?;
+    store.create_issue(&issue("bd-d", "in_progress", 2, Some("bd-c")))?;
+
+    let ready: Vec<String> = store.ready_issues()?.into_iter().map(|i| i.id).collect();
+    assert_eq!(ready, vec!["bd-d", "bd-a"]);
+
+    let blocked = store.blocked_issues()?;
+    assert_eq!(blocked.len(), 1);
+    assert_eq!(blocked[0].issue.id, "bd-b");
+    assert_eq!(blocked[0].blockers.len(), 1);
+    assert_eq!(blocked[0].blockers[0].title, "Issue bd-a");
+    assert_eq!(blocked[0].blockers[0].status, "open");
+
+    Ok(())
+}
+
+#[test]
+fn test_add_remove_dependency() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let now = Utc::now();
+
+    for id in ["bd-a", "bd-b", "bd-c"] {
+        store.create_issue(&Issue {
+            id: id.to_string(),
+            title: id.to_string(),
+            status: "open".to_string(),
+            created_at: now,
+            updated_at: now,
+            ..Default::default()
+        })?;
+    }
+    store.execute_raw("DELETE FROM dirty_issues")?;
+
+    let dep = |from: &str, to: &str| Dependency {
+        issue_id: from.to_string(),
+        depends_on_id: to.to_string(),
+        type_: "blocks".to_string(),
+        created_at: now,
+        created_by: "test".to_string(),
+    };
+
+    store.add_dependency(&dep("bd-a", "bd-b"))?;
+    store.add_dependency(&dep("bd-b", "bd-c"))?;
+    assert_eq!(store.get_issue("bd-a")?.unwrap().dependencies.len(), 1);
+
+    let err = store.add_dependency(&dep("bd-c", "bd-a")).unwrap_err();
+    let cycle = err.downcast_ref::<CycleError>().expect("cycle error");
+    assert_eq!(cycle.path, vec!["bd-c", "bd-a", "bd-b", "bd-c"]);
+
+    assert!(store.add_dependency(&dep("bd-a", "bd-missing")).is_err());
+    assert!(store.add_dependency(&dep("bd-a", "bd-b")).is_err());
+    let err = store
+        .add_dependency(&Dependency {
+            type_: "bogus".to_string(),
+            ..dep("bd-a", "bd-c")
+        })
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("duplicates, supersedes"), "{}", err);
+
+    store.remove_dependency("bd-a", "bd-b")?;
+    assert!(store.get_issue("bd-a")?.unwrap().dependencies.is_empty());
+    assert!(store.remove_dependency("bd-a", "bd-b").is_err());
+
+    // The edge that used to close the cycle is now allowed
+    store.add_dependency(&dep("bd-c", "bd-a"))?;
+    assert!(store.dependency_graph()?.cycles().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_delete_issue_creates_tombstone() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let now = Utc::now();
+
+    for id in ["bd-a", "bd-b", "bd-c"] {
+        store.create_issue(&Issue {
+            id: id.to_string(),
+            title: id.to_string(),
+            status: "open".to_string(),
+            issue_type: "feature".to_string(),
+            created_at: now,
+            updated_at: now,
+            ..Default::default()
+        })?;
+    }
+    let dep = |from: &str, to: &str| Dependency {
+        issue_id: from.to_string(),
+        depends_on_id: to.to_string(),
+        type_: "blocks".to_string(),
+        created_at: now,
+        created_by: "test".to_string(),
+    };
+    store.add_dependency(&dep("bd-a", "bd-b"))?;
+    store.add_dependency(&dep("bd-b", "bd-c"))?;
+    store.execute_raw("DELETE FROM dirty_issues")?;
+
+    store.delete_issue("bd-b", "alice", "duplicate")?;
+
+    let tombstone = store.get_issue("bd-b")?.unwrap();
+    assert_eq!(tombstone.status, "tombstone");
+    assert_eq!(tombstone.original_type, "feature");
+    assert_eq!(tombstone.deleted_by, "alice");
+    assert_eq!(tombstone.delete_reason, "duplicate");
+    assert!(tombstone.deleted_at.is_some());
+    assert!(tombstone.dependencies.is_empty());
+    assert!(store.get_issue("bd-a")?.unwrap().dependencies.is_empty());
+
+    // Both neighbours lost a link and are re-exported with the tombstone
+    let fs = StdFileSystem;
+    let jsonl = dir.path().join("issues.jsonl");
+    store.export_to_jsonl(&jsonl, &fs)?;
+    assert!(std::fs::read_to_string(&jsonl)?.contains("\"status\":\"tombstone\""));
+
+    let listed: Vec<String> = store
+        .list_issues(&IssueFilter::default())?
+        .into_iter()
+        .map(|i| i.id)
+        .collect();
+    assert!(!listed.contains(&"bd-b".to_string()));
+    assert!(store.ready_issues()?.iter().all(|i| i.id != "bd-b"));
+
+    assert!(store.delete_issue("bd-b", "alice", "again").is_err());
+    assert!(store.delete_issue("bd-missing", "alice", "").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_reopen_and_undelete() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let now = Utc::now();
+
+    for (id, status) in [("bd-a", "closed"), ("bd-b", "open")] {
+        store.create_issue(&Issue {
+            id: id.to_string(),
+            title: id.to_string(),
+            status: status.to_string(),
+            issue_type: "epic".to_string(),
+            created_at: now,
+            updated_at: now,
+            closed_at: (status == "closed").then_some(now),
+            ..Default::default()
+        })?;
+    }
+    store.execute_raw("DELETE FROM dirty_issues")?;
+
+    store.reopen_issue("bd-a", "alice", "regressed")?;
+    let reopened = store.get_issue("bd-a")?.unwrap();
+    assert_eq!(reopened.status, "open");
+    assert!(reopened.closed_at.is_none());
+    assert_eq!(reopened.comments.len(), 1);
+    assert_eq!(reopened.comments[0].author, "alice");
+    assert_eq!(reopened.comments[0].text, "regressed");
+    assert!(store.reopen_issue("bd-b", "alice", "").is_err());
+
+    assert!(store.undelete_issue("bd-b").is_err());
+    store.delete_issue("bd-b", "alice", "oops")?;
+    store.undelete_issue("bd-b")?;
+    let restored = store.get_issue("bd-b")?.unwrap();
+    assert_eq!(restored.status, "open");
+    assert_eq!(restored.issue_type, "epic");
+    assert!(restored.deleted_at.is_none());
+    assert!(restored.original_type.is_empty());
+
+    // Tombstones past the TTL stay deleted
+    store.delete_issue("bd-b", "alice", "again")?;
+    store.execute_raw("UPDATE issues SET deleted_at = '2000-01-01T00:00:00Z' WHERE id = 'bd-b'")?;
+    assert!(store.undelete_issue("bd-b").is_err());
+
+    let fs = StdFileSystem;
+    let jsonl = dir.path().join("issues.jsonl");
+    store.export_to_jsonl(&jsonl, &fs)?;
+    assert!(std::fs::read_to_string(&jsonl)?.contains("regressed"));
+
+    Ok(())
+}
+
+#[test]
+fn test_comments() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let mut store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let now = Utc::now();
+    store.create_issue(&Issue {
+        id: "bd-a".to_string(),
+        title: "A".to_string(),
+        status: "open".to_string(),
+        created_at: now,
+        updated_at: now,
+        ..Default::default()
+    })?;
+    store.execute_raw("DELETE FROM dirty_issues")?;
+
+    let first = store.add_comment("bd-a", "alice", "first")?;
+    let second = store.add_comment("bd-a", "bob", "second")?;
+    assert_ne!(first.id, second.id);
+    assert!(store.add_comment("bd-missing", "alice", "nope").is_err());
+
+    let texts: Vec<String> = store
+        .list_comments("bd-a")?
+        .into_iter()
+        .map(|c| c.text)
+        .collect();
+    assert_eq!(texts, vec!["first", "second"]);
+    assert_eq!(store.get_issue("bd-a")?.unwrap().comments.len(), 2);
+
+    store.delete_comment(first.id)?;
+    let remaining = store.list_comments("bd-a")?;
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].author, "bob");
+    assert!(store.delete_comment(first.id).is_err());
+
+    let jsonl = dir.path().join("issues.jsonl");
+    store.export_to_jsonl(&jsonl, &StdFileSystem)?;
+    let exported = std::fs::read_to_string(&jsonl)?;
+    assert!(exported.contains("second") && !exported.contains("first"));
+
+    // Importing an issue without a comment the database has drops it
+    store.add_comment("bd-a", "carol", "third")?;
+    store.import_from_jsonl(&jsonl, &StdFileSystem)?;
+    let texts: Vec<String> = store
+        .list_comments("bd-a")?
+        .into_iter()
+        .map(|c| c.text)
+        .collect();
+    assert_eq!(texts, vec!["second"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_search() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("beads.db");
+    let store = SqliteStore::open(&db_path)?;
+    let now = Utc::now();
+
+    let issue = |id: &str, title: &str, description: &str, labels: &[&str]| Issue {
+        id: id.to_string(),
+        title: title.to_string(),
+        description: description.to_string(),
+        status: "open".to_string(),
+        priority: 2,
+        issue_type: "bug".to_string(),
+        created_at: now,
+        updated_at: now,
+        labels: labels.iter().map(|l| l.to_string()).collect(),
+        ..Default::default()
+    };
+    store.create_issue(&issue(
+        "bd-a1",
+        "Login fails",
+        "Authentication error after authentication refactor",
+        &["backend"],
+    ))?;
+    store.create_issue(&issue("bd-b2", "Slow dashboard", "Mentions auth once", &[]))?;
+    store.create_issue(&issue("bd-c3", "Unrelated", "Nothing to see", &[]))?;
+
+    let ids = |results: Vec<beads_core::SearchResult>| -> Vec<String> {
+        results.into_iter().map(|r| r.issue.id).collect()
+    };
+
+    // Prefix matching, best match first
+    let results = store.search("auth", &IssueFilter::default())?;
+    assert!(results[0].snippet.contains("[Authentication]"));
+    assert_eq!(ids(results), vec!["bd-a1", "bd-b2"]);
+
+    // Every word must match
+    assert_eq!(
+        ids(store.search("auth dashboard", &IssueFilter::default())?),
+        vec!["bd-b2"]
+    );
+
+    // Partial IDs and comment text are searchable
+    assert_eq!(
+        ids(store.search("bd-c", &IssueFilter::default())?),
+        vec!["bd-c3"]
+    );
+    store.add_comment("bd-c3", "alice", "flamegraph attached")?;
+    assert_eq!(
+        ids(store.search("flamegraph", &IssueFilter::default())?),
+        vec!["bd-c3"]
+    );
+
+    // Updates are reindexed and filters apply
+    let mut updated = store.get_issue("bd-b2")?.unwrap();
+    updated.description = "Nothing here".to_string();
+    store.update_issue(&updated)?;
+    let backend = IssueFilter {
+        labels: vec!["backend".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(ids(store.search("auth", &backend)?), vec!["bd-a1"]);
+    assert!(store
+        .search(
+            "auth",
+            &IssueFilter {
+                statuses: vec!["closed".to_string()],
+                ..Default::default()
+            }
+        )?
+        .is_empty());
+
+    // Deleted issues drop out, and reopening the database keeps the index
+    store.delete_issue("bd-a1", "alice", "")?;
+    assert!(store.search("login", &IssueFilter::default())?.is_empty());
+    drop(store);
+    let store = SqliteStore::open(&db_path)?;
+    assert_eq!(
+        ids(store.search("flamegraph", &IssueFilter::default())?),
+        vec!["bd-c3"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_list_issues_filter() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let day = |d: u32| Utc.with_ymd_and_hms(2025, 1, d, 12, 0, 0).unwrap();
+    let issue = |id: &str, status: &str, priority: i32, created: u32, labels: &[&str]| Issue {
+        id: id.to_string(),
+        title: format!("Issue {}", id),
+        description: if id.ends_with('1') {
+            "Needle in here".to_string()
+        } else {
+            String::new()
+        },
+        status: status.to_string(),
+        priority,
+        issue_type: "task".to_string(),
+        assignee: (priority == 0).then(|| "alice".to_string()),
+        created_at: day(created),
+        updated_at: day(created + 1),
+        closed_at: (status == "closed").then(|| day(created + 2)),
+        labels: labels.iter().map(|l| l.to_string()).collect(),
+        ..Default::default()
+    };
+    for i in [
+        issue("bd-a1", "open", 0, 1, &["backend", "api"]),
+        issue("bd-a2", "in_progress", 1, 2, &["backend"]),
+        issue("bd-b1", "closed", 2, 3, &["frontend"]),
+        issue("bd-b2", "blocked", 3, 4, &[]),
+        issue("bd-c1", "tombstone", 1, 5, &[]),
+    ] {
+        store.create_issue(&i)?;
+    }
+
+    let ids = |filter: IssueFilter| -> anyhow::Result<Vec<String>> {
+        Ok(store
+            .list_issues(&filter)?
+            .into_iter()
+            .map(|i| i.id)
+            .collect())
+    };
+
+    // Newest first, tombstones hidden by default
+    assert_eq!(
+        ids(IssueFilter::default())?,
+        vec!["bd-b2", "bd-b1", "bd-a2", "bd-a1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            statuses: vec!["tombstone".to_string()],
+            ..Default::default()
+        })?,
+        vec!["bd-c1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            statuses: vec!["open".to_string(), "blocked".to_string()],
+            ..Default::default()
+        })?,
+        vec!["bd-b2", "bd-a1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            exclude_statuses: vec!["closed".to_string()],
+            sort: SortField::Priority,
+            ..Default::default()
+        })?,
+        vec!["bd-a1", "bd-a2", "bd-b2"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            labels: vec!["backend".to_string(), "api".to_string()],
+            ..Default::default()
+        })?,
+        vec!["bd-a1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            labels_any: vec!["api".to_string(), "frontend".to_string()],
+            ..Default::default()
+        })?,
+        vec!["bd-b1", "bd-a1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            priority_min: Some(1),
+            priority_max: Some(2),
+            ..Default::default()
+        })?,
+        vec!["bd-b1", "bd-a2"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            created_after: Some(day(2)),
+            created_before: Some(day(3)),
+            ..Default::default()
+        })?,
+        vec!["bd-b1", "bd-a2"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            updated_after: Some(day(4)),
+            ..Default::default()
+        })?,
+        vec!["bd-b2", "bd-b1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            closed_before: Some(day(10)),
+            ..Default::default()
+        })?,
+        vec!["bd-b1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            id_prefix: Some("bd-b".to_string()),
+            ..Default::default()
+        })?,
+        vec!["bd-b2", "bd-b1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            text: Some("NEEDLE".to_string()),
+            ..Default::default()
+        })?,
+        vec!["bd-b1", "bd-a1"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            assignee: Some("unassigned".to_string()),
+            sort: SortField::Id,
+            reverse: true,
+            ..Default::default()
+        })?,
+        vec!["bd-b2", "bd-b1", "bd-a2"]
+    );
+    assert_eq!(
+        ids(IssueFilter {
+            sort: SortField::Created,
+            reverse: true,
+            offset: 1,
+            limit: Some(2),
+            ..Default::default()
+        })?,
+        vec!["bd-a2", "bd-b1"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_schema_migrations() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("beads.db");
+    let latest = beads_core::migrations::latest_version();
+
+    // A fresh database is brought to the latest version
+    let store = SqliteStore::open(&db_path)?;
+    assert_eq!(store.schema_version()?, latest);
+    assert!(store.pending_migrations()?.is_empty());
+    drop(store);
+
+    // Databases from before versioning report 0 and are upgraded in place
+    let legacy = dir.path().join("legacy.db");
+    let store = SqliteStore::open(&legacy)?;
+    store.create_issue(&Issue {
+        id: "bd-old".to_string(),
+        title: "Predates search".to_string(),
+        status: "open".to_string(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        ..Default::default()
+    })?;
+    store.execute_raw(
+        "ALTER TABLE issues DROP COLUMN original_size;
+         DROP TABLE issues_fts; DROP INDEX idx_labels_label; PRAGMA user_version = 0;",
+    )?;
+    drop(store);
+
+    let mut store = SqliteStore::open_unmigrated(&legacy)?;
+    assert_eq!(store.schema_version()?, 0);
+    assert_eq!(store.pending_migrations()?.len(), latest as usize);
+    let applied: Vec<u32> = store.migrate()?.iter().map(|m| m.version).collect();
+    assert_eq!(applied, (1..=latest).collect::<Vec<_>>());
+    assert_eq!(
+        store
+            .search("predates", &IssueFilter::default())?
+            .first()
+            .map(|r| r.issue.id.as_str()),
+        Some("bd-old")
+    );
+    assert_eq!(store.get_issue("bd-old")?.unwrap().original_size, 0);
+    assert!(store.migrate()?.is_empty());
+
+    // Databases written by a newer binary are refused
+    store.execute_raw(&format!("PRAGMA user_version = {}", latest + 1))?;
+    drop(store);
+    let err = SqliteStore::open(&legacy)
+        .err()
+        .expect("newer schema refused");
+    assert!(err.to_string().contains("newer than this bd supports"));
+
+    Ok(())
+}
+
+#[test]
+fn test_content_hashes() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let fs = StdFileSystem;
+    let mut store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let issue = |id: &str, title: &str| Issue {
+        id: id.to_string(),
+        title: title.to_string(),
+        status: "open".to_string(),
+        priority: 2,
+        issue_type: "task".to_string(),
+        ..Default::default()
+    };
+
+    // Hashes are stored on create and follow every kind of update
+    store.create_issue(&issue("bd-a", "First"))?;
+    let created = store.get_issue("bd-a")?.unwrap();
+    assert_eq!(created.content_hash, created.compute_content_hash());
+
+    store.update_issue(&issue("bd-a", "Renamed"))?;
+    let renamed = store.get_issue("bd-a")?.unwrap();
+    assert_ne!(renamed.content_hash, created.content_hash);
+    assert_eq!(renamed.content_hash, renamed.compute_content_hash());
+
+    store.delete_issue("bd-a", "alice", "gone")?;
+    let deleted = store.get_issue("bd-a")?.unwrap();
+    assert_eq!(deleted.content_hash, deleted.compute_content_hash());
+    store.undelete_issue("bd-a")?;
+
+    // Stale writers are refused
+    let current = store.get_issue("bd-a")?.unwrap();
+    let err = store
+        .update_issue_if_unchanged(&issue("bd-a", "Stale"), &created.content_hash)
+        .unwrap_err();
+    assert!(err.to_string().contains("modified since it was read"));
+    assert_eq!(store.get_issue("bd-a")?.unwrap().title, "Renamed");
+    store.update_issue_if_unchanged(&issue("bd-a", "Fresh"), &current.content_hash)?;
+    assert_eq!(store.get_issue("bd-a")?.unwrap().title, "Fresh");
+
+    // Import skips issues whose content is unchanged
+    store.create_issue(&issue("bd-b", "Second"))?;
+    let jsonl = dir.path().join("issues.jsonl");
+    store.export_to_jsonl(&jsonl, &fs)?;
+    let stats = store.import_from_jsonl(&jsonl, &fs)?;
+    assert_eq!((stats.created, stats.updated, stats.unchanged), (0, 0, 2));
+
+    let mut changed = store.get_issue("bd-b")?.unwrap();
+    changed.title = "Second, edited".to_string();
+    let lines = [
+        serde_json::to_string(&store.get_issue("bd-a")?.unwrap())?,
+        serde_json::to_string(&changed)?,
+        serde_json::to_string(&issue("bd-c", "Third"))?,
+    ];
+    fs::write(&jsonl, lines.join("\n"))?;
+    let stats = store.import_from_jsonl(&jsonl, &fs)?;
+    assert_eq!((stats.created, stats.updated, stats.unchanged), (1, 1, 1));
+    let imported = store.get_issue("bd-b")?.unwrap();
+    assert_eq!(imported.title, "Second, edited");
+    assert_eq!(imported.content_hash, imported.compute_content_hash());
+
+    // Fields outside the content hash count as changes too
+    let mut linked = store.get_issue("bd-a")?.unwrap();
+    linked.relates_to = vec!["bd-b".to_string()];
+    linked.estimated_minutes = Some(30);
+    linked.duplicate_of = "bd-c".to_string();
+    fs::write(&jsonl, serde_json::to_string(&linked)?)?;
+    let stats = store.import_from_jsonl(&jsonl, &fs)?;
+    assert_eq!((stats.created, stats.updated, stats.unchanged), (0, 1, 0));
+    let imported = store.get_issue("bd-a")?.unwrap();
+    assert_eq!(imported.relates_to, vec!["bd-b"]);
+    assert_eq!(imported.estimated_minutes, Some(30));
+    assert_eq!(imported.duplicate_of, "bd-c");
+
+    Ok(())
+}
+
+#[test]
+fn test_incremental_export() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let fs = StdFileSystem;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let jsonl = dir.path().join("issues.jsonl");
+    let full = dir.path().join("full.jsonl");
+    let issue = |id: &str, title: &str| Issue {
+        id: id.to_string(),
+        title: title.to_string(),
+        status: "open".to_string(),
+        priority: 2,
+        issue_type: "task".to_string(),
+        created_at: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+        updated_at: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+        ..Default::default()
+    };
+
+    // With no file yet, the first export is a full one
+    for id in ["bd-a", "bd-c", "bd-e"] {
+        store.create_issue(&issue(id, id))?;
+    }
+    store.export_dirty_to_jsonl(&jsonl, &fs)?;
+    assert_eq!(fs::read_to_string(&jsonl)?.lines().count(), 3);
+
+    // Only dirty lines are rewritten: bd-e's out-of-band edit is not picked up
+    store.execute_raw("UPDATE issues SET title = 'unflushed' WHERE id = 'bd-e'")?;
+    store.update_issue(&issue("bd-a", "renamed"))?;
+    store.create_issue(&issue("bd-b", "inserted"))?;
+    store.create_issue(&issue("bd-z", "appended"))?;
+    store.add_comment("bd-c", "alice", "note")?;
+    store.export_dirty_to_jsonl(&jsonl, &fs)?;
+    let patched = fs::read_to_string(&jsonl)?;
+    let ids: Vec<String> = patched
+        .lines()
+        .map(|l| serde_json::from_str::<Issue>(l).map(|i| i.id))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(ids, ["bd-a", "bd-b", "bd-c", "bd-e", "bd-z"]);
+    assert!(patched.contains("renamed") && patched.contains("\"note\""));
+    assert!(!patched.contains("unflushed"));
+
+    // Once everything is dirty, the result matches a full rebuild exactly
+    store.execute_raw("INSERT OR IGNORE INTO dirty_issues (issue_id) SELECT id FROM issues")?;
+    store.execute_raw("DELETE FROM issues WHERE id = 'bd-z'")?;
+    store.export_dirty_to_jsonl(&jsonl, &fs)?;
+    store.export_to_jsonl(&full, &fs)?;
+    assert_eq!(fs::read_to_string(&jsonl)?, fs::read_to_string(&full)?);
+    assert!(!fs::read_to_string(&jsonl)?.contains("bd-z"));
+
+    // A file changed behind our back is rebuilt rather than patched
+    store.export_to_jsonl(&jsonl, &fs)?;
+    fs::write(&jsonl, "{\"id\":\"bd-stray\"}\n")?;
+    store.update_issue(&issue("bd-c", "edited"))?;
+    store.export_dirty_to_jsonl(&jsonl, &fs)?;
+    store.export_to_jsonl(&full, &fs)?;
+    assert_eq!(fs::read_to_string(&jsonl)?, fs::read_to_string(&full)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_auto_import() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let fs = StdFileSystem;
+    let mut store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let jsonl = dir.path().join("issues.jsonl");
+    let issue = |id: &str, title: &str| Issue {
+        id: id.to_string(),
+        title: title.to_string(),
+        status: "open".to_string(),
+        priority: 2,
+        issue_type: "task".to_string(),
+        ..Default::default()
+    };
+    let line = |i: &Issue| serde_json::to_string(i).unwrap();
+
+    store.create_issue(&issue("bd-a", "A"))?;
+    store.create_issue(&issue("bd-b", "B"))?;
+    store.export_to_jsonl(&jsonl, &fs)?;
+    assert!(auto_import(&mut store, &jsonl, &fs)?.is_none());
+
+    // A pull edits both issues and adds one, while bd-a has an unflushed edit
+    store.update_issue(&issue("bd-a", "A, local"))?;
+    let pulled = [
+        issue("bd-a", "A, remote"),
+        issue("bd-b", "B, remote"),
+        issue("bd-c", "C"),
+    ];
+    fs::write(
+        &jsonl,
+        pulled.iter().map(line).collect::<Vec<_>>().join("\n"),
+    )?;
+    let imported = auto_import(&mut store, &jsonl, &fs)?.expect("file changed");
+    assert_eq!((imported.stats.created, imported.stats.updated), (1, 1));
+    assert_eq!(imported.protected, ["bd-a"]);
+    assert_eq!(store.get_issue("bd-a")?.unwrap().title, "A, local");
+    assert_eq!(store.get_issue("bd-b")?.unwrap().title, "B, remote");
+    assert!(store.get_issue("bd-c")?.is_some());
+
+    // Nothing to do until the file changes again, even if only its mtime does
+    assert!(auto_import(&mut store, &jsonl, &fs)?.is_none());
+    fs::write(&jsonl, fs::read(&jsonl)?)?;
+    assert!(auto_import(&mut store, &jsonl, &fs)?.is_none());
+
+    // Conflicted files are refused
+    fs::write(
+        &jsonl,
+        format!(
+            "<<<<<<< HEAD\n{}\n=======\n>>>>>>> theirs\n",
+            line(&pulled[2])
+        ),
+    )?;
+    let err = auto_import(&mut store, &jsonl, &fs).unwrap_err();
+    assert!(err.to_string().contains("merge conflict"));
+
+    Ok(())
+}
+
+#[test]
+fn test_adaptive_id_length() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let hash_len = |id: String| id.strip_prefix("bd-").unwrap().len();
+
+    // A small database gets the shortest IDs
+    let id = store.generate_unique_id("bd", "First", "", "alice")?;
+    assert_eq!(hash_len(id.clone()), 3);
+
+    // Taken IDs are skipped, and child IDs do not count towards the size
+    store.create_issue(&Issue {
+        id: id.clone(),
+        ..Default::default()
+    })?;
+    store.create_issue(&Issue {
+        id: format!("{}.1", id),
+        ..Default::default()
+    })?;
+    let next = store.generate_unique_id("bd", "First", "", "alice")?;
+    assert_ne!(next, id);
+    assert_eq!(hash_len(next), 3);
+
+    store.set_config("min_hash_length", "5")?;
+    assert_eq!(
+        hash_len(store.generate_unique_id("bd", "T", "", "alice")?),
+        5
+    );
+
+    // A strict threshold lengthens IDs; unparsable values are ignored
+    store.set_config("min_hash_length", "short")?;
+    store.set_config("max_collision_prob", "0.000000001")?;
+    assert_eq!(
+        hash_len(store.generate_unique_id("bd", "T", "", "alice")?),
+        6
+    );
+    store.set_config("max_hash_length", "4")?;
+    assert_eq!(
+        hash_len(store.generate_unique_id("bd", "T", "", "alice")?),
+        4
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_child_ids_and_epic_status() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let new_issue = |id: &str, issue_type: &str, status: &str, parent: Option<&str>| Issue {
+        id: id.to_string(),
+        title: id.to_string(),
+        issue_type: issue_type.to_string(),
+        status: status.to_string(),
+        dependencies: parent
+            .map(|p| Dependency {
+                issue_id: id.to_string(),
+                depends_on_id: p.to_string(),
+                type_: "parent-child".to_string(),
+                created_at: Utc::now(),
+                created_by: "test".to_string(),
+            })
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    };
+
+    assert!(store.next_child_id("bd-epic").is_err());
+    store.create_issue(&new_issue("bd-epic", "epic", "open", None))?;
+    assert_eq!(store.next_child_id("bd-epic")?, "bd-epic.1");
+    // Children that already exist (e.g. imported) are skipped
+    store.create_issue(&new_issue("bd-epic.1", "task", "closed", Some("bd-epic")))?;
+    store.create_issue(&new_issue("bd-epic.5", "task", "open", Some("bd-epic")))?;
+    assert_eq!(store.next_child_id("bd-epic")?, "bd-epic.6");
+    assert_eq!(store.next_child_id("bd-epic")?, "bd-epic.7");
+
+    store.create_issue(&new_issue("bd-epic.1.1", "task", "open", None))?;
+    store.create_issue(&new_issue("bd-epic.1.1.1", "task", "open", None))?;
+    assert_eq!(store.next_child_id("bd-epic.1.1")?, "bd-epic.1.1.2");
+    let err = store.next_child_id("bd-epic.1.1.1").unwrap_err();
+    assert!(err.to_string().contains("maximum hierarchy depth"));
+
+    store.create_issue(&Issue {
+        priority: 3,
+        ..new_issue("bd-empty", "epic", "open", None)
+    })?;
+    store.create_issue(&new_issue("bd-done", "epic", "closed", None))?;
+    let status = store.epic_status()?;
+    let ids: Vec<&str> = status.iter().map(|s| s.epic.id.as_str()).collect();
+    assert_eq!(ids, ["bd-epic", "bd-empty"]);
+    assert_eq!(
+        (status[0].closed_children, status[0].total_children),
+        (1, 2)
+    );
+    assert_eq!(status[0].percent_complete(), 50);
+    assert!(!status[0].eligible_for_close);
+    assert!(!status[1].eligible_for_close);
+
+    // Deleted children no longer count
+    store.delete_issue("bd-epic.5", "test", "dropped")?;
+    let status = store.epic_status()?;
+    assert_eq!(
+        (status[0].closed_children, status[0].total_children),
+        (1, 1)
+    );
+    assert!(status[0].eligible_for_close);
+
+    Ok(())
+}
+
+#[test]
+fn test_rename_prefix() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let dep = |from: &str, to: &str, type_: &str| Dependency {
+        issue_id: from.to_string(),
+        depends_on_id: to.to_string(),
+        type_: type_.to_string(),
+        created_at: Utc::now(),
+        created_by: "test".to_string(),
+    };
+
+    store.create_issue(&Issue {
+        id: "bd-a".to_string(),
+        title: "Parent".to_string(),
+        description: "Split into bd-a.1; see also bd-b.".to_string(),
+        labels: vec!["area".to_string()],
+        ..Default::default()
+    })?;
+    store.create_issue(&Issue {
+        id: "bd-a.1".to_string(),
+        dependencies: vec![dep("bd-a.1", "bd-a", "parent-child")],
+        ..Default::default()
+    })?;
+    assert_eq!(store.next_child_id("bd-a")?, "bd-a.2");
+    store.create_issue(&Issue {
+        id: "bd-b".to_string(),
+        replies_to: "bd-a".to_string(),
+        relates_to: vec!["bd-a.1".to_string()],
+        duplicate_of: "bd-a".to_string(),
+        superseded_by: "bd-a.1".to_string(),
+        dependencies: vec![dep("bd-b", "bd-a", "blocks")],
+        ..Default::default()
+    })?;
+    store.add_comment("bd-b", "alice", "Waiting on bd-a (not bd-ab)")?;
+    store.create_issue(&Issue {
+        id: "ext-1".to_string(),
+        notes: "Tracks bd-b".to_string(),
+        ..Default::default()
+    })?;
+
+    assert!(store.rename_prefix("bd", "Bad!", false).is_err());
+    assert!(store.rename_prefix("bd", "bd-", false).is_err());
+
+    let moves = store.rename_prefix("bd", "kw-", true)?;
+    assert_eq!(
+        moves,
+        [("bd-a", "kw-a"), ("bd-a.1", "kw-a.1"), ("bd-b", "kw-b")]
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+    );
+    assert!(store.get_issue("bd-a")?.is_some());
+    assert_eq!(store.get_config("issue_id_prefix")?, None);
+
+    assert_eq!(store.rename_prefix("bd", "kw", false)?.len(), 3);
+    assert_eq!(store.get_config("issue_id_prefix")?.as_deref(), Some("kw"));
+    assert!(store.get_issue("bd-a")?.is_none());
+
+    let a = store.get_issue("kw-a")?.unwrap();
+    assert_eq!(a.description, "Split into kw-a.1; see also kw-b.");
+    assert_eq!(a.labels, ["area"]);
+    let b = store.get_issue("kw-b")?.unwrap();
+    assert_eq!(b.replies_to, "kw-a");
+    assert_eq!(b.relates_to, ["kw-a.1"]);
+    assert_eq!(
+        (b.duplicate_of.as_str(), b.superseded_by.as_str()),
+        ("kw-a", "kw-a.1")
+    );
+    assert_eq!(b.dependencies[0].depends_on_id, "kw-a");
+    assert_eq!(b.comments[0].text, "Waiting on kw-a (not bd-ab)");
+    assert_eq!(store.get_issue("ext-1")?.unwrap().notes, "Tracks kw-b");
+    assert_eq!(store.epic_status()?.len(), 0);
+    assert_eq!(
+        store
+            .dependency_graph()?
+            .children_of("kw-a")
+            .collect::<Vec<_>>(),
+        ["kw-a.1"]
+    );
+    assert_eq!(store.next_child_id("kw-a")?, "kw-a.3");
+    assert!(store
+        .dirty_issue_ids()?
+        .iter()
+        .all(|id| !id.starts_with("bd-")));
+
+    // Renaming onto IDs that already exist is refused and changes nothing
+    store.create_issue(&Issue {
+        id: "zz-a".to_string(),
+        ..Default::default()
+    })?;
+    let err = store.rename_prefix("kw", "zz", false).unwrap_err();
+    assert!(err.to_string().contains("already taken"));
+    assert!(store.get_issue("kw-b")?.is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_compaction() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let now = Utc::now();
+    let closed = |id: &str, days_ago: i64| Issue {
+        id: id.to_string(),
+        title: format!("Closed {}", id),
+        description: format!("Why {} happened.\n\n{}", id, "Long analysis. ".repeat(20)),
+        design: "A design that no longer matters.".to_string(),
+        notes: "Scratch notes.".to_string(),
+        status: "closed".to_string(),
+        created_at: now - chrono::Duration::days(days_ago + 1),
+        updated_at: now - chrono::Duration::days(days_ago),
+        closed_at: Some(now - chrono::Duration::days(days_ago)),
+        ..Default::default()
+    };
+
+    store.create_issue(&closed("bd-old", 40))?;
+    store.add_comment("bd-old", "alice", "Fixed in the parser.\nDetails follow.")?;
+    store.create_issue(&closed("bd-recent", 5))?;
+    store.create_issue(&closed("bd-needed", 40))?;
+    store.create_issue(&Issue {
+        id: "bd-open".to_string(),
+        status: "open".to_string(),
+        dependencies: vec![Dependency {
+            issue_id: "bd-open".to_string(),
+            depends_on_id: "bd-needed".to_string(),
+            type_: "blocks".to_string(),
+            created_at: now,
+            created_by: "test".to_string(),
+        }],
+        ..Default::default()
+    })?;
+
+    let config = CompactionConfig::default();
+    let ids = |issues: Vec<Issue>| issues.into_iter().map(|i| i.id).collect::<Vec<_>>();
+    assert_eq!(
+        ids(compact::candidates(&store, 1, &config, now)?),
+        ["bd-old"]
+    );
+    assert!(compact::candidates(&store, 2, &config, now)?.is_empty());
+    let needed = store.get_issue("bd-needed")?.unwrap();
+    let reason = compact::check_eligibility(&store, &needed, 1, &config, now, false)?;
+    assert!(reason.unwrap().contains("bd-open"));
+
+    let result =
+        compact::compact_issue(&store, "bd-old", 1, &BuiltinSummarizer, Some("abc123"), now)?;
+    assert!(result.size_after < result.size_before);
+    let old = store.get_issue("bd-old")?.unwrap();
+    assert_eq!(
+        old.description,
+        "Why bd-old happened.\n\nResolution: Fixed in the parser."
+    );
+    assert!(old.design.is_empty() && old.notes.is_empty() && old.comments.is_empty());
+    assert_eq!(old.compaction_level, 1);
+    assert_eq!(old.compacted_at_commit.as_deref(), Some("abc123"));
+    assert_eq!(old.original_size as usize, result.size_before);
+    assert!(store.dirty_issue_ids()?.contains(&"bd-old".to_string()));
+    assert!(compact::candidates(&store, 1, &config, now)?.is_empty());
+
+    // Tier 2 keeps the commit and size of the first compaction
+    let reason = compact::check_eligibility(&store, &old, 2, &config, now, false)?;
+    assert!(reason.unwrap().contains("less than 90 days"));
+    assert_eq!(
+        compact::check_eligibility(&store, &old, 2, &config, now, true)?,
+        None
+    );
+    compact::compact_issue(&store, "bd-old", 2, &BuiltinSummarizer, Some("def456"), now)?;
+    let old = store.get_issue("bd-old")?.unwrap();
+    assert_eq!(old.description, "Why bd-old happened.");
+    assert_eq!(old.compaction_level, 2);
+    assert_eq!(old.compacted_at_commit.as_deref(), Some("abc123"));
+    assert_eq!(old.original_size as usize, result.size_before);
+
+    // Open issues are never compacted, even with force
+    let open = store.get_issue("bd-open")?.unwrap();
+    assert!(compact::check_eligibility(&store, &open, 1, &config, now, true)?.is_some());
+    Ok(())
+}
+
+#[test]
+fn test_create_issues_from_template() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let epic = beads_core::template::builtin("epic").unwrap();
+
+    let issues = epic.instantiate("bd-e1", "Search", "alice", Utc::now())?;
+    store.create_issues(&issues)?;
+    assert_eq!(store.get_issue("bd-e1")?.unwrap().labels, ["epic"]);
+    let child = store.get_issue("bd-e1.2")?.unwrap();
+    assert_eq!(child.title, "Implement: Search");
+    assert_eq!(child.dependencies[0].depends_on_id, "bd-e1");
+    assert_eq!(store.epic_status()?[0].total_children, 3);
+    assert_eq!(store.next_child_id("bd-e1")?, "bd-e1.4");
+
+    // A clash anywhere in the set creates none of it
+    let mut clash = epic.instantiate("bd-e2", "Export", "alice", Utc::now())?;
+    clash[2].id = "bd-e1".to_string();
+    assert!(store.create_issues(&clash).is_err());
+    assert!(store.get_issue("bd-e2")?.is_none());
+    assert!(store.get_issue("bd-e2.1")?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_mail() -> anyhow::Result<()> {
+    use beads_core::mail;
+
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    let now = Utc::now();
+    store.create_issue(&Issue {
+        id: "bd-task".to_string(),
+        title: "Task".to_string(),
+        status: "open".to_string(),
+        ..Default::default()
+    })?;
+    let question = mail::new_message("bd-m1", "bob", "alice", "Deploy?", "Ready?", 2, now);
+    store.create_issue(&question)?;
+    store.create_issue(&mail::new_message(
+        "bd-m2",
+        "bob",
+        "carol",
+        "Outage",
+        "Help",
+        mail::PRIORITY_URGENT,
+        now,
+    ))?;
+
+    store.create_issue(&Issue {
+        status: "blocked".to_string(),
+        ..mail::new_message("bd-m9", "dave", "bob", "Stuck", "", 2, now)
+    })?;
+
+    // Messages are not work
+    let ready: Vec<String> = store.ready_issues()?.into_iter().map(|i| i.id).collect();
+    assert_eq!(ready, ["bd-task"]);
+    assert!(store.blocked_issues()?.is_empty());
+    let filter = IssueFilter {
+        exclude_types: vec![beads_core::TYPE_MESSAGE.to_string()],
+        ..Default::default()
+    };
+    assert_eq!(store.list_issues(&filter)?.len(), 1);
+
+    let inbox: Vec<String> = mail::inbox(&store, "bob")?
+        .into_iter()
+        .map(|i| i.id)
+        .collect();
+    assert_eq!(inbox, ["bd-m2", "bd-m1"]);
+    assert!(mail::inbox(&store, "alice")?.is_empty());
+    assert!(mail::get_message(&store, "bd-task").is_err());
+
+    let reply = mail::new_reply("bd-m3", &question, "bob", "Yes", 2, now)?;
+    store.create_issue(&reply)?;
+    assert_eq!(mail::inbox(&store, "alice")?[0].replies_to, "bd-m1");
+
+    mail::acknowledge(&store, "bd-m1", now)?;
+    assert!(mail::acknowledge(&store, "bd-m1", now).is_err());
+    assert_eq!(mail::inbox(&store, "bob")?.len(), 1);
+
+    // Only acknowledged messages are cleaned up
+    assert_eq!(mail::cleanup(&store, "bob", true)?, ["bd-m1"]);
+    assert_eq!(store.get_issue("bd-m1")?.unwrap().status, "closed");
+    assert_eq!(mail::cleanup(&store, "bob", false)?, ["bd-m1"]);
+    assert_eq!(store.get_issue("bd-m1")?.unwrap().status, "tombstone");
+    assert!(mail::cleanup(&store, "bob", false)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_issue_links() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = SqliteStore::open(dir.path().join("beads.db"))?;
+    for (id, title) in [
+        ("bd-a", "Login"),
+        ("bd-b", "Sessions"),
+        ("bd-c", "Login again"),
+    ] {
+        store.create_issue(&Issue {
+            id: id.to_string(),
+            title: title.to_string(),
+            status: "open".to_string(),
+            ..Default::default()
+        })?;
+    }
+
+    store.relate_issues("bd-a", "bd-b")?;
+    store.relate_issues("bd-b", "bd-a")?;
+    assert_eq!(store.get_issue("bd-a")?.unwrap().relates_to, ["bd-b"]);
+    assert_eq!(store.get_issue("bd-b")?.unwrap().relates_to, ["bd-a"]);
+    assert!(store.relate_issues("bd-a", "bd-a").is_err());
+    assert!(store.relate_issues("bd-a", "bd-zzz").is_err());
+
+    store.mark_duplicate("bd-c", "bd-a")?;
+    let c = store.get_issue("bd-c")?.unwrap();
+    assert_eq!(c.status, "closed");
+    assert!(c.closed_at.is_some());
+    assert_eq!(c.duplicate_of, "bd-a");
+    assert!(store.mark_duplicate("bd-b", "bd-zzz").is_err());
+    assert_eq!(store.get_issue("bd-b")?.unwrap().status, "open");
+
+    store.mark_superseded("bd-a", "bd-b")?;
+    let links = store.issue_links("bd-a")?;
+    let ids = |summaries: &[beads_core::IssueSummary]| -> Vec<String> {
+        summaries.iter().map(|s| s.id.clone()).collect()
+    };
+    assert_eq!(ids(&links.related), ["bd-b"]);
+    assert_eq!(ids(&links.duplicates), ["bd-c"]);
+    assert_eq!(links.superseded_by.unwrap().title, "Sessions");
+    assert!(links.duplicate_of.is_none());
+    assert_eq!(ids(&store.issue_links("bd-b")?.supersedes), ["bd-a"]);
+    assert_eq!(store.issue_links("bd-c")?.duplicate_of.unwrap().id, "bd-a");
+    Ok(())
+}